@@ -3,12 +3,17 @@
 //! Responsibilities:
 //! - Load background music and sound effects from assets (with fallbacks)
 //! - Provide helpers to play one-shot and looped sounds with optional volume scaling
-//! - Maintain separate sinks for SFX categories (steps, loops, general)
+//! - Maintain separate sinks for SFX categories (steps, loops, radar, general)
+//! - Reopen the output device after it was missing or went away (`ReinitBackoff`, `reopen`)
 //!
 use std::{fs::File, io::Read, io::BufReader, time::{Instant, Duration}, sync::Arc};
 use rodio::{OutputStream, OutputStreamHandle, Sink, Decoder};
 use rodio::Source;
+use rodio::source::{ChannelVolume, SineWave, Zero};
+use rodio::cpal::traits::HostTrait;
 use std::io::Cursor;
+use crate::core::assets::{AssetReport, AssetSource};
+use crate::core::tiles::Surface;
 
 fn load_bytes(path: &str) -> Option<Vec<u8>> {
     let mut f = File::open(path).ok()?;
@@ -18,32 +23,140 @@ fn load_bytes(path: &str) -> Option<Vec<u8>> {
 }
 
 fn load_bytes_any(paths: &[&str]) -> Option<Vec<u8>> {
+    find_bytes(paths).map(|(_, b)| b)
+}
+
+// First readable path of `paths` together with its bytes
+fn find_bytes<'a>(paths: &[&'a str]) -> Option<(&'a str, Vec<u8>)> {
     for p in paths {
-        if let Some(b) = load_bytes(p) { return Some(b); }
+        if let Some(b) = load_bytes(p) { return Some((p, b)); }
     }
     None
 }
 
+// Load the first readable candidate and record where it came from (or that it's missing)
+fn load_reported(report: &mut AssetReport, name: &str, paths: &[&str]) -> Option<Arc<Vec<u8>>> {
+    match find_bytes(paths) {
+        Some((p, b)) => { report.push(name, AssetSource::Disk(p.to_string())); Some(Arc::new(b)) }
+        None => { report.push(name, AssetSource::Missing); None }
+    }
+}
+
+/// First wait (s) before trying to reopen a missing audio device.
+pub const REINIT_FIRST_SECS: f32 = 2.0;
+/// Longest wait (s) between reopen attempts.
+pub const REINIT_MAX_SECS: f32 = 30.0;
+
+/// Retry schedule for reopening the audio device: the wait doubles after every failed
+/// attempt, capped at `REINIT_MAX_SECS`, and starts over once a device is back.
+#[derive(Clone, Debug)]
+pub struct ReinitBackoff {
+    wait: f32,
+    left: f32,
+}
+
+impl Default for ReinitBackoff {
+    fn default() -> Self { Self::new() }
+}
+
+impl ReinitBackoff {
+    pub fn new() -> Self { Self { wait: REINIT_FIRST_SECS, left: REINIT_FIRST_SECS } }
+
+    /// Advance by `dt`; true once an attempt is due (and until `failed` or `reset` is called).
+    pub fn tick(&mut self, dt: f32) -> bool {
+        self.left -= dt.max(0.0);
+        self.left <= 0.0
+    }
+
+    /// The attempt failed: wait twice as long before the next one.
+    pub fn failed(&mut self) {
+        self.wait = (self.wait * 2.0).min(REINIT_MAX_SECS);
+        self.left = self.wait;
+    }
+
+    pub fn reset(&mut self) { *self = Self::new(); }
+
+    /// Current wait between attempts.
+    pub fn wait(&self) -> f32 { self.wait }
+}
+
+/// Stop the background loop in `sink` unless it already plays `want` (its file being
+/// `current`); true when a new loop has to be started.
+fn release_bg_for(sink: &mut Option<Sink>, current: &Option<String>, want: &Option<String>) -> bool {
+    if sink.is_some() && current == want { return false; }
+    if let Some(s) = sink.take() { s.stop(); }
+    true
+}
+
 pub struct AudioManager {
     _stream: OutputStream,
     handle: OutputStreamHandle,
     bg_sink: Option<Sink>,
+    bg_path: Option<String>,
     sfx_sink: Sink,
     foot_sink: Sink,
+    radar_sink: Sink,
+    heart_sink: Sink,
     orb: Option<Arc<Vec<u8>>>,
     enemy_seen: Option<Arc<Vec<u8>>>,
     player_alert: Option<Arc<Vec<u8>>>,
     player_caught: Option<Arc<Vec<u8>>>,
-    player_step: Option<Arc<Vec<u8>>>,
+    player_steps: StepSamples,
     enemy_step: Option<Arc<Vec<u8>>>,
+    enemy_patrol: Option<Arc<Vec<u8>>>,
     seen_loop_sink: Option<Sink>,
     player_alert_loop_sink: Option<Sink>,
+    patrol_loop_sink: Option<Sink>,
     last_player_step: Instant,
     last_enemy_step: Instant,
     step_interval_player_walk: Duration,
     step_interval_player_sprint: Duration,
     step_interval_enemy: Duration,
     orb_volume: f32,
+    radar_volume: f32,
+    orbs: OrbBatch,
+}
+
+/// Orb pickups waiting to be played as one sound.
+struct OrbBatch {
+    pending: u32,
+    window_start: Option<Instant>,
+    window: Duration,
+}
+
+impl OrbBatch {
+    fn new(window: Duration) -> Self { Self { pending: 0, window_start: None, window } }
+    fn push(&mut self, now: Instant) {
+        if self.pending == 0 { self.window_start = Some(now); }
+        self.pending += 1;
+    }
+    /// Pickup count once the window opened by the first one has passed.
+    fn take_due(&mut self, now: Instant) -> Option<u32> {
+        let start = self.window_start?;
+        if now.duration_since(start) < self.window { return None; }
+        self.window_start = None;
+        Some(std::mem::take(&mut self.pending))
+    }
+}
+
+/// Player footstep samples, one set per floor surface.
+#[derive(Default)]
+struct StepSamples {
+    default: Option<Arc<Vec<u8>>>,
+    water: Option<Arc<Vec<u8>>>,
+    metal: Option<Arc<Vec<u8>>>,
+}
+
+impl StepSamples {
+    /// Sample for `surface`; surfaces without their own sample use the default steps.
+    fn for_surface(&self, surface: Surface) -> Option<Arc<Vec<u8>>> {
+        let own = match surface {
+            Surface::Default => None,
+            Surface::Water => self.water.as_ref(),
+            Surface::Metal => self.metal.as_ref(),
+        };
+        own.or(self.default.as_ref()).cloned()
+    }
 }
 
 impl AudioManager {
@@ -51,92 +164,136 @@ impl AudioManager {
         let (_stream, handle) = OutputStream::try_default().ok()?;
         let sfx_sink = Sink::try_new(&handle).ok()?;
         let foot_sink = Sink::try_new(&handle).ok()?;
+        let radar_sink = Sink::try_new(&handle).ok()?;
+        let heart_sink = Sink::try_new(&handle).ok()?;
         Some(Self {
             _stream,
             handle,
             bg_sink: None,
+            bg_path: None,
             sfx_sink,
             foot_sink,
+            radar_sink,
+            heart_sink,
             orb: None,
             enemy_seen: None,
             player_alert: None,
             player_caught: None,
-            player_step: None,
+            player_steps: StepSamples::default(),
             enemy_step: None,
+            enemy_patrol: None,
             seen_loop_sink: None,
             player_alert_loop_sink: None,
+            patrol_loop_sink: None,
             last_player_step: Instant::now(),
             last_enemy_step: Instant::now(),
             step_interval_player_walk: Duration::from_millis(260),
             step_interval_player_sprint: Duration::from_millis(170),
             step_interval_enemy: Duration::from_millis(320),
             orb_volume: 0.65,
+            radar_volume: 0.35,
+            orbs: OrbBatch::new(Duration::from_millis(30)),
         })
     }
 
+    /// Open the default device again and reload SFX and music (`music` as in `switch_music`).
+    pub fn reopen(music: Option<&str>) -> Option<Self> {
+        let mut a = Self::new()?;
+        a.load_sfx_auto();
+        a.switch_music(music);
+        Some(a)
+    }
+
+    /// True while the system still reports a default output device.
+    pub fn device_present() -> bool {
+        rodio::cpal::default_host().default_output_device().is_some()
+    }
+
     pub fn load_sfx(&mut self, orb: &str, enemy_seen: &str, player_step: &str, enemy_step: &str) {
         self.orb = load_bytes(orb).map(Arc::new);
         self.enemy_seen = load_bytes(enemy_seen).map(Arc::new);
-        self.player_step = load_bytes(player_step).map(Arc::new);
+        self.player_steps.default = load_bytes(player_step).map(Arc::new);
         self.enemy_step = load_bytes(enemy_step).map(Arc::new);
     }
 
-    pub fn load_sfx_auto(&mut self) {
-        self.orb = load_bytes_any(&[
+    /// Load every SFX from the first existing candidate path; returns what was found.
+    pub fn load_sfx_auto(&mut self) -> AssetReport {
+        let mut report = AssetReport::new();
+        self.orb = load_reported(&mut report, "sfx orb", &[
             "assets/sfx_orb.wav",
             "assets/sounds/orb.wav",
             "assets/sounds/puffle.wav",
             "assets/sounds/key.wav",
-        ]).map(Arc::new);
-        self.enemy_seen = load_bytes_any(&[
+        ]);
+        self.enemy_seen = load_reported(&mut report, "sfx enemy_seen", &[
             "assets/sfx_enemy_seen.wav",
             "assets/sounds/enemy_alert.wav",
             "assets/sounds/enemy_seen.wav",
             "assets/sounds/alert.wav",
-        ]).map(Arc::new);
-        self.player_alert = load_bytes_any(&[
+        ]);
+        self.player_alert = load_reported(&mut report, "sfx player_alert", &[
             "assets/sfx_player_alert.wav",
             "assets/sounds/player_alert.wav",
             "assets/sounds/alert_player.wav",
-        ]).map(Arc::new);
-        self.player_step = load_bytes_any(&[
+        ]);
+        self.player_steps.default = load_reported(&mut report, "sfx player_step", &[
             "assets/sfx_player_step.wav",
             "assets/sounds/foot.wav",
             "assets/sounds/step.wav",
             "assets/sounds/footstep.wav",
-        ]).map(Arc::new);
-        self.enemy_step = load_bytes_any(&[
+        ]);
+        self.player_steps.water = load_reported(&mut report, "sfx player_step_water", &[
+            "assets/sfx_player_step_water.wav",
+            "assets/sounds/step_water.wav",
+            "assets/sounds/splash.wav",
+        ]);
+        self.player_steps.metal = load_reported(&mut report, "sfx player_step_metal", &[
+            "assets/sfx_player_step_metal.wav",
+            "assets/sounds/step_metal.wav",
+        ]);
+        self.enemy_step = load_reported(&mut report, "sfx enemy_step", &[
             "assets/sfx_enemy_step.wav",
             "assets/sounds/enemy_foot.wav",
             "assets/sounds/enemy_step.wav",
-        ]).map(Arc::new);
-        self.player_caught = load_bytes_any(&[
+        ]);
+        self.enemy_patrol = load_reported(&mut report, "sfx enemy_patrol", &[
+            "assets/sfx_enemy_patrol.wav",
+            "assets/sounds/enemy_breath.wav",
+            "assets/sounds/breathing.wav",
+        ]);
+        self.player_caught = load_reported(&mut report, "sfx player_caught", &[
             "assets/sfx_player_caught.wav",
             "assets/sounds/caught.wav",
             "assets/sounds/caught.mp3",
-        ]).map(Arc::new);
+        ]);
+        report
     }
 
-    pub fn play_orb(&self) {
+    /// Queue an orb pickup; pickups within the coalesce window are flushed by `update`
+    /// as one sound, a bit louder and higher-pitched per extra orb.
+    pub fn play_orb(&mut self) { self.orbs.push(Instant::now()); }
+    fn flush_orbs(&mut self, n: u32) {
+        let extra = (n - 1).min(6) as f32;
         if let Some(d) = self.orb.clone() {
             if let Ok(dec) = Decoder::new(BufReader::new(Cursor::new(d.as_ref().clone()))) {
                 if let Ok(sink) = Sink::try_new(&self.handle) {
-                    sink.append(dec.amplify(self.orb_volume.clamp(0.0, 2.5)));
+                    let vol = (self.orb_volume * (1.0 + 0.12 * extra)).clamp(0.0, 2.5);
+                    sink.append(dec.speed(1.0 + 0.05 * extra).amplify(vol));
                     sink.detach();
                 }
             }
         }
     }
     pub fn play_enemy_seen(&self) { self.play_data(self.enemy_seen.clone()); }
-    pub fn play_player_step(&mut self, sprinting: bool) {
+    pub fn play_player_step(&mut self, surface: Surface, sprinting: bool) {
         let interval = if sprinting { self.step_interval_player_sprint } else { self.step_interval_player_walk };
         if self.last_player_step.elapsed() >= interval {
             self.last_player_step = Instant::now();
-            self.play_data_on_foot(self.player_step.clone());
+            self.play_data_on_foot(self.player_steps.for_surface(surface));
         }
     }
-    pub fn force_player_step(&mut self) {
-        self.play_data_on_foot(self.player_step.clone());
+    pub fn force_player_step(&mut self, surface: Surface) {
+        self.play_data_on_foot(self.player_steps.for_surface(surface));
         self.last_player_step = Instant::now();
     }
     pub fn stop_player_steps(&mut self) {
@@ -172,6 +329,44 @@ impl AudioManager {
             }
         }
     }
+    /// Short synthesized blip on the radar sink. `pan` in -1..1 (left..right), `pitch` scales the tone.
+    pub fn play_radar_ping(&self, pan: f32, pitch: f32) {
+        let p = pan.clamp(-1.0, 1.0);
+        let (left, right) = ((1.0 - p) * 0.5, (1.0 + p) * 0.5);
+        let tone = SineWave::new(880.0 * pitch.clamp(0.25, 4.0))
+            .take_duration(Duration::from_millis(90))
+            .fade_in(Duration::from_millis(8))
+            .amplify(self.radar_volume);
+        self.radar_sink.append(ChannelVolume::new(tone, vec![left, right]));
+    }
+    /// Soft falling tone while the enemy calms down; `intensity` in 0..1 (fades as the cooldown ends).
+    pub fn play_calm_cue(&self, intensity: f32) {
+        let k = intensity.clamp(0.0, 1.0);
+        if k <= 0.0 { return; }
+        let tone = SineWave::new(330.0 + 110.0 * k)
+            .take_duration(Duration::from_millis(160))
+            .fade_in(Duration::from_millis(40))
+            .amplify(0.18 * k);
+        self.radar_sink.append(tone);
+    }
+    /// Low rumble played once when the enemy first appears in a level.
+    pub fn play_spawn_cue(&self) {
+        let rumble = SineWave::new(42.0)
+            .take_duration(Duration::from_millis(900))
+            .fade_in(Duration::from_millis(120))
+            .amplify(0.6);
+        self.radar_sink.append(rumble);
+    }
+    /// Synthesized "lub-dub" heartbeat; `intensity` in 0..1 scales loudness.
+    pub fn play_heartbeat(&self, intensity: f32) {
+        let k = intensity.clamp(0.0, 1.0);
+        if k <= 0.0 || !self.heart_sink.empty() { return; }
+        let thump = |freq: f32, ms: u64, vol: f32| SineWave::new(freq).take_duration(Duration::from_millis(ms)).fade_in(Duration::from_millis(10)).amplify(vol);
+        let vol = 0.25 + 0.75 * k;
+        self.heart_sink.append(thump(58.0, 110, vol));
+        self.heart_sink.append(Zero::<f32>::new(1, 48000).take_duration(Duration::from_millis(140)));
+        self.heart_sink.append(thump(50.0, 130, vol * 0.8));
+    }
     pub fn play_player_alert(&self) { self.play_data_with_volume(self.player_alert.clone(), 0.55); }
     fn play_data_on_foot(&self, data: Option<Arc<Vec<u8>>>) {
         if let Some(d) = data {
@@ -211,7 +406,17 @@ impl AudioManager {
             }
         }
     }
-    pub fn update(&self) { /* sinks auto-play */ }
+    /// Hard-swap the background loop to `path`; `None` (or an unreadable file) uses the auto list.
+    pub fn switch_music(&mut self, path: Option<&str>) {
+        let want = path.filter(|p| std::path::Path::new(p).is_file()).map(str::to_owned);
+        if !release_bg_for(&mut self.bg_sink, &self.bg_path, &want) { return; }
+        if let Some(p) = want.as_deref() { self.play_music_loop(p); }
+        if self.bg_sink.is_some() { self.bg_path = want; } else { self.bg_path = None; self.play_music_loop_auto(); }
+    }
+    /// Per-frame housekeeping: flush coalesced orb pickups once their window has passed.
+    pub fn update(&mut self) {
+        if let Some(n) = self.orbs.take_due(Instant::now()) { self.flush_orbs(n); }
+    }
     pub fn play_player_caught(&self) { self.play_data(self.player_caught.clone()); }
     pub fn start_enemy_seen_loop(&mut self) {
         if self.seen_loop_sink.is_some() { return; }
@@ -225,6 +430,33 @@ impl AudioManager {
             }
         }
     }
+    /// Pause (or resume) for an unfocused window: music is held, looping and step sounds stop.
+    pub fn set_paused(&mut self, paused: bool) {
+        if let Some(s) = self.bg_sink.as_ref() { if paused { s.pause(); } else { s.play(); } }
+        if paused {
+            self.stop_enemy_seen_loop();
+            self.stop_player_alert_loop();
+            self.stop_player_steps();
+            self.set_patrol_ambient(0.0);
+        }
+    }
+    /// Looping ambience of a patrolling enemy (breathing sample, or a low synthesized hum when
+    /// missing) at `volume`; 0 stops it. Call every frame with the current volume.
+    pub fn set_patrol_ambient(&mut self, volume: f32) {
+        if volume <= 0.0 {
+            if let Some(s) = self.patrol_loop_sink.take() { s.stop(); }
+            return;
+        }
+        if self.patrol_loop_sink.is_none() {
+            let Ok(sink) = Sink::try_new(&self.handle) else { return };
+            match self.enemy_patrol.as_ref().and_then(|b| Decoder::new_looped(Cursor::new(b.as_ref().clone())).ok()) {
+                Some(dec) => sink.append(dec),
+                None => sink.append(SineWave::new(58.0).amplify(0.5)),
+            }
+            self.patrol_loop_sink = Some(sink);
+        }
+        if let Some(s) = self.patrol_loop_sink.as_ref() { s.set_volume(volume.min(1.0)); }
+    }
     pub fn stop_enemy_seen_loop(&mut self) { if let Some(s) = self.seen_loop_sink.take() { s.stop(); } }
     pub fn start_player_alert_loop(&mut self, volume: f32) {
         if self.player_alert_loop_sink.is_some() { return; }
@@ -240,3 +472,120 @@ impl AudioManager {
     }
     pub fn stop_player_alert_loop(&mut self) { if let Some(s) = self.player_alert_loop_sink.take() { s.stop(); } }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn idle() -> Option<Sink> { Some(Sink::new_idle().0) }
+
+    #[test]
+    fn switching_to_another_track_releases_the_playing_loop() {
+        let (a, b) = (Some("a.wav".to_string()), Some("b.wav".to_string()));
+        let mut sink = idle();
+        assert!(release_bg_for(&mut sink, &a, &b));
+        assert!(sink.is_none());
+        // Back to the auto list from a chosen track
+        let mut sink = idle();
+        assert!(release_bg_for(&mut sink, &a, &None));
+        assert!(sink.is_none());
+    }
+
+    #[test]
+    fn same_track_keeps_the_playing_loop() {
+        let a = Some("a.wav".to_string());
+        let mut sink = idle();
+        assert!(!release_bg_for(&mut sink, &a, &a));
+        assert!(sink.is_some());
+        // Nothing playing yet: always start one
+        let mut none = None;
+        assert!(release_bg_for(&mut none, &a, &a));
+    }
+
+    #[test]
+    fn orb_pickups_inside_the_window_become_one_sound() {
+        let t0 = Instant::now();
+        let mut b = OrbBatch::new(Duration::from_millis(30));
+        for ms in [0, 5, 12] { b.push(t0 + Duration::from_millis(ms)); }
+        assert_eq!(b.take_due(t0 + Duration::from_millis(20)), None);
+        assert_eq!(b.take_due(t0 + Duration::from_millis(30)), Some(3));
+        assert_eq!(b.take_due(t0 + Duration::from_millis(90)), None);
+    }
+
+    #[test]
+    fn spaced_orb_pickups_play_separately() {
+        let t0 = Instant::now();
+        let mut b = OrbBatch::new(Duration::from_millis(30));
+        let mut sounds = Vec::new();
+        for ms in [0u64, 50, 100] {
+            let now = t0 + Duration::from_millis(ms);
+            b.push(now);
+            // One frame later, past the window
+            sounds.extend(b.take_due(now + Duration::from_millis(35)));
+        }
+        assert_eq!(sounds, vec![1, 1, 1]);
+    }
+
+    #[test]
+    fn report_lists_found_sounds_as_disk_and_absent_ones_as_missing() {
+        let path = std::env::temp_dir().join(format!("sfx_test_{}.wav", std::process::id()));
+        std::fs::write(&path, b"RIFF").unwrap();
+        let present = path.to_str().unwrap();
+        let mut report = AssetReport::new();
+        let found = load_reported(&mut report, "sfx orb", &["no/such/orb.wav", present]);
+        let lost = load_reported(&mut report, "sfx step", &["no/such/step.wav"]);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(found.as_deref().map(|b| b.as_slice()), Some(&b"RIFF"[..]));
+        assert!(lost.is_none());
+        assert_eq!(report.source("sfx orb"), Some(&AssetSource::Disk(present.to_string())));
+        assert!(report.is_loaded("sfx orb"));
+        assert_eq!(report.source("sfx step"), Some(&AssetSource::Missing));
+        assert!(!report.is_loaded("sfx step"));
+    }
+
+    #[test]
+    fn reinit_waits_double_after_each_failure_up_to_the_cap() {
+        let mut b = ReinitBackoff::new();
+        assert!(!b.tick(REINIT_FIRST_SECS - 0.5));
+        assert!(b.tick(0.5));
+        // Still due until the attempt is reported
+        assert!(b.tick(0.0));
+        let mut waits = Vec::new();
+        for _ in 0..6 {
+            b.failed();
+            waits.push(b.wait());
+            assert!(!b.tick(b.wait() - 0.5));
+            assert!(b.tick(0.5));
+        }
+        assert_eq!(waits, vec![4.0, 8.0, 16.0, REINIT_MAX_SECS, REINIT_MAX_SECS, REINIT_MAX_SECS]);
+    }
+
+    #[test]
+    fn reinit_schedule_starts_over_after_success() {
+        let mut b = ReinitBackoff::new();
+        for _ in 0..4 { b.failed(); }
+        b.reset();
+        assert_eq!(b.wait(), REINIT_FIRST_SECS);
+        assert!(!b.tick(1.0));
+        assert!(b.tick(1.0));
+        // Negative frame times don't rewind the clock
+        let mut b = ReinitBackoff::new();
+        b.tick(-5.0);
+        assert!(b.tick(REINIT_FIRST_SECS));
+    }
+
+    #[test]
+    fn footsteps_use_the_sample_set_of_the_tile_underfoot() {
+        use crate::core::tiles::{surface_at, METAL, WATER};
+        let maze: Vec<Vec<char>> = vec![vec![' ', WATER, METAL]];
+        let (plain, water) = (Arc::new(vec![1u8]), Arc::new(vec![2u8]));
+        let steps = StepSamples { default: Some(plain.clone()), water: Some(water.clone()), metal: None };
+        let at = |i: f32| steps.for_surface(surface_at(&maze, 64.0, (i + 0.5) * 64.0, 32.0));
+        assert_eq!(at(0.0), Some(plain.clone()));
+        assert_eq!(at(1.0), Some(water));
+        // Metal has no sample of its own: default steps; off the map too
+        assert_eq!(at(2.0), Some(plain.clone()));
+        assert_eq!(at(5.0), Some(plain));
+        assert_eq!(StepSamples::default().for_surface(Surface::Water), None);
+    }
+}
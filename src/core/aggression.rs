@@ -0,0 +1,87 @@
+//! Opt-in adaptive enemy: an aggression value carried from level to level and persisted.
+//!
+//! Clearing a level without being spotted lowers it (a slower enemy next time); being spotted
+//! raises it, more so the more often it happened. It scales the enemy's speed at level start.
+use std::fs;
+
+pub const AGGRESSION_FILE: &str = "aggression.txt";
+
+/// Aggression lost after a level cleared without being spotted.
+pub const MERCY_STEP: f32 = 0.25;
+/// Aggression gained per time spotted during a level.
+pub const SEEN_STEP: f32 = 0.1;
+/// Most sightings counted for one level.
+pub const SEEN_CAP: u32 = 4;
+/// Enemy speed change at full aggression (either way).
+pub const SPEED_SPAN: f32 = 0.15;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Aggression {
+    pub enabled: bool,
+    /// -1 (mercy) ..= 1 (hard); 0 is neutral.
+    pub level: f32,
+}
+
+impl Aggression {
+    /// Read `path`; a missing or malformed file means off and neutral.
+    pub fn load(path: &str) -> Self {
+        let mut a = Self::default();
+        let Ok(text) = fs::read_to_string(path) else { return a; };
+        for line in text.lines() {
+            let Some((k, v)) = line.split_once('=') else { continue; };
+            match k.trim() {
+                "enabled" => if let Ok(b) = v.trim().parse::<bool>() { a.enabled = b; },
+                "level" => if let Ok(x) = v.trim().parse::<f32>() { a.level = x.clamp(-1.0, 1.0); },
+                _ => {}
+            }
+        }
+        a
+    }
+
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        fs::write(path, format!("enabled = {}\nlevel = {}\n", self.enabled, self.level))
+    }
+
+    /// Carry a cleared level over: no sightings lowers aggression, sightings raise it.
+    pub fn record_level(&mut self, times_spotted: u32) {
+        let delta = if times_spotted == 0 { -MERCY_STEP } else { SEEN_STEP * times_spotted.min(SEEN_CAP) as f32 };
+        self.level = (self.level + delta).clamp(-1.0, 1.0);
+    }
+
+    /// Multiplier on the enemy's patrol and chase speed (1 while off).
+    pub fn speed_mul(&self) -> f32 {
+        if self.enabled { 1.0 + SPEED_SPAN * self.level } else { 1.0 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sightings_raise_aggression_and_clean_levels_lower_it() {
+        let mut a = Aggression { enabled: true, level: 0.0 };
+        a.record_level(3);
+        assert!((a.level - 0.3).abs() < 1e-6);
+        assert!(a.speed_mul() > 1.0);
+        a.record_level(0);
+        assert!((a.level - 0.05).abs() < 1e-6);
+        a.record_level(0);
+        assert!(a.level < 0.0 && a.speed_mul() < 1.0);
+        // Sightings past the cap count as the cap; the level stays within -1..=1
+        let mut b = Aggression { enabled: true, level: 0.0 };
+        b.record_level(50);
+        assert!((b.level - SEEN_STEP * SEEN_CAP as f32).abs() < 1e-6);
+        for _ in 0..10 { b.record_level(SEEN_CAP); }
+        assert_eq!(b.level, 1.0);
+        for _ in 0..20 { b.record_level(0); }
+        assert_eq!(b.level, -1.0);
+    }
+
+    #[test]
+    fn off_keeps_the_enemy_at_normal_speed() {
+        let a = Aggression { enabled: false, level: 1.0 };
+        assert_eq!(a.speed_mul(), 1.0);
+        assert_eq!(Aggression::load("definitely_missing_aggression.txt"), Aggression::default());
+    }
+}
@@ -0,0 +1,65 @@
+//! Asset-loading diagnostics: which textures/sounds came from disk and which fell back.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AssetSource {
+    /// Loaded from this file.
+    Disk(String),
+    /// Not found; a generated stand-in is used.
+    Procedural,
+    /// Not found and there is no stand-in (e.g. a silent sound).
+    Missing,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct AssetReport {
+    pub entries: Vec<(String, AssetSource)>,
+}
+
+impl AssetReport {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn push(&mut self, name: impl Into<String>, source: AssetSource) {
+        self.entries.push((name.into(), source));
+    }
+
+    pub fn source(&self, name: &str) -> Option<&AssetSource> {
+        self.entries.iter().find(|(n, _)| n == name).map(|(_, s)| s)
+    }
+
+    pub fn is_loaded(&self, name: &str) -> bool {
+        matches!(self.source(name), Some(AssetSource::Disk(_)))
+    }
+
+    /// One human-readable line per entry, e.g. `texture 'g': assets/goal.png`.
+    pub fn lines(&self) -> Vec<String> {
+        self.entries.iter().map(|(n, s)| match s {
+            AssetSource::Disk(p) => format!("{}: {}", n, p),
+            AssetSource::Procedural => format!("{}: fallback (procedural)", n),
+            AssetSource::Missing => format!("{}: missing", n),
+        }).collect()
+    }
+
+    pub fn eprint(&self) {
+        for l in self.lines() { eprintln!("[assets] {}", l); }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_say_where_each_asset_came_from() {
+        let mut r = AssetReport::new();
+        r.push("texture 'g'", AssetSource::Disk("assets/goal.png".into()));
+        r.push("texture 'o'", AssetSource::Procedural);
+        r.push("sfx orb", AssetSource::Missing);
+        assert_eq!(r.lines(), vec![
+            "texture 'g': assets/goal.png",
+            "texture 'o': fallback (procedural)",
+            "sfx orb: missing",
+        ]);
+        assert!(r.is_loaded("texture 'g'") && !r.is_loaded("texture 'o'"));
+        assert_eq!(r.source("texture 'x'"), None);
+    }
+}
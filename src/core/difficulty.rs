@@ -0,0 +1,106 @@
+//! Difficulty presets picked on the menu.
+//!
+//! A preset scales the per-level values (enemy speed and sight, spawn delay, battery drain)
+//! and sets the catch distance and the share of orbs needed to open the exit, for every level.
+//! `Normal` keeps the tuned per-level behavior unchanged.
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+/// Values a preset applies on top of the level config.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DifficultyCfg {
+    /// Multiplier on the enemy's patrol and chase speed.
+    pub enemy_speed: f32,
+    /// Multiplier on the enemy's sight range.
+    pub enemy_range: f32,
+    /// Distance (world units) at which the enemy catches the player.
+    pub catch_dist: f32,
+    /// Multiplier on the delay before the enemy appears.
+    pub spawn_delay: f32,
+    /// Multiplier on the flashlight battery drain.
+    pub battery_drain: f32,
+    /// Share of the level's orbs needed before the exit opens (0..1].
+    pub orb_fraction: f32,
+}
+
+impl Difficulty {
+    pub fn next(self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Easy,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    pub fn cfg(self) -> DifficultyCfg {
+        match self {
+            Difficulty::Easy => DifficultyCfg { enemy_speed: 0.85, enemy_range: 0.85, catch_dist: 22.0, spawn_delay: 1.5, battery_drain: 0.7, orb_fraction: 0.8 },
+            Difficulty::Normal => DifficultyCfg { enemy_speed: 1.0, enemy_range: 1.0, catch_dist: 26.0, spawn_delay: 1.0, battery_drain: 1.0, orb_fraction: 1.0 },
+            Difficulty::Hard => DifficultyCfg { enemy_speed: 1.15, enemy_range: 1.15, catch_dist: 30.0, spawn_delay: 0.6, battery_drain: 1.3, orb_fraction: 1.0 },
+        }
+    }
+}
+
+/// Orbs still to collect before the exit opens (0 once `collected` reaches `required`).
+pub fn orbs_to_unlock(required: usize, collected: usize) -> usize {
+    required.saturating_sub(collected)
+}
+
+impl DifficultyCfg {
+    /// Orbs to collect out of `total` before the exit opens (at least one if there are any).
+    pub fn required_orbs(&self, total: usize) -> usize {
+        ((total as f32 * self.orb_fraction.clamp(0.0, 1.0)).ceil() as usize).clamp(total.min(1), total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hard_is_faster_and_spawns_sooner_than_easy() {
+        let (easy, hard) = (Difficulty::Easy.cfg(), Difficulty::Hard.cfg());
+        assert!(hard.enemy_speed > easy.enemy_speed);
+        assert!(hard.spawn_delay < easy.spawn_delay);
+        assert!(hard.catch_dist > easy.catch_dist);
+    }
+
+    #[test]
+    fn normal_leaves_level_values_unchanged() {
+        assert_eq!(Difficulty::default(), Difficulty::Normal);
+        let n = Difficulty::Normal.cfg();
+        assert_eq!((n.enemy_speed, n.enemy_range, n.spawn_delay, n.battery_drain), (1.0, 1.0, 1.0, 1.0));
+        assert_eq!(n.required_orbs(7), 7);
+    }
+
+    #[test]
+    fn easy_needs_fewer_orbs_but_at_least_one() {
+        let easy = Difficulty::Easy.cfg();
+        assert_eq!(easy.required_orbs(10), 8);
+        assert_eq!(easy.required_orbs(1), 1);
+        assert_eq!(easy.required_orbs(0), 0);
+    }
+
+    #[test]
+    fn orbs_to_unlock_counts_down_to_zero() {
+        assert_eq!(orbs_to_unlock(8, 3), 5);
+        assert_eq!(orbs_to_unlock(8, 8), 0);
+        // Orbs collected past the requirement don't go negative
+        assert_eq!(orbs_to_unlock(8, 10), 0);
+        assert_eq!(orbs_to_unlock(0, 0), 0);
+    }
+}
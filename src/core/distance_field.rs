@@ -0,0 +1,175 @@
+//! Shared BFS distance field towards the player.
+//!
+//! One BFS from the player's cell gives every free cell its step distance; enemies then
+//! walk downhill (gradient descent) instead of running their own BFS each frame.
+use std::collections::VecDeque;
+use crate::core::maze::Maze;
+use crate::core::tiles::tiles;
+
+pub const UNREACHABLE: u32 = u32::MAX;
+
+pub struct DistanceField {
+    w: usize,
+    h: usize,
+    dist: Vec<u32>,
+    origin: Option<(usize, usize)>,
+}
+
+#[inline]
+fn passable(maze: &Maze, i: usize, j: usize) -> bool {
+    if j >= maze.len() || i >= maze[j].len() { return false; }
+    tiles().enemy_walkable(maze[j][i])
+}
+
+impl Default for DistanceField {
+    fn default() -> Self { Self::new() }
+}
+
+impl DistanceField {
+    pub fn new() -> Self {
+        Self { w: 0, h: 0, dist: Vec::new(), origin: None }
+    }
+
+    /// Rebuild from world position `(wx, wy)` only if it lies in a different cell than the
+    /// current origin (or the maze size changed). Returns true when a rebuild happened.
+    pub fn update(&mut self, maze: &Maze, block: usize, wx: f32, wy: f32) -> bool {
+        let i = (wx / block as f32).floor();
+        let j = (wy / block as f32).floor();
+        if i < 0.0 || j < 0.0 { return false; }
+        let cell = (i as usize, j as usize);
+        let h = maze.len();
+        let w = maze.first().map(|r| r.len()).unwrap_or(0);
+        if self.origin == Some(cell) && self.w == w && self.h == h { return false; }
+        self.build(maze, cell);
+        true
+    }
+
+    /// Full BFS from `origin` over enemy-walkable cells.
+    pub fn build(&mut self, maze: &Maze, origin: (usize, usize)) {
+        self.h = maze.len();
+        self.w = maze.first().map(|r| r.len()).unwrap_or(0);
+        self.dist.clear();
+        self.dist.resize(self.w * self.h, UNREACHABLE);
+        self.origin = Some(origin);
+        if !passable(maze, origin.0, origin.1) { return; }
+        let mut q = VecDeque::new();
+        self.dist[origin.1 * self.w + origin.0] = 0;
+        q.push_back(origin);
+        let dirs = [(1isize, 0isize), (-1, 0), (0, 1), (0, -1)];
+        while let Some((cx, cy)) = q.pop_front() {
+            let d = self.dist[cy * self.w + cx];
+            for (dx, dy) in dirs {
+                let nx = cx as isize + dx;
+                let ny = cy as isize + dy;
+                if nx < 0 || ny < 0 { continue; }
+                let (nx, ny) = (nx as usize, ny as usize);
+                if nx >= self.w || ny >= self.h || !passable(maze, nx, ny) { continue; }
+                let idx = ny * self.w + nx;
+                if self.dist[idx] != UNREACHABLE { continue; }
+                self.dist[idx] = d + 1;
+                q.push_back((nx, ny));
+            }
+        }
+    }
+
+    pub fn origin(&self) -> Option<(usize, usize)> { self.origin }
+
+    /// Step distance from cell `(i, j)` to the origin, or `UNREACHABLE`.
+    pub fn distance(&self, i: usize, j: usize) -> u32 {
+        if i >= self.w || j >= self.h { return UNREACHABLE; }
+        self.dist[j * self.w + i]
+    }
+
+    /// Neighbor of `(i, j)` with the lowest distance (one gradient-descent step).
+    /// `None` at the origin or when the cell can't reach it.
+    pub fn next_cell(&self, i: usize, j: usize) -> Option<(usize, usize)> {
+        let here = self.distance(i, j);
+        if here == 0 || here == UNREACHABLE { return None; }
+        let mut best: Option<((usize, usize), u32)> = None;
+        let dirs = [(1isize, 0isize), (-1, 0), (0, 1), (0, -1)];
+        for (dx, dy) in dirs {
+            let nx = i as isize + dx;
+            let ny = j as isize + dy;
+            if nx < 0 || ny < 0 { continue; }
+            let (nx, ny) = (nx as usize, ny as usize);
+            let d = self.distance(nx, ny);
+            if d < here && best.map(|b| d < b.1).unwrap_or(true) { best = Some(((nx, ny), d)); }
+        }
+        best.map(|b| b.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(rows: &[&str]) -> Maze {
+        rows.iter().map(|r| r.chars().collect()).collect()
+    }
+
+    // The bottom-right pocket is sealed off from the rest of the maze.
+    fn maze() -> Maze {
+        grid(&[
+            "+-------+",
+            "|   |   |",
+            "| | | | |",
+            "| |   | |",
+            "| +---+ |",
+            "|     |+|",
+            "+-----+ |",
+            "+-------+",
+        ])
+    }
+
+    #[test]
+    fn descending_the_field_reaches_the_origin_from_every_reachable_cell() {
+        let maze = maze();
+        let mut field = DistanceField::new();
+        field.build(&maze, (1, 1));
+        let mut checked = 0;
+        for (j, row) in maze.iter().enumerate() {
+            for i in 0..row.len() {
+                let start = field.distance(i, j);
+                if start == UNREACHABLE { continue; }
+                let (mut ci, mut cj) = (i, j);
+                let mut steps = 0;
+                while let Some((ni, nj)) = field.next_cell(ci, cj) {
+                    assert_eq!(field.distance(ni, nj) + 1, field.distance(ci, cj));
+                    (ci, cj) = (ni, nj);
+                    steps += 1;
+                }
+                assert_eq!((ci, cj), (1, 1), "descent from ({i}, {j}) stopped short");
+                assert_eq!(steps, start);
+                checked += 1;
+            }
+        }
+        assert!(checked > 20);
+    }
+
+    #[test]
+    fn walls_and_sealed_cells_stay_unreachable() {
+        let maze = maze();
+        let mut field = DistanceField::new();
+        field.build(&maze, (1, 1));
+        for (j, row) in maze.iter().enumerate() {
+            for (i, &c) in row.iter().enumerate() {
+                if !tiles().enemy_walkable(c) {
+                    assert_eq!(field.distance(i, j), UNREACHABLE, "wall at ({i}, {j})");
+                }
+            }
+        }
+        assert_eq!(field.distance(7, 6), UNREACHABLE);
+        assert_eq!(field.next_cell(7, 6), None);
+        assert_eq!(field.distance(99, 99), UNREACHABLE);
+    }
+
+    #[test]
+    fn update_rebuilds_only_when_the_cell_changes() {
+        let maze = maze();
+        let mut field = DistanceField::new();
+        assert!(field.update(&maze, 10, 15.0, 15.0));
+        assert!(!field.update(&maze, 10, 18.0, 12.0));
+        assert!(field.update(&maze, 10, 25.0, 15.0));
+        assert_eq!(field.origin(), Some((2, 1)));
+    }
+}
@@ -1,5 +1,8 @@
 //! Enemy AI and navigation.
 use crate::core::maze::Maze;
+use crate::core::tiles::tiles;
+use crate::core::distance_field::DistanceField;
+use crate::core::pathfinder::{PathScratch, DEFAULT_SEARCH_RADIUS};
 
 #[inline]
 fn normalize_angle(mut a: f32) -> f32 {
@@ -8,8 +11,27 @@ fn normalize_angle(mut a: f32) -> f32 {
     a
 }
 
+/// Default max turn speed (rad/s) while chasing or searching.
+pub const DEFAULT_TURN_RATE: f32 = 2.8;
+/// Chase memory drains this many times faster while the player hides in a safe room.
+pub const DEFAULT_SAFE_DECAY: f32 = 3.0;
+/// Seconds of patrol between detours towards the orb lure (see `set_orb_lure`).
+pub const LURE_INTERVAL_SECS: f32 = 8.0;
+/// A lure detour ends once the enemy is within this many cells of the lure point.
+const LURE_ARRIVE_CELLS: f32 = 1.0;
+/// Walk-cycle radians per world unit travelled (one full squash/stretch every ~40 units).
+const WALK_PHASE_PER_UNIT: f32 = std::f32::consts::TAU / 40.0;
+/// Peak squash/stretch of the sprite height while walking (0.06 = ±6%).
+pub const WALK_SQUASH_AMP: f32 = 0.06;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum EnemyState { Patrol, Chase, Scan, Cooldown }
+
+/// Enemy archetype. `Angel` freezes while the player looks at it and closes in when unobserved.
+/// `Mirror` ignores sight entirely and copies each player step, reversed unless `set_mirror_same`.
+/// `Blind` never sees; it only hunts the footstep noise fed through `hear_noise`.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
-enum EnemyState { Patrol, Chase, Cooldown }
+pub enum EnemyKind { Hunter, Angel, Mirror, Blind }
 
 pub struct Enemy {
     pub x: f32,
@@ -18,8 +40,10 @@ pub struct Enemy {
     pub active: bool,
     pub fov: f32,
     pub range: f32,
+    pub kind: EnemyKind,
     speed_patrol: f32,
     speed_chase: f32,
+    turn_rate: f32,
     state: EnemyState,
     cooldown: f32,
     cooldown_max: f32,
@@ -30,6 +54,32 @@ pub struct Enemy {
     last_seen_y: f32,
     has_last_seen: bool,
     memory_time: f32,
+    memory_max: f32,
+    detect_boost: f32,
+    conspicuity: f32,
+    sight_scale: f32,
+    chase_scale: f32,
+    light_scale: f32,
+    player_safe: bool,
+    safe_decay: f32,
+    scan_time: f32,
+    scan_timer: f32,
+    scan_base: f32,
+    player_view: Option<(f32, f32)>,
+    player_step: (f32, f32),
+    mirror_same: bool,
+    walk_phase: f32,
+    walk_amount: f32,
+    moved: bool,
+    patrol_route: Vec<(usize, usize)>,
+    route_idx: Option<usize>,
+    route_heading: f32,
+    lure: Option<(f32, f32)>,
+    lure_goal: Option<(f32, f32)>,
+    lure_timer: f32,
+    saw_player: bool,
+    just_spotted: bool,
+    path: PathScratch,
 }
 
 impl Enemy {
@@ -39,8 +89,10 @@ impl Enemy {
             active: false,
             fov: std::f32::consts::PI * (2.0/3.0),
             range: 1100.0,
+            kind: EnemyKind::Hunter,
             speed_patrol: 50.0,
             speed_chase: 115.0,
+            turn_rate: DEFAULT_TURN_RATE,
             state: EnemyState::Patrol,
             cooldown: 0.0,
             cooldown_max: 2.5,
@@ -51,45 +103,294 @@ impl Enemy {
             last_seen_y: 0.0,
             has_last_seen: false,
             memory_time: 0.0,
+            memory_max: 5.0,
+            detect_boost: 0.0,
+            conspicuity: 1.0,
+            sight_scale: 1.0,
+            chase_scale: 1.0,
+            light_scale: 1.0,
+            player_safe: false,
+            safe_decay: DEFAULT_SAFE_DECAY,
+            scan_time: 1.6,
+            scan_timer: 0.0,
+            scan_base: 0.0,
+            player_view: None,
+            player_step: (0.0, 0.0),
+            mirror_same: false,
+            walk_phase: 0.0,
+            walk_amount: 0.0,
+            moved: false,
+            patrol_route: Vec::new(),
+            route_idx: None,
+            route_heading: 0.0,
+            lure: None,
+            lure_goal: None,
+            lure_timer: LURE_INTERVAL_SECS,
+            saw_player: false,
+            just_spotted: false,
+            path: PathScratch::new(DEFAULT_SEARCH_RADIUS),
         }
     }
     pub fn is_chasing(&self) -> bool { matches!(self.state, EnemyState::Chase) }
+    pub fn is_scanning(&self) -> bool { matches!(self.state, EnemyState::Scan) }
+    pub fn is_cooling_down(&self) -> bool { matches!(self.state, EnemyState::Cooldown) }
+    // 0 when the cooldown starts, 1 right before returning to patrol (0 outside cooldown).
+    pub fn cooldown_progress(&self) -> f32 {
+        if !self.is_cooling_down() || self.cooldown_max <= 0.0 { return 0.0; }
+        (1.0 - self.cooldown / self.cooldown_max).clamp(0.0, 1.0)
+    }
+    // Seconds spent looking around at the last-seen spot before giving up (0 = skip scanning).
+    pub fn set_scan_time(&mut self, secs: f32) { self.scan_time = secs.clamp(0.0, 10.0); }
+    pub fn speed_patrol(&self) -> f32 { self.speed_patrol }
+    pub fn speed_chase(&self) -> f32 { self.speed_chase }
+    pub fn memory_max(&self) -> f32 { self.memory_max }
+    pub fn turn_rate(&self) -> f32 { self.turn_rate }
+    // Setters for live tuning; values are clamped so a stray keypress can't break the AI.
+    pub fn set_fov(&mut self, fov: f32) { self.fov = fov.clamp(10f32.to_radians(), std::f32::consts::TAU); }
+    pub fn set_range(&mut self, range: f32) { self.range = range.clamp(64.0, 4000.0); }
+    pub fn set_speed_patrol(&mut self, speed: f32) { self.speed_patrol = speed.clamp(0.0, 400.0); }
+    pub fn set_speed_chase(&mut self, speed: f32) { self.speed_chase = speed.clamp(0.0, 600.0); }
+    // Max heading change in rad/s; lower is easier to juke, higher is relentless.
+    pub fn set_turn_rate(&mut self, rate: f32) { self.turn_rate = rate.clamp(0.5, 12.0); }
+    // BFS depth cap (cells) for chase/route pathing; 0 = unbounded. Defaults to DEFAULT_SEARCH_RADIUS.
+    pub fn set_path_radius(&mut self, cells: usize) { self.path.set_radius(cells); }
+    pub fn set_speeds(&mut self, patrol: f32, chase: f32) { self.set_speed_patrol(patrol); self.set_speed_chase(chase); }
+    pub fn set_memory_max(&mut self, secs: f32) { self.memory_max = secs.clamp(0.0, 30.0); self.memory_time = self.memory_time.min(self.memory_max); }
+    // Temporary detectability boost (e.g. right after an orb pickup); 0 = normal.
+    pub fn set_detect_boost(&mut self, boost: f32) { self.detect_boost = boost.clamp(0.0, 2.0); }
+    // How visible the player is (flashlight brightness): scales the sight range; 1 = normal.
+    pub fn set_conspicuity(&mut self, c: f32) { self.conspicuity = c.clamp(0.2, 2.0); }
+    // Rule-driven sight multiplier (e.g. shorter sight while the player escapes); 1 = normal.
+    pub fn set_sight_scale(&mut self, s: f32) { self.sight_scale = s.clamp(0.1, 1.0); }
+    // Rule-driven chase speed multiplier (e.g. enraged while the player escapes); 1 = normal.
+    pub fn set_chase_scale(&mut self, s: f32) { self.chase_scale = s.clamp(0.5, 3.0); }
+    // Chase speed multiplier from the player's flashlight (slower while lit, faster in the dark); 1 = normal.
+    pub fn set_light_scale(&mut self, s: f32) { self.light_scale = s.clamp(0.25, 3.0); }
+    /// Chase speed after the rule and light multipliers (before the close-range boost).
+    pub fn effective_chase_speed(&self) -> f32 { self.speed_chase * self.chase_scale * self.light_scale }
+    // Whether the player currently stands in a safe room (set each frame before `update`).
+    pub fn set_player_safe(&mut self, safe: bool) { self.player_safe = safe; }
+    // Memory decay multiplier while the player is safe (1 = no effect).
+    pub fn set_safe_decay(&mut self, mul: f32) { self.safe_decay = mul.clamp(1.0, 20.0); }
+    /// Seconds of chase memory lost per second of `dt` right now.
+    pub fn memory_decay_rate(&self) -> f32 { if self.player_safe { self.safe_decay } else { 1.0 } }
+    pub fn detection_range(&self) -> f32 { self.range * (1.0 + self.detect_boost) * self.conspicuity * self.sight_scale }
     pub fn sees_player(&self, maze: &Maze, px: f32, py: f32, block_size: usize) -> bool {
-        let vx = px - self.x; let vy = py - self.y; let dist = (vx*vx + vy*vy).sqrt(); if dist > self.range { return false; }
+        if self.kind == EnemyKind::Blind { return false; }
+        let vx = px - self.x; let vy = py - self.y; let dist = (vx*vx + vy*vy).sqrt(); if dist > self.detection_range() { return false; }
         let target = vy.atan2(vx); let ad = normalize_angle(target - self.a).abs(); if ad > self.fov * 0.5 { return false; }
         line_of_sight_clear(maze, self.x, self.y, px, py, block_size)
     }
-    pub fn update(&mut self, maze: &Maze, px: f32, py: f32, block_size: usize, dt: f32) {
-        if !self.active { return; }
+    // Noise cue: while boosted, the enemy hears the player through walls within a few cells.
+    pub fn hears_player(&self, px: f32, py: f32, block_size: usize) -> bool {
+        if self.detect_boost <= 0.0 { return false; }
+        let vx = px - self.x; let vy = py - self.y; let hear = block_size as f32 * 6.0 * self.detect_boost; vx*vx + vy*vy <= hear*hear
+    }
+    // Mirror of `sees_player`: is the enemy inside the player's view cone (heading `pa`, width `pfov`) with clear LOS?
+    pub fn player_sees_enemy(&self, maze: &Maze, px: f32, py: f32, pa: f32, pfov: f32, block_size: usize) -> bool {
+        let vx = self.x - px; let vy = self.y - py;
+        let ad = normalize_angle(vy.atan2(vx) - pa).abs(); if ad > pfov * 0.5 { return false; }
+        line_of_sight_clear(maze, px, py, self.x, self.y, block_size)
+    }
+    // Player heading and FOV for this frame; only the `Angel` uses it (None = nobody is looking).
+    pub fn set_player_view(&mut self, pa: f32, pfov: f32) { self.player_view = Some((pa, pfov)); }
+    // Player displacement this frame (world units); only `Mirror` enemies use it, once per `update`.
+    pub fn set_player_step(&mut self, dx: f32, dy: f32) { self.player_step = (dx, dy); }
+    // Mirror enemies copy the player's step as-is instead of reversing it.
+    pub fn set_mirror_same(&mut self, same: bool) { self.mirror_same = same; }
+    /// A noise (player footstep) at `(x, y)` audible up to `radius` world units, walls included.
+    /// Only `Blind` enemies react: they search the spot as if they had seen the player there.
+    pub fn hear_noise(&mut self, x: f32, y: f32, radius: f32) {
+        if !self.active || self.kind != EnemyKind::Blind { return; }
+        if (x - self.x).powi(2) + (y - self.y).powi(2) > radius * radius { return; }
+        self.last_seen_x = x; self.last_seen_y = y; self.has_last_seen = true;
+        if !self.is_chasing() { self.path_recalc_timer = 0.0; }
+        self.state = EnemyState::Chase; self.memory_time = self.memory_max;
+    }
+    /// Where the enemy last saw (or heard) the player, while it still remembers; `None` once
+    /// the memory is cleared.
+    pub fn last_seen(&self) -> Option<(f32, f32)> { self.has_last_seen.then_some((self.last_seen_x, self.last_seen_y)) }
+    /// Chase memory left, 1 right after contact down to 0 when it gives up.
+    pub fn memory_fraction(&self) -> f32 { if self.memory_max > 0.0 { (self.memory_time / self.memory_max).clamp(0.0, 1.0) } else { 0.0 } }
+    /// True only on the update where the enemy went from not seeing to seeing the player.
+    pub fn just_spotted(&self) -> bool { self.just_spotted }
+    /// Alert from another enemy: a patrolling/searching/cooling enemy heads for `(x, y)` as if it
+    /// had last seen the player there. Inactive, chasing or non-Hunter enemies ignore it.
+    pub fn alert_to(&mut self, x: f32, y: f32) {
+        if !self.active || self.kind != EnemyKind::Hunter || self.is_chasing() { return; }
+        self.last_seen_x = x; self.last_seen_y = y; self.has_last_seen = true;
+        self.state = EnemyState::Chase; self.memory_time = self.memory_max; self.path_recalc_timer = 0.0;
+    }
+    /// Anti-camp nudge: a patrolling/searching/cooling enemy of any kind goes to search `(x, y)`.
+    /// Inactive or already chasing enemies ignore it.
+    pub fn nudge_to(&mut self, x: f32, y: f32) {
+        if !self.active || self.is_chasing() { return; }
+        self.last_seen_x = x; self.last_seen_y = y; self.has_last_seen = true;
+        self.state = EnemyState::Chase; self.memory_time = self.memory_max; self.path_recalc_timer = 0.0;
+    }
+    // Send the enemy away: inactive, with its chase memory and search state cleared.
+    pub fn banish(&mut self) {
+        self.active = false; self.moved = false; self.route_idx = None; self.lure_goal = None; self.state = EnemyState::Patrol; self.has_last_seen = false; self.memory_time = 0.0; self.cooldown = 0.0;
+    }
+    pub fn update(&mut self, maze: &Maze, px: f32, py: f32, block_size: usize, dt: f32, field: Option<&DistanceField>) {
+        let (x0, y0) = (self.x, self.y);
+        self.think(maze, px, py, block_size, dt, field);
+        let moved = ((self.x - x0).powi(2) + (self.y - y0).powi(2)).sqrt();
+        self.moved = moved > 1e-3;
+        self.advance_walk(moved, dt);
+    }
+    /// Follow `route` (cells, looped) while patrolling instead of wandering; empty = wander.
+    pub fn set_patrol_route(&mut self, route: Vec<(usize, usize)>) { self.patrol_route = route; self.route_idx = None; }
+    /// Point (e.g. `orb_cluster_target`) a patrolling enemy detours to every `LURE_INTERVAL_SECS`;
+    /// `None` turns the lure off and drops any detour in progress.
+    pub fn set_orb_lure(&mut self, target: Option<(f32, f32)>) {
+        self.lure = target;
+        if target.is_none() { self.lure_goal = None; }
+    }
+    pub fn patrol_route(&self) -> &[(usize, usize)] { &self.patrol_route }
+    /// True if the last `update` actually displaced the enemy (not blocked, scanning or frozen).
+    pub fn moved_last_update(&self) -> bool { self.moved }
+    // Walk cycle driven by distance travelled; `walk_amount` eases in/out so starting or
+    // stopping doesn't pop.
+    fn advance_walk(&mut self, moved: f32, dt: f32) {
+        self.walk_phase = (self.walk_phase + moved * WALK_PHASE_PER_UNIT) % std::f32::consts::TAU;
+        let target = if moved > 1e-3 { 1.0 } else { 0.0 };
+        self.walk_amount += (target - self.walk_amount) * (dt * 10.0).min(1.0);
+        if self.walk_amount < 1e-3 { self.walk_amount = 0.0; }
+    }
+    /// Sprite height scale for the walk cycle (1.0 when standing still); width uses the inverse.
+    pub fn walk_squash(&self) -> f32 {
+        1.0 + WALK_SQUASH_AMP * self.walk_amount * (2.0 * self.walk_phase).sin()
+    }
+    fn think(&mut self, maze: &Maze, px: f32, py: f32, block_size: usize, dt: f32, field: Option<&DistanceField>) {
+        self.just_spotted = false;
+        if !self.active { self.saw_player = false; return; }
+        if self.kind == EnemyKind::Angel {
+            // Frozen while observed; otherwise always closing in on the player
+            let observed = self.player_view.is_some_and(|(pa, pfov)| self.player_sees_enemy(maze, px, py, pa, pfov, block_size));
+            if observed { return; }
+            self.state = EnemyState::Chase;
+            self.chase(px, py, maze, block_size, dt, field);
+            return;
+        }
+        if self.kind == EnemyKind::Mirror {
+            self.mirror_step(maze, block_size);
+            return;
+        }
         let sees_now = self.sees_player(maze, px, py, block_size);
-        if sees_now { self.last_seen_x = px; self.last_seen_y = py; self.has_last_seen = true; self.state = EnemyState::Chase; self.memory_time = 5.0; self.cooldown = self.cooldown_max; }
+        self.just_spotted = sees_now && !self.saw_player; self.saw_player = sees_now;
+        if sees_now { self.last_seen_x = px; self.last_seen_y = py; self.has_last_seen = true; self.state = EnemyState::Chase; self.memory_time = self.memory_max; self.cooldown = self.cooldown_max; }
+        else if self.hears_player(px, py, block_size) { self.last_seen_x = px; self.last_seen_y = py; self.has_last_seen = true; self.state = EnemyState::Chase; self.memory_time = self.memory_max; }
         else {
             match self.state {
-                EnemyState::Chase => { if self.memory_time > 0.0 { self.memory_time -= dt; } else { self.state = EnemyState::Cooldown; self.cooldown = self.cooldown_max; self.has_last_seen = false; } }
+                EnemyState::Chase => { if self.memory_time > 0.0 { self.memory_time -= dt * self.memory_decay_rate(); } else { self.state = EnemyState::Cooldown; self.cooldown = self.cooldown_max; self.has_last_seen = false; } }
+                EnemyState::Scan => { self.scan_timer -= dt; if self.scan_timer <= 0.0 { self.state = EnemyState::Cooldown; self.cooldown = self.cooldown_max; } }
                 EnemyState::Cooldown => { self.cooldown -= dt; if self.cooldown <= 0.0 { self.state = EnemyState::Patrol; } }
                 EnemyState::Patrol => {}
             }
         }
         match self.state {
-            EnemyState::Chase => { if sees_now { self.chase(px, py, maze, block_size, dt) } else if self.has_last_seen { self.search_last_seen(maze, block_size, dt); } }
+            EnemyState::Chase => { self.route_idx = None; if sees_now { self.chase(px, py, maze, block_size, dt, field) } else if self.has_last_seen { self.search_last_seen(maze, block_size, dt); } }
+            EnemyState::Scan => self.scan(),
             EnemyState::Cooldown => self.patrol(maze, block_size, dt, true),
             EnemyState::Patrol => self.patrol(maze, block_size, dt, false),
         }
     }
+    // Apply the player's last step (reversed unless `mirror_same`) with the usual wall sliding.
+    fn mirror_step(&mut self, maze: &Maze, block_size: usize) {
+        let (sx, sy) = std::mem::take(&mut self.player_step);
+        let k = if self.mirror_same { 1.0 } else { -1.0 };
+        let (dx, dy) = (sx * k, sy * k);
+        if dx * dx + dy * dy < 1e-8 { return; }
+        self.a = dy.atan2(dx);
+        try_move_with_slide(maze, block_size, &mut self.x, &mut self.y, dx, dy);
+    }
     fn search_last_seen(&mut self, maze: &Maze, block_size: usize, dt: f32) {
-        let dx = self.last_seen_x - self.x; let dy = self.last_seen_y - self.y; if (dx*dx + dy*dy) < 40.0*40.0 { self.has_last_seen = false; return; }
-        self.path_recalc_timer -= dt; if self.path_recalc_timer <= 0.0 { self.path_recalc_timer = 0.25; if let Some((nx, ny)) = next_step_towards(maze, block_size, self.x, self.y, self.last_seen_x, self.last_seen_y) { let target = ny.atan2(nx); let mut diff = normalize_angle(target - self.a); let max_turn = 2.6 * dt; if diff >  max_turn { diff =  max_turn; } if diff < -max_turn { diff = -max_turn; } self.a = normalize_angle(self.a + diff); } }
-        let speed = self.speed_chase * 0.82; let dxm = self.a.cos() * speed * dt; let dym = self.a.sin() * speed * dt; let _ = try_move_with_slide(maze, block_size, &mut self.x, &mut self.y, dxm, dym);
+        let dx = self.last_seen_x - self.x; let dy = self.last_seen_y - self.y; if (dx*dx + dy*dy) < 40.0*40.0 { self.has_last_seen = false; self.begin_scan(); return; }
+        self.path_recalc_timer -= dt; if self.path_recalc_timer <= 0.0 { self.path_recalc_timer = 0.25; if let Some((nx, ny)) = self.path.next_step(maze, block_size, self.x, self.y, self.last_seen_x, self.last_seen_y) { self.turn_towards(ny.atan2(nx), dt); } }
+        let speed = self.effective_chase_speed() * 0.82; let dxm = self.a.cos() * speed * dt; let dym = self.a.sin() * speed * dt; let _ = try_move_with_slide(maze, block_size, &mut self.x, &mut self.y, dxm, dym);
+    }
+    // Rotate towards `target` by at most `turn_rate * dt`.
+    pub fn turn_towards(&mut self, target: f32, dt: f32) {
+        let max_turn = self.turn_rate * dt; let diff = normalize_angle(target - self.a).clamp(-max_turn, max_turn); self.a = normalize_angle(self.a + diff);
+    }
+    fn begin_scan(&mut self) {
+        if self.scan_time <= 0.0 { return; }
+        self.state = EnemyState::Scan; self.scan_timer = self.scan_time; self.scan_base = self.a;
     }
-    fn chase(&mut self, px: f32, py: f32, maze: &Maze, block_size: usize, dt: f32) {
-        let target = (py - self.y).atan2(px - self.x); let mut diff = normalize_angle(target - self.a); let max_turn = 2.8 * dt; if diff >  max_turn { diff =  max_turn; } if diff < -max_turn { diff = -max_turn; } self.a = normalize_angle(self.a + diff);
-        let dxn = px - self.x; let dyn_ = py - self.y; let dist2 = dxn*dxn + dyn_*dyn_; let boost = if dist2 < 120.0*120.0 { 1.15 } else { 1.0 }; let speed = self.speed_chase * boost;
+    // Look around in place: sweep left/right around the arrival heading.
+    fn scan(&mut self) {
+        let phase = 1.0 - (self.scan_timer / self.scan_time.max(1e-3)).clamp(0.0, 1.0); self.a = normalize_angle(self.scan_base + (phase * std::f32::consts::TAU).sin() * 1.3);
+    }
+    fn chase(&mut self, px: f32, py: f32, maze: &Maze, block_size: usize, dt: f32, field: Option<&DistanceField>) {
+        // With a shared distance field, head for the downhill neighbor cell unless already next to the player
+        let b = block_size as f32; let (ci, cj) = ((self.x / b).floor().max(0.0) as usize, (self.y / b).floor().max(0.0) as usize);
+        let (tx, ty) = match field.filter(|f| f.distance(ci, cj) > 1).and_then(|f| f.next_cell(ci, cj)) { Some((ni, nj)) => ((ni as f32 + 0.5) * b, (nj as f32 + 0.5) * b), None => (px, py) };
+        self.turn_towards((ty - self.y).atan2(tx - self.x), dt);
+        let dxn = px - self.x; let dyn_ = py - self.y; let dist2 = dxn*dxn + dyn_*dyn_; let boost = if dist2 < 120.0*120.0 { 1.15 } else { 1.0 }; let speed = self.effective_chase_speed() * boost;
         let dx = self.a.cos() * speed * dt; let dy = self.a.sin() * speed * dt; try_move_with_slide(maze, block_size, &mut self.x, &mut self.y, dx, dy);
     }
     fn patrol(&mut self, maze: &Maze, block_size: usize, dt: f32, slow: bool) {
-        let speed = if slow { self.speed_patrol * 0.6 } else { self.speed_patrol }; self.patrol_turn_timer -= dt; if self.patrol_turn_timer <= 0.0 { self.patrol_turn_timer = 1.2; self.a = normalize_angle(self.a + 0.6 - 1.2 * ((self.x as i32 ^ self.y as i32) & 1) as f32); }
+        let speed = if slow { self.speed_patrol * 0.6 } else { self.speed_patrol };
+        if !slow && self.head_for_lure(maze, block_size, dt, speed) { return; }
+        if !self.patrol_route.is_empty() { self.follow_route(maze, block_size, dt, speed); return; } self.patrol_turn_timer -= dt; if self.patrol_turn_timer <= 0.0 { self.patrol_turn_timer = 1.2; self.a = normalize_angle(self.a + 0.6 - 1.2 * ((self.x as i32 ^ self.y as i32) & 1) as f32); }
         let dx = self.a.cos() * speed * dt; let dy = self.a.sin() * speed * dt; if !try_move_with_slide(maze, block_size, &mut self.x, &mut self.y, dx, dy) { self.a = normalize_angle(self.a + 0.5); self.patrol_turn_timer = self.patrol_turn_timer.max(0.2); }
     }
+    // Orb lure detour: every `LURE_INTERVAL_SECS` of patrol, walk to the lure point (BFS stepping)
+    // until close; false when there is no detour and the usual patrol should run.
+    fn head_for_lure(&mut self, maze: &Maze, block_size: usize, dt: f32, speed: f32) -> bool {
+        self.lure_timer -= dt;
+        if self.lure_goal.is_none() && self.lure_timer <= 0.0 {
+            self.lure_timer = LURE_INTERVAL_SECS;
+            self.lure_goal = self.lure;
+            self.path_recalc_timer = 0.0;
+        }
+        let Some((tx, ty)) = self.lure_goal else { return false };
+        let b = block_size as f32;
+        if (tx - self.x).powi(2) + (ty - self.y).powi(2) < (LURE_ARRIVE_CELLS * b).powi(2) {
+            self.lure_goal = None;
+            self.route_idx = None;
+            return false;
+        }
+        self.path_recalc_timer -= dt;
+        if self.path_recalc_timer <= 0.0 {
+            self.path_recalc_timer = 0.25;
+            if let Some((nx, ny)) = self.path.next_step(maze, block_size, self.x, self.y, tx, ty) { self.route_heading = ny.atan2(nx); }
+        }
+        self.turn_towards(self.route_heading, dt);
+        let (dx, dy) = (self.a.cos() * speed * dt, self.a.sin() * speed * dt);
+        let _ = try_move_with_slide(maze, block_size, &mut self.x, &mut self.y, dx, dy);
+        true
+    }
+    // Walk the patrol route with BFS stepping; (re)join it at the nearest waypoint after a chase.
+    fn follow_route(&mut self, maze: &Maze, block_size: usize, dt: f32, speed: f32) {
+        let b = block_size as f32;
+        let center = |(i, j): (usize, usize)| ((i as f32 + 0.5) * b, (j as f32 + 0.5) * b);
+        let idx = match self.route_idx {
+            Some(k) if k < self.patrol_route.len() => k,
+            _ => {
+                let d2 = |c| { let (x, y) = center(c); (x - self.x).powi(2) + (y - self.y).powi(2) };
+                let k = (0..self.patrol_route.len()).min_by(|&a, &c| d2(self.patrol_route[a]).total_cmp(&d2(self.patrol_route[c]))).unwrap_or(0);
+                self.path_recalc_timer = 0.0;
+                k
+            }
+        };
+        let (tx, ty) = center(self.patrol_route[idx]);
+        if (tx - self.x).powi(2) + (ty - self.y).powi(2) < (0.3 * b).powi(2) {
+            self.route_idx = Some((idx + 1) % self.patrol_route.len());
+            self.path_recalc_timer = 0.0;
+            return;
+        }
+        self.route_idx = Some(idx);
+        self.path_recalc_timer -= dt;
+        if self.path_recalc_timer <= 0.0 {
+            self.path_recalc_timer = 0.25;
+            if let Some((nx, ny)) = self.path.next_step(maze, block_size, self.x, self.y, tx, ty) { self.route_heading = ny.atan2(nx); }
+        }
+        self.turn_towards(self.route_heading, dt);
+        let (dx, dy) = (self.a.cos() * speed * dt, self.a.sin() * speed * dt);
+        let _ = try_move_with_slide(maze, block_size, &mut self.x, &mut self.y, dx, dy);
+    }
     pub fn facing_key_for_camera(&mut self, cam_x: f32, cam_y: f32) -> char {
         let ang_to_cam = (cam_y - self.y).atan2(cam_x - self.x); let diff = normalize_angle(ang_to_cam - self.a); let deg = diff.to_degrees();
         let candidate = if deg > -60.0 && deg <= 60.0 { 'S' } else if deg > 60.0 && deg <= 150.0 { 'E' } else if deg <= -60.0 && deg > -150.0 { 'W' } else { 'N' };
@@ -98,27 +399,49 @@ impl Enemy {
     }
 }
 
-fn next_step_towards(maze: &Maze, block: usize, sx: f32, sy: f32, tx: f32, ty: f32) -> Option<(f32, f32)> {
-    let w = maze[0].len(); let h = maze.len();
-    let start = ((sx / block as f32).floor() as isize, (sy / block as f32).floor() as isize);
-    let goal  = ((tx / block as f32).floor() as isize, (ty / block as f32).floor() as isize);
-    if start.0 < 0 || start.1 < 0 || goal.0 < 0 || goal.1 < 0 { return None; }
-    let (sx_i, sy_i) = (start.0 as usize, start.1 as usize); let (gx_i, gy_i) = (goal.0 as usize, goal.1 as usize);
-    if sx_i >= w || sy_i >= h || gx_i >= w || gy_i >= h { return None; }
-    let passable = |i: usize, j: usize| -> bool { if j >= h || i >= w { return false; } let c = maze[j][i]; c == ' ' || c == 'g' };
-    if !passable(sx_i, sy_i) || !passable(gx_i, gy_i) { return None; }
-    let mut prev: Vec<Vec<Option<(usize,usize)>>> = vec![vec![None; w]; h];
-    let mut q = std::collections::VecDeque::new(); q.push_back((sx_i, sy_i)); prev[sy_i][sx_i] = Some((sx_i, sy_i));
-    let dirs = [(1,0),(-1,0),(0,1),(0,-1)];
-    while let Some((cx, cy)) = q.pop_front() { if (cx, cy) == (gx_i, gy_i) { break; } for (dx,dy) in dirs { let nx = cx as isize + dx; let ny = cy as isize + dy; if nx < 0 || ny < 0 { continue; } let (nxu, nyu) = (nx as usize, ny as usize); if nxu >= w || nyu >= h { continue; } if prev[nyu][nxu].is_some() { continue; } if !passable(nxu, nyu) { continue; } prev[nyu][nxu] = Some((cx, cy)); q.push_back((nxu, nyu)); } }
-    if prev[gy_i][gx_i].is_none() { return None; }
-    let mut cur = (gx_i, gy_i); let mut last = cur; while cur != (sx_i, sy_i) { last = cur; if let Some(p) = prev[cur.1][cur.0] { cur = p; } else { break; } }
-    let cx = (last.0 as f32 + 0.5) * block as f32; let cy = (last.1 as f32 + 0.5) * block as f32; Some((cx - sx, cy - sy))
+/// Patrol tour for `maze`: a depth-first walk over the enemy-walkable cells reachable from the
+/// first one (row-major), keeping only the cells where a corridor ends, bends or branches.
+/// Followed in order (and looped) it sweeps every corridor; consecutive waypoints may be far
+/// apart after a dead end, which the BFS stepping covers.
+pub fn auto_patrol_route(maze: &Maze) -> Vec<(usize, usize)> {
+    let h = maze.len();
+    let open = |i: isize, j: isize| -> bool {
+        i >= 0 && j >= 0 && maze.get(j as usize).and_then(|r| r.get(i as usize)).is_some_and(|&c| tiles().enemy_walkable(c))
+    };
+    let dirs: [(isize, isize); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+    let Some(start) = (0..h).flat_map(|j| (0..maze[j].len()).map(move |i| (i, j))).find(|&(i, j)| open(i as isize, j as isize)) else { return Vec::new(); };
+    let mut seen = vec![vec![false; maze.iter().map(|r| r.len()).max().unwrap_or(0)]; h];
+    let mut stack = vec![start];
+    let mut route = Vec::new();
+    while let Some((i, j)) = stack.pop() {
+        if seen[j][i] { continue; }
+        seen[j][i] = true;
+        let exits: Vec<(isize, isize)> = dirs.iter().copied().filter(|&(dx, dy)| open(i as isize + dx, j as isize + dy)).collect();
+        let straight = exits.len() == 2 && exits[0].0 == -exits[1].0 && exits[0].1 == -exits[1].1;
+        if !straight { route.push((i, j)); }
+        for &(dx, dy) in exits.iter().rev() {
+            let (ni, nj) = ((i as isize + dx) as usize, (j as isize + dy) as usize);
+            if !seen[nj][ni] { stack.push((ni, nj)); }
+        }
+    }
+    route
+}
+
+/// Lure point for the orb cluster nearest `from`: the orbs within `radius` of the closest orb
+/// form the cluster, and the cluster orb nearest its centroid is returned (an orb always sits
+/// on a walkable cell, the centroid may not). `None` without orbs.
+pub fn orb_cluster_target(orbs: &[(f32, f32)], from: (f32, f32), radius: f32) -> Option<(f32, f32)> {
+    let d2 = |a: (f32, f32), b: (f32, f32)| (a.0 - b.0).powi(2) + (a.1 - b.1).powi(2);
+    let seed = orbs.iter().copied().min_by(|&a, &b| d2(a, from).total_cmp(&d2(b, from)))?;
+    let cluster: Vec<(f32, f32)> = orbs.iter().copied().filter(|&o| d2(o, seed) <= radius * radius).collect();
+    let n = cluster.len() as f32;
+    let c = (cluster.iter().map(|o| o.0).sum::<f32>() / n, cluster.iter().map(|o| o.1).sum::<f32>() / n);
+    cluster.into_iter().min_by(|&a, &b| d2(a, c).total_cmp(&d2(b, c)))
 }
 
 fn line_of_sight_clear(maze: &Maze, x0: f32, y0: f32, x1: f32, y1: f32, block_size: usize) -> bool {
     let dx = x1 - x0; let dy = y1 - y0; let step = (block_size as f32 * 0.6).max(5.0); let dist = (dx*dx + dy*dy).sqrt(); let steps = (dist / step).ceil() as i32;
-    for i in 0..=steps { let t = i as f32 / steps.max(1) as f32; let sx = x0 + dx * t; let sy = y0 + dy * t; let ci = (sx / block_size as f32).floor() as isize; let cj = (sy / block_size as f32).floor() as isize; if cj < 0 || ci < 0 { return false; } let (ci, cj) = (ci as usize, cj as usize); if cj >= maze.len() || ci >= maze[cj].len() { return false; } let c = maze[cj][ci]; if c != ' ' && c != 'g' { return false; } }
+    for i in 0..=steps { let t = i as f32 / steps.max(1) as f32; let sx = x0 + dx * t; let sy = y0 + dy * t; let ci = (sx / block_size as f32).floor() as isize; let cj = (sy / block_size as f32).floor() as isize; if cj < 0 || ci < 0 { return false; } let (ci, cj) = (ci as usize, cj as usize); if cj >= maze.len() || ci >= maze[cj].len() { return false; } if tiles().blocks_los(maze[cj][ci]) { return false; } }
     true
 }
 
@@ -134,5 +457,367 @@ fn is_free_radius(map: &Maze, block: usize, wx: f32, wy: f32, radius: f32) -> bo
 fn is_cell_free(map: &Maze, block: usize, wx: f32, wy: f32) -> bool {
     let i = (wx / block as f32).floor() as isize; let j = (wy / block as f32).floor() as isize; if i < 0 || j < 0 { return false; }
     let (i, j) = (i as usize, j as usize); if j >= map.len() || i >= map[0].len() { return false; }
-    let c = map[j][i]; c == ' ' || c == 'g'
+    tiles().enemy_walkable(map[j][i])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tuning_setters_clamp_their_inputs() {
+        let mut e = Enemy::new(0.0, 0.0, 0.0);
+        e.set_fov(0.0);
+        assert_eq!(e.fov, 10f32.to_radians());
+        e.set_fov(100.0);
+        assert_eq!(e.fov, std::f32::consts::TAU);
+        e.set_range(1.0);
+        assert_eq!(e.range, 64.0);
+        e.set_range(1e6);
+        assert_eq!(e.range, 4000.0);
+        e.set_speed_patrol(-5.0);
+        assert_eq!(e.speed_patrol(), 0.0);
+        e.set_speed_patrol(1e4);
+        assert_eq!(e.speed_patrol(), 400.0);
+        e.set_speed_chase(-5.0);
+        assert_eq!(e.speed_chase(), 0.0);
+        e.set_speed_chase(1e4);
+        assert_eq!(e.speed_chase(), 600.0);
+        e.set_memory_max(-1.0);
+        assert_eq!(e.memory_max(), 0.0);
+        e.set_memory_max(99.0);
+        assert_eq!(e.memory_max(), 30.0);
+    }
+
+    #[test]
+    fn lowering_memory_max_trims_the_current_memory() {
+        let mut e = Enemy::new(0.0, 0.0, 0.0);
+        e.memory_time = 20.0;
+        e.set_memory_max(5.0);
+        assert_eq!(e.memory_time, 5.0);
+    }
+
+    #[test]
+    fn in_range_values_pass_through() {
+        let mut e = Enemy::new(0.0, 0.0, 0.0);
+        e.set_fov(1.0);
+        e.set_range(500.0);
+        e.set_speeds(120.0, 260.0);
+        assert_eq!((e.fov, e.range, e.speed_patrol(), e.speed_chase()), (1.0, 500.0, 120.0, 260.0));
+    }
+
+    fn grid(rows: &[&str]) -> Maze {
+        rows.iter().map(|r| r.chars().collect()).collect()
+    }
+
+    #[test]
+    fn alert_sends_a_patrolling_enemy_to_search_the_point() {
+        // Straight corridor; the player sits in a sealed cell below it, out of sight
+        let maze = grid(&[
+            "+--------+",
+            "|        |",
+            "+--------+",
+            "| +------+",
+        ]);
+        let block = 64usize;
+        let (px, py) = (1.5 * 64.0, 3.5 * 64.0);
+        let (ax, ay) = (8.5 * 64.0, 1.5 * 64.0);
+        let mut e = Enemy::new(1.5 * 64.0, 1.5 * 64.0, 0.0);
+        e.active = true;
+        assert!(!e.is_chasing());
+        e.alert_to(ax, ay);
+        assert!(e.is_chasing());
+        assert_eq!(e.last_seen(), Some((ax, ay)));
+        let start = (ax - e.x).abs();
+        for _ in 0..60 { e.update(&maze, px, py, block, 1.0 / 30.0, None); }
+        assert!((ax - e.x).abs() < start - 128.0, "enemy did not head for the alert ({} -> {})", start, (ax - e.x).abs());
+        assert!(!e.just_spotted());
+    }
+
+    #[test]
+    fn alert_is_ignored_while_chasing_or_inactive() {
+        let mut e = Enemy::new(0.0, 0.0, 0.0);
+        e.active = true;
+        e.alert_to(100.0, 0.0);
+        e.alert_to(300.0, 0.0);
+        assert_eq!(e.last_seen(), Some((100.0, 0.0)));
+        let mut gone = Enemy::new(0.0, 0.0, 0.0);
+        gone.active = true;
+        gone.banish();
+        gone.alert_to(100.0, 0.0);
+        assert!(!gone.is_chasing());
+    }
+
+    // Active enemy at the west end of a long corridor, facing east
+    fn corridor_enemy() -> (Maze, Enemy) {
+        let maze = grid(&["+------------------+", "|                  |", "+------------------+"]);
+        let mut e = Enemy::new(1.5 * 64.0, 1.5 * 64.0, 0.0);
+        e.active = true;
+        (maze, e)
+    }
+
+    #[test]
+    fn set_speeds_scales_the_chase_step() {
+        let (maze, mut slow) = corridor_enemy();
+        let mut fast = Enemy::new(slow.x, slow.y, 0.0);
+        fast.active = true;
+        slow.set_speeds(40.0, 100.0);
+        fast.set_speeds(80.0, 200.0);
+        assert_eq!((slow.speed_patrol(), slow.speed_chase()), (40.0, 100.0));
+        assert_eq!((fast.speed_patrol(), fast.speed_chase()), (80.0, 200.0));
+        // Player in plain sight further down the corridor
+        let (px, py, x0) = (15.5 * 64.0, 1.5 * 64.0, slow.x);
+        slow.update(&maze, px, py, 64, 0.1, None);
+        fast.update(&maze, px, py, 64, 0.1, None);
+        assert!(slow.is_chasing() && fast.is_chasing());
+        assert!(((slow.x - x0) - 10.0).abs() < 1e-3, "slow step {}", slow.x - x0);
+        assert!(((fast.x - x0) - 20.0).abs() < 1e-3, "fast step {}", fast.x - x0);
+    }
+
+    #[test]
+    fn reaching_the_last_seen_spot_scans_in_place_then_cools_down() {
+        let (maze, mut e) = corridor_enemy();
+        // Player far out of range; the last sighting is a few units ahead
+        let (px, py) = (5000.0, 5000.0);
+        e.alert_to(e.x + 20.0, e.y);
+        e.update(&maze, px, py, 64, 0.05, None);
+        assert!(e.is_scanning());
+        let (x0, y0) = (e.x, e.y);
+        let mut headings = Vec::new();
+        let mut frames = 0;
+        while e.is_scanning() {
+            e.update(&maze, px, py, 64, 0.05, None);
+            if e.is_scanning() { assert_eq!((e.x, e.y), (x0, y0), "moved while scanning"); }
+            headings.push(e.a);
+            frames += 1;
+            assert!(frames < 100, "scan never ended");
+        }
+        let spread = headings.iter().cloned().fold(f32::MIN, f32::max) - headings.iter().cloned().fold(f32::MAX, f32::min);
+        assert!(spread > 1.0, "barely looked around: {}", spread);
+        assert!(e.is_cooling_down());
+        assert_eq!(e.last_seen(), None);
+    }
+
+    #[test]
+    fn angel_freezes_while_watched_and_moves_otherwise() {
+        let (maze, mut e) = corridor_enemy();
+        e.kind = EnemyKind::Angel;
+        let (px, py) = (10.5 * 64.0, 1.5 * 64.0);
+        let start = (e.x, e.y);
+        // Player looking straight down the corridor at it
+        e.set_player_view(std::f32::consts::PI, std::f32::consts::FRAC_PI_2);
+        for _ in 0..10 { e.update(&maze, px, py, 64, 0.05, None); }
+        assert_eq!((e.x, e.y), start);
+        assert!(!e.moved_last_update());
+        // Player turned away
+        e.set_player_view(0.0, std::f32::consts::FRAC_PI_2);
+        for _ in 0..10 { e.update(&maze, px, py, 64, 0.05, None); }
+        assert!(e.x > start.0 + 10.0, "angel stayed put at {}", e.x);
+    }
+
+    #[test]
+    fn cooldown_progress_climbs_to_one_as_the_cooldown_runs_out() {
+        let (maze, mut e) = corridor_enemy();
+        assert_eq!(e.cooldown_progress(), 0.0);
+        e.state = EnemyState::Cooldown;
+        e.cooldown = e.cooldown_max;
+        assert_eq!(e.cooldown_progress(), 0.0);
+        // Player just behind the enemy, outside its view: nothing restarts the chase
+        let mut last = 0.0;
+        while e.is_cooling_down() {
+            e.update(&maze, 1.2 * 64.0, 1.5 * 64.0, 64, 0.1, None);
+            if !e.is_cooling_down() { break; }
+            let p = e.cooldown_progress();
+            assert!(p > last && p <= 1.0, "{} after {}", p, last);
+            last = p;
+        }
+        assert!(last > 0.9);
+        assert_eq!(e.cooldown_progress(), 0.0);
+    }
+
+    #[test]
+    fn conspicuity_stretches_the_detection_range() {
+        let (maze, mut e) = corridor_enemy();
+        e.set_range(640.0);
+        // Player 12 cells down the corridor, beyond the plain range
+        let (px, py) = (13.5 * 64.0, 1.5 * 64.0);
+        assert!(!e.sees_player(&maze, px, py, 64));
+        e.set_conspicuity(1.5);
+        assert!(e.detection_range() > 640.0);
+        assert!(e.sees_player(&maze, px, py, 64));
+        // Dim: even a closer player goes unseen
+        e.set_conspicuity(0.5);
+        assert!(!e.sees_player(&maze, 7.5 * 64.0, py, 64));
+        assert!(e.sees_player(&maze, 5.5 * 64.0, py, 64));
+    }
+
+    #[test]
+    fn higher_turn_rate_aligns_in_fewer_updates() {
+        let updates_to_face = |rate: f32| {
+            let mut e = Enemy::new(0.0, 0.0, 0.0);
+            e.set_turn_rate(rate);
+            let target = 2.5;
+            (1..1000).find(|_| { e.turn_towards(target, 1.0 / 60.0); normalize_angle(target - e.a).abs() < 1e-4 }).unwrap()
+        };
+        let (slow, fast) = (updates_to_face(2.0), updates_to_face(6.0));
+        assert!(fast < slow, "{} vs {}", fast, slow);
+        // Roughly inversely proportional to the rate
+        assert!((slow as f32 / fast as f32 - 3.0).abs() < 0.2);
+    }
+
+    #[test]
+    fn walk_squash_oscillates_only_while_moving() {
+        let mut e = Enemy::new(0.0, 0.0, 0.0);
+        assert_eq!(e.walk_squash(), 1.0);
+        let dt = 1.0 / 60.0;
+        let mut seen = Vec::new();
+        for _ in 0..120 {
+            e.advance_walk(3.0, dt);
+            seen.push(e.walk_squash());
+        }
+        assert!(seen.iter().any(|&s| s > 1.03) && seen.iter().any(|&s| s < 0.97));
+        assert!(seen.iter().all(|&s| (s - 1.0).abs() <= WALK_SQUASH_AMP + 1e-6));
+        // Standing still eases back to exactly 1
+        for _ in 0..120 { e.advance_walk(0.0, dt); }
+        assert_eq!(e.walk_squash(), 1.0);
+    }
+
+    #[test]
+    fn memory_fades_faster_while_the_player_is_in_a_safe_room() {
+        let frames_to_forget = |safe: bool| {
+            let (maze, mut e) = corridor_enemy();
+            e.set_safe_decay(4.0);
+            e.state = EnemyState::Chase;
+            e.memory_time = e.memory_max;
+            e.set_player_safe(safe);
+            // Player just behind the enemy, out of view
+            (1..10_000).find(|_| { e.update(&maze, 1.2 * 64.0, 1.5 * 64.0, 64, 1.0 / 60.0, None); e.state != EnemyState::Chase }).unwrap()
+        };
+        let (exposed, hidden) = (frames_to_forget(false), frames_to_forget(true));
+        assert!(hidden * 3 < exposed, "{} vs {}", hidden, exposed);
+        let mut e = Enemy::new(0.0, 0.0, 0.0);
+        assert_eq!(e.memory_decay_rate(), 1.0);
+        e.set_player_safe(true);
+        assert_eq!(e.memory_decay_rate(), DEFAULT_SAFE_DECAY);
+    }
+
+    #[test]
+    fn mirror_enemy_steps_opposite_to_the_player() {
+        let maze = grid(&["+------+", "|      |", "|      |", "|      |", "+------+"]);
+        let mut e = Enemy::new(3.5 * 64.0, 2.5 * 64.0, 0.0);
+        e.active = true;
+        e.kind = EnemyKind::Mirror;
+        e.set_player_step(10.0, -6.0);
+        e.update(&maze, 0.0, 0.0, 64, 1.0 / 60.0, None);
+        assert!((e.x - (3.5 * 64.0 - 10.0)).abs() < 1e-3 && (e.y - (2.5 * 64.0 + 6.0)).abs() < 1e-3);
+        // The step is used once; no player step, no movement
+        let (x, y) = (e.x, e.y);
+        e.update(&maze, 0.0, 0.0, 64, 1.0 / 60.0, None);
+        assert_eq!((e.x, e.y), (x, y));
+        // Same-direction variant
+        e.set_mirror_same(true);
+        e.set_player_step(10.0, -6.0);
+        e.update(&maze, 0.0, 0.0, 64, 1.0 / 60.0, None);
+        assert!((e.x - (x + 10.0)).abs() < 1e-3 && (e.y - (y - 6.0)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn blocked_on_both_axes_is_not_moving() {
+        // A single walkable cell: any big step runs into walls on x and on y
+        let maze = grid(&["+-+", "| |", "+-+"]);
+        let mut e = Enemy::new(1.5 * 64.0, 1.5 * 64.0, 0.0);
+        e.active = true;
+        e.kind = EnemyKind::Mirror;
+        e.set_player_step(-40.0, -40.0);
+        e.update(&maze, 0.0, 0.0, 64, 1.0 / 60.0, None);
+        assert_eq!((e.x, e.y), (1.5 * 64.0, 1.5 * 64.0));
+        assert!(!e.moved_last_update());
+        // A step that fits does count
+        e.set_player_step(-5.0, 0.0);
+        e.update(&maze, 0.0, 0.0, 64, 1.0 / 60.0, None);
+        assert!(e.moved_last_update());
+        // Inactive enemies never move
+        e.active = false;
+        e.update(&maze, 0.0, 0.0, 64, 1.0 / 60.0, None);
+        assert!(!e.moved_last_update());
+    }
+
+    #[test]
+    fn auto_patrol_route_tours_walkable_corners() {
+        let maze = grid(&[
+            "+-------+",
+            "|   |   |",
+            "| +-+ | |",
+            "|     | |",
+            "+-+ +-+ |",
+            "|       |",
+            "+-------+",
+        ]);
+        let route = auto_patrol_route(&maze);
+        assert!(route.len() >= 8, "route too short: {route:?}");
+        assert!(route.iter().all(|&(i, j)| tiles().enemy_walkable(maze[j][i])));
+        let mut unique = route.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), route.len());
+        // Dead ends and corners are waypoints; the middle of a straight corridor is not
+        assert!(route.contains(&(1, 1)) && route.contains(&(3, 1)) && route.contains(&(7, 5)));
+        assert!(!route.contains(&(4, 5)));
+        assert!(auto_patrol_route(&grid(&["+-+", "+-+"])).is_empty());
+    }
+
+    #[test]
+    fn blind_enemy_only_hunts_noise() {
+        let (maze, mut e) = corridor_enemy();
+        e.kind = EnemyKind::Blind;
+        // Right in front of it, in plain sight
+        assert!(!e.sees_player(&maze, 3.5 * 64.0, 1.5 * 64.0, 64));
+        // A footstep out of earshot changes nothing, a near one starts the search there
+        e.hear_noise(15.5 * 64.0, 1.5 * 64.0, 4.0 * 64.0);
+        assert!(!e.is_chasing() && e.last_seen().is_none());
+        e.hear_noise(4.5 * 64.0, 1.5 * 64.0, 4.0 * 64.0);
+        assert!(e.is_chasing());
+        assert_eq!(e.last_seen(), Some((4.5 * 64.0, 1.5 * 64.0)));
+        // Sighted kinds ignore noise
+        let (_, mut hunter) = corridor_enemy();
+        hunter.hear_noise(2.5 * 64.0, 1.5 * 64.0, 4.0 * 64.0);
+        assert!(!hunter.is_chasing());
+    }
+
+    #[test]
+    fn a_lured_patrol_detours_to_the_orb_cluster() {
+        let (maze, mut e) = corridor_enemy();
+        (e.x, e.a) = (8.5 * 64.0, std::f32::consts::PI);
+        // Two orbs east (the nearest cluster) and a lone one farther west
+        let orbs = [(13.5 * 64.0, 96.0), (14.5 * 64.0, 96.0), (1.5 * 64.0, 96.0)];
+        let target = orb_cluster_target(&orbs, (e.x, e.y), 2.0 * 64.0).unwrap();
+        assert_eq!(target, (13.5 * 64.0, 96.0));
+        e.set_orb_lure(Some(target));
+        let dt = 1.0 / 60.0;
+        let mut closest = f32::INFINITY;
+        for _ in 0..(20.0 / dt) as usize {
+            e.update(&maze, -1000.0, -1000.0, 64, dt, None);
+            assert!(!e.is_chasing());
+            closest = closest.min((e.x - target.0).abs());
+        }
+        assert!(closest < LURE_ARRIVE_CELLS * 64.0, "closest {closest}");
+        assert_eq!(orb_cluster_target(&[], (0.0, 0.0), 64.0), None);
+    }
+
+    #[test]
+    fn last_seen_holds_while_remembered_and_clears_after() {
+        let (maze, mut e) = corridor_enemy();
+        assert_eq!(e.last_seen(), None);
+        e.set_memory_max(1.0);
+        e.update(&maze, 5.5 * 64.0, 1.5 * 64.0, 64, 1.0 / 60.0, None);
+        assert_eq!(e.last_seen(), Some((5.5 * 64.0, 1.5 * 64.0)));
+        // Player gone (out of the maze): the position stays while memory lasts
+        let dt = 0.1;
+        for _ in 0..5 {
+            e.update(&maze, -1000.0, -1000.0, 64, dt, None);
+            assert_eq!(e.last_seen(), Some((5.5 * 64.0, 1.5 * 64.0)));
+        }
+        for _ in 0..30 { e.update(&maze, -1000.0, -1000.0, 64, dt, None); }
+        assert_eq!(e.last_seen(), None);
+    }
 }
@@ -0,0 +1,120 @@
+//! Structured game events for outside integrations (stream overlays, automated runs).
+//!
+//! The game loop reports what happens to one `GameEventSink`; `NullSink` ignores everything
+//! and `LogSink` appends one line per event to a text file.
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+
+pub const EVENT_LOG_FILE: &str = "events.log";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    /// A level began; `file` is its maze file.
+    LevelStarted { file: String },
+    /// An orb was picked up; `score` counts orbs collected so far this level.
+    OrbCollected { score: usize },
+    /// The enemy appeared at world position `(x, y)`.
+    EnemySpawned { x: f32, y: f32 },
+    /// The enemy just spotted the player.
+    PlayerSeen,
+    /// The enemy caught the player.
+    PlayerCaught,
+    /// The level was won with `score` orbs after `secs` seconds.
+    LevelWon { score: usize, secs: f32 },
+}
+
+impl GameEvent {
+    /// One-line `key=value` form used by `LogSink`.
+    pub fn to_line(&self) -> String {
+        match self {
+            GameEvent::LevelStarted { file } => format!("level_started file={}", file),
+            GameEvent::OrbCollected { score } => format!("orb_collected score={}", score),
+            GameEvent::EnemySpawned { x, y } => format!("enemy_spawned x={:.0} y={:.0}", x, y),
+            GameEvent::PlayerSeen => "player_seen".to_string(),
+            GameEvent::PlayerCaught => "player_caught".to_string(),
+            GameEvent::LevelWon { score, secs } => format!("level_won score={} secs={:.1}", score, secs),
+        }
+    }
+}
+
+/// Receiver for game events; `emit` is called from the game loop, so keep it cheap.
+pub trait GameEventSink {
+    fn emit(&mut self, event: GameEvent);
+}
+
+/// Sink that drops every event.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NullSink;
+
+impl GameEventSink for NullSink {
+    fn emit(&mut self, _event: GameEvent) {}
+}
+
+/// Sink that appends `<seconds since start> <event line>` to a file.
+pub struct LogSink {
+    file: File,
+    start: std::time::Instant,
+}
+
+impl LogSink {
+    /// Open `path` for appending (created if missing).
+    pub fn open(path: &str) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { file, start: std::time::Instant::now() })
+    }
+}
+
+impl GameEventSink for LogSink {
+    fn emit(&mut self, event: GameEvent) {
+        let _ = writeln!(self.file, "{:.3} {}", self.start.elapsed().as_secs_f32(), event.to_line());
+    }
+}
+
+/// Sink that keeps every event in memory, for tests.
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct RecordingSink {
+    pub events: Vec<GameEvent>,
+}
+
+#[cfg(test)]
+impl GameEventSink for RecordingSink {
+    fn emit(&mut self, event: GameEvent) { self.events.push(event); }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recording_sink_keeps_events_in_order() {
+        let mut sink = RecordingSink::default();
+        let dyn_sink: &mut dyn GameEventSink = &mut sink;
+        dyn_sink.emit(GameEvent::PlayerSeen);
+        dyn_sink.emit(GameEvent::OrbCollected { score: 1 });
+        dyn_sink.emit(GameEvent::PlayerCaught);
+        assert_eq!(sink.events, vec![GameEvent::PlayerSeen, GameEvent::OrbCollected { score: 1 }, GameEvent::PlayerCaught]);
+    }
+
+    #[test]
+    fn lines_are_stable() {
+        assert_eq!(GameEvent::LevelStarted { file: "maze2.txt".into() }.to_line(), "level_started file=maze2.txt");
+        assert_eq!(GameEvent::EnemySpawned { x: 96.4, y: 160.6 }.to_line(), "enemy_spawned x=96 y=161");
+        assert_eq!(GameEvent::LevelWon { score: 12, secs: 83.26 }.to_line(), "level_won score=12 secs=83.3");
+    }
+
+    #[test]
+    fn log_sink_appends_one_line_per_event() {
+        let path = std::env::temp_dir().join(format!("events_test_{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        {
+            let mut sink = LogSink::open(path.to_str().unwrap()).unwrap();
+            sink.emit(GameEvent::PlayerSeen);
+            sink.emit(GameEvent::PlayerCaught);
+        }
+        let text = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let kinds: Vec<&str> = text.lines().map(|l| l.split_once(' ').unwrap().1).collect();
+        assert_eq!(kinds, ["player_seen", "player_caught"]);
+    }
+}
@@ -1,10 +1,49 @@
 //! Maze loading and normalization.
 use std::fs::File;
 use std::io::{BufRead, BufReader};
+use crate::core::tiles::{tiles, FLOOR, EXIT, WALL};
 
 pub type Maze = Vec<Vec<char>>;
 
+/// Trap tile: walkable, but slows the player while standing on it.
+pub const TRAP: char = 'x';
+/// Banish switch: a solid tile the player activates by walking up to it.
+pub const BANISH: char = 'B';
+
+/// Cell `(i, j)`, or `None` off the map. With `wrap` (toroidal levels) indices past an edge
+/// come back in from the opposite one.
+pub fn cell_at(maze: &Maze, i: isize, j: isize, wrap: bool) -> Option<char> {
+    let (w, h) = (maze.first().map_or(0, |r| r.len()) as isize, maze.len() as isize);
+    if w == 0 || h == 0 { return None; }
+    let (i, j) = if wrap { (i.rem_euclid(w), j.rem_euclid(h)) } else { (i, j) };
+    if i < 0 || j < 0 || i >= w || j >= h { return None; }
+    maze[j as usize].get(i as usize).copied()
+}
+
+/// World position folded back into the maze's extent (toroidal levels).
+pub fn wrap_pos(maze: &Maze, block: f32, x: f32, y: f32) -> (f32, f32) {
+    let (w, h) = (maze.first().map_or(0, |r| r.len()) as f32 * block, maze.len() as f32 * block);
+    if w <= 0.0 || h <= 0.0 { return (x, y); }
+    (x.rem_euclid(w), y.rem_euclid(h))
+}
+
+/// Cell `(i, j)` and its char under world position `(x, y)`; `None` off the map. With `wrap`
+/// the position is folded back in first.
+pub fn cell_at_world(maze: &Maze, block: f32, x: f32, y: f32, wrap: bool) -> Option<(usize, usize, char)> {
+    let (x, y) = if wrap { wrap_pos(maze, block, x, y) } else { (x, y) };
+    let (i, j) = ((x / block).floor() as isize, (y / block).floor() as isize);
+    let c = cell_at(maze, i, j, false)?;
+    Some((i as usize, j as usize, c))
+}
+
 pub fn load_maze(path: &str) -> Maze {
+    load_maze_with(path, false)
+}
+
+/// Like `load_maze`; with `seal_border` any free cell on the perimeter becomes a wall so
+/// the world is always enclosed (an exit `g` on the edge is kept: it's solid for the player).
+/// Decoy exits `d` don't count: a map with only decoys still gets a true `g` placed.
+pub fn load_maze_with(path: &str, seal_border: bool) -> Maze {
     let file = File::open(path).expect("No pude abrir el maze.txt");
     let reader = BufReader::new(file);
     let mut grid: Maze = Vec::new();
@@ -12,27 +51,85 @@ pub fn load_maze(path: &str) -> Maze {
         let mut row: Vec<char> = Vec::new();
         if let Ok(s) = line {
             for ch in s.chars() {
-                if ch == ' ' || ch == 'g' || ch == '+' || ch == '-' || ch == '|' {
+                if tiles().knows(ch) {
                     row.push(ch);
                 } else {
-                    if ch == '\t' { row.push(' ') } else { row.push('#') }
+                    if ch == '\t' { row.push(FLOOR) } else { row.push(WALL) }
                 }
             }
         }
         if !row.is_empty() { grid.push(row); }
     }
     let maxw = grid.iter().map(|r| r.len()).max().unwrap_or(0);
-    for r in &mut grid { while r.len() < maxw { r.push('#'); } }
+    for r in &mut grid { while r.len() < maxw { r.push(WALL); } }
+    if seal_border { seal_maze_border(&mut grid); }
     let mut has_exit = false;
-    for row in &grid { if row.iter().any(|&c| c == 'g') { has_exit = true; break; } }
+    for row in &grid { if row.iter().any(|&c| tiles().is_exit(c)) { has_exit = true; break; } }
     if !has_exit {
         let mut best: Option<(usize,usize,usize)> = None;
         for (j,row) in grid.iter().enumerate() {
             for (i,&c) in row.iter().enumerate() {
-                if c == ' ' { let d = i*i + j*j; if best.map(|b| d > b.2).unwrap_or(true) { best = Some((i,j,d)); } }
+                if c == FLOOR { let d = i*i + j*j; if best.map(|b| d > b.2).unwrap_or(true) { best = Some((i,j,d)); } }
             }
         }
-        if let Some((i,j,_)) = best { grid[j][i] = 'g'; }
+        if let Some((i,j,_)) = best { grid[j][i] = EXIT; }
     }
     grid
 }
+
+/// Turn every player-walkable perimeter cell (floor, traps) into a wall.
+pub fn seal_maze_border(grid: &mut Maze) {
+    let h = grid.len();
+    for (j, row) in grid.iter_mut().enumerate() {
+        let w = row.len();
+        for (i, c) in row.iter_mut().enumerate() {
+            let edge = i == 0 || j == 0 || i + 1 == w || j + 1 == h;
+            if edge && tiles().player_walkable(*c) { *c = WALL; }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write `text` to a scratch maze file and load it.
+    fn load(name: &str, text: &str, seal_border: bool) -> Maze {
+        let path = std::env::temp_dir().join(format!("maze_test_{}_{name}.txt", std::process::id()));
+        std::fs::write(&path, text).unwrap();
+        let maze = load_maze_with(path.to_str().unwrap(), seal_border);
+        let _ = std::fs::remove_file(&path);
+        maze
+    }
+
+    // Open on every side, rows of different lengths, a trap on the edge.
+    const RAGGED: &str = "   +--\n|  g  \nx    |\n+ -\n";
+
+    #[test]
+    fn sealing_a_ragged_maze_walls_off_the_whole_perimeter() {
+        let maze = load("ragged_sealed", RAGGED, true);
+        let (h, w) = (maze.len(), maze[0].len());
+        assert!(maze.iter().all(|r| r.len() == w));
+        for (j, row) in maze.iter().enumerate() {
+            for (i, &c) in row.iter().enumerate() {
+                if i == 0 || j == 0 || i + 1 == w || j + 1 == h {
+                    assert!(!tiles().player_walkable(c), "open edge at ({i}, {j}): {c:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sealing_leaves_inner_cells_alone() {
+        let open = load("ragged_open", RAGGED, false);
+        let sealed = load("ragged_sealed_inner", RAGGED, true);
+        assert_eq!(open.len(), sealed.len());
+        for j in 1..open.len() - 1 {
+            let w = open[j].len();
+            assert_eq!(open[j][1..w - 1], sealed[j][1..w - 1], "row {j}");
+        }
+        // Without the flag the edges stay open
+        assert_eq!(open[0][0], FLOOR);
+        assert_eq!(open[2][0], TRAP);
+    }
+}
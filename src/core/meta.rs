@@ -0,0 +1,90 @@
+//! Opt-in meta progression: a cumulative orb counter persisted across runs.
+use std::fs;
+
+pub const META_FILE: &str = "meta.txt";
+
+/// Cumulative orbs needed for the warm wall theme.
+pub const THEME_UNLOCK_ORBS: u32 = 60;
+/// Cumulative orbs needed for the starting speed bonus.
+pub const BONUS_UNLOCK_ORBS: u32 = 150;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Meta {
+    pub enabled: bool,
+    pub total_orbs: u32,
+}
+
+impl Meta {
+    /// Read `path`; a missing or malformed file means meta is off with no progress.
+    pub fn load(path: &str) -> Self {
+        let mut m = Self::default();
+        let Ok(text) = fs::read_to_string(path) else { return m; };
+        for line in text.lines() {
+            let Some((k, v)) = line.split_once('=') else { continue; };
+            match k.trim() {
+                "enabled" => if let Ok(b) = v.trim().parse::<bool>() { m.enabled = b; },
+                "total_orbs" => if let Ok(n) = v.trim().parse::<u32>() { m.total_orbs = n; },
+                _ => {}
+            }
+        }
+        m
+    }
+
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        fs::write(path, format!("enabled = {}\ntotal_orbs = {}\n", self.enabled, self.total_orbs))
+    }
+
+    /// Add a won run's orbs to the counter (no-op while meta is off).
+    pub fn record_win(&mut self, orbs: u32) {
+        if self.enabled { self.total_orbs = self.total_orbs.saturating_add(orbs); }
+    }
+
+    /// True when meta is on and the counter has reached `threshold`.
+    pub fn unlocked(&self, threshold: u32) -> bool {
+        self.enabled && self.total_orbs >= threshold
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wins_add_to_the_counter_only_when_enabled() {
+        let mut m = Meta::default();
+        m.record_win(40);
+        assert_eq!(m.total_orbs, 0);
+        m.enabled = true;
+        m.record_win(40);
+        m.record_win(25);
+        assert_eq!(m.total_orbs, 65);
+        m.total_orbs = u32::MAX - 1;
+        m.record_win(10);
+        assert_eq!(m.total_orbs, u32::MAX);
+    }
+
+    #[test]
+    fn unlocks_need_the_threshold_and_meta_on() {
+        let mut m = Meta { enabled: true, total_orbs: THEME_UNLOCK_ORBS - 1 };
+        assert!(!m.unlocked(THEME_UNLOCK_ORBS));
+        m.record_win(1);
+        assert!(m.unlocked(THEME_UNLOCK_ORBS));
+        assert!(!m.unlocked(BONUS_UNLOCK_ORBS));
+        // Turning meta off hides the unlocks but keeps the progress
+        m.enabled = false;
+        assert!(!m.unlocked(THEME_UNLOCK_ORBS));
+        assert_eq!(m.total_orbs, THEME_UNLOCK_ORBS);
+    }
+
+    #[test]
+    fn counter_survives_a_save_and_load() {
+        let path = std::env::temp_dir().join(format!("meta_test_{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        let m = Meta { enabled: true, total_orbs: 123 };
+        m.save(path).unwrap();
+        assert_eq!(Meta::load(path), m);
+        fs::remove_file(path).unwrap();
+        // Missing file: off, no progress
+        assert_eq!(Meta::load(path), Meta::default());
+    }
+}
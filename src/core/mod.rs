@@ -5,8 +5,30 @@
 //! - `enemy`: Enemy AI and navigation
 //! - `maze`: Maze loading and normalization
 //! - `process_events`: Input handling and movement
+//! - `distance_field`: Shared BFS distance field for enemy pathing
+//! - `pathfinder`: Point-to-point BFS with reusable buffers and a depth cap
+//! - `settings`: Persisted player settings (mouse)
+//! - `meta`: Opt-in cumulative orb counter and unlocks
+//! - `assets`: Asset-loading report (disk vs. fallback)
+//! - `shake`: Trauma-based camera shake
+//! - `tiles`: Maze tile legend (walkability, sight, texture, exit/hazard per char)
+//! - `difficulty`: Easy/Normal/Hard presets applied on top of every level
+//! - `stats`: Per-level death counter (`stats.txt`)
+//! - `aggression`: Opt-in adaptive enemy speed carried between levels (`aggression.txt`)
+//! - `events`: Structured game events and sinks (no-op, `events.log`)
 
 pub mod player;
 pub mod enemy;
 pub mod maze;
 pub mod process_events;
+pub mod distance_field;
+pub mod pathfinder;
+pub mod settings;
+pub mod meta;
+pub mod assets;
+pub mod shake;
+pub mod tiles;
+pub mod difficulty;
+pub mod stats;
+pub mod aggression;
+pub mod events;
@@ -0,0 +1,194 @@
+//! Grid BFS for the enemy's point-to-point pathing with reusable scratch buffers.
+//!
+//! The parent grid and queue live in `PathScratch` and survive between calls, so a recalc
+//! doesn't allocate; a generation stamp marks visited cells instead of clearing the grid.
+//! The search stops after `radius` steps from the start, capping the cost on huge mazes.
+use std::collections::VecDeque;
+use crate::core::maze::Maze;
+use crate::core::tiles::tiles;
+
+/// Default BFS depth cap (steps from the start); 0 means unbounded.
+pub const DEFAULT_SEARCH_RADIUS: usize = 64;
+
+#[derive(Clone, Debug, Default)]
+pub struct PathScratch {
+    w: usize,
+    h: usize,
+    prev: Vec<usize>,
+    stamp: Vec<u32>,
+    generation: u32,
+    queue: VecDeque<(usize, u32)>,
+    radius: usize,
+}
+
+impl PathScratch {
+    pub fn new(radius: usize) -> Self {
+        Self { radius, ..Self::default() }
+    }
+
+    pub fn radius(&self) -> usize { self.radius }
+    pub fn set_radius(&mut self, radius: usize) { self.radius = radius; }
+
+    // Resize for a maze of `w`x`h` cells and start a fresh generation (wrapping clears stamps).
+    fn begin(&mut self, w: usize, h: usize) {
+        if self.w != w || self.h != h {
+            self.w = w; self.h = h;
+            self.prev.clear(); self.prev.resize(w * h, 0);
+            self.stamp.clear(); self.stamp.resize(w * h, 0);
+            self.generation = 0;
+        }
+        self.generation = self.generation.wrapping_add(1);
+        if self.generation == 0 {
+            self.stamp.iter_mut().for_each(|s| *s = 0);
+            self.generation = 1;
+        }
+        self.queue.clear();
+    }
+
+    /// Offset from `(sx, sy)` to the centre of the first cell on a shortest path to `(tx, ty)`.
+    /// If the goal lies beyond the search radius, heads for the explored cell nearest to it.
+    /// `None` when either end is off the map or not walkable, or the goal can't be reached.
+    pub fn next_step(&mut self, maze: &Maze, block: usize, sx: f32, sy: f32, tx: f32, ty: f32) -> Option<(f32, f32)> {
+        let w = maze.first().map_or(0, |r| r.len()); let h = maze.len();
+        let start = ((sx / block as f32).floor() as isize, (sy / block as f32).floor() as isize);
+        let goal  = ((tx / block as f32).floor() as isize, (ty / block as f32).floor() as isize);
+        if start.0 < 0 || start.1 < 0 || goal.0 < 0 || goal.1 < 0 { return None; }
+        let (sx_i, sy_i) = (start.0 as usize, start.1 as usize); let (gx_i, gy_i) = (goal.0 as usize, goal.1 as usize);
+        if sx_i >= w || sy_i >= h || gx_i >= w || gy_i >= h { return None; }
+        let passable = |i: usize, j: usize| -> bool { j < h && i < w && i < maze[j].len() && tiles().enemy_walkable(maze[j][i]) };
+        if !passable(sx_i, sy_i) || !passable(gx_i, gy_i) { return None; }
+        self.begin(w, h);
+        let stamp_id = self.generation;
+        let (start_idx, goal_idx) = (sy_i * w + sx_i, gy_i * w + gx_i);
+        self.stamp[start_idx] = stamp_id; self.prev[start_idx] = start_idx;
+        self.queue.push_back((start_idx, 0));
+        let manhattan = |idx: usize| (idx % w).abs_diff(gx_i) + (idx / w).abs_diff(gy_i);
+        let mut best = (start_idx, manhattan(start_idx));
+        let (mut found, mut truncated) = (false, false);
+        let dirs = [(1isize, 0isize), (-1, 0), (0, 1), (0, -1)];
+        while let Some((idx, depth)) = self.queue.pop_front() {
+            if idx == goal_idx { found = true; break; }
+            let m = manhattan(idx);
+            if m < best.1 { best = (idx, m); }
+            if self.radius > 0 && depth as usize >= self.radius { truncated = true; continue; }
+            let (cx, cy) = (idx % w, idx / w);
+            for (dx, dy) in dirs {
+                let nx = cx as isize + dx; let ny = cy as isize + dy;
+                if nx < 0 || ny < 0 { continue; }
+                let (nxu, nyu) = (nx as usize, ny as usize);
+                if nxu >= w || nyu >= h { continue; }
+                let n = nyu * w + nxu;
+                if self.stamp[n] == stamp_id || !passable(nxu, nyu) { continue; }
+                self.stamp[n] = stamp_id; self.prev[n] = idx;
+                self.queue.push_back((n, depth + 1));
+            }
+        }
+        // Cut short by the radius with the goal unseen: aim at the closest explored cell instead
+        let target = if found { goal_idx } else if truncated && best.0 != start_idx { best.0 } else { return None; };
+        let mut cur = target; let mut last = cur;
+        while cur != start_idx { last = cur; cur = self.prev[cur]; }
+        let cx = ((last % w) as f32 + 0.5) * block as f32; let cy = ((last / w) as f32 + 0.5) * block as f32;
+        Some((cx - sx, cy - sy))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(rows: &[&str]) -> Maze {
+        rows.iter().map(|r| r.chars().collect()).collect()
+    }
+
+    fn maze() -> Maze {
+        grid(&[
+            "+---------+",
+            "|   |     |",
+            "| | | +-+ |",
+            "| |   | | |",
+            "| +---+ | |",
+            "|       |  ",
+            "+-------+-+",
+        ])
+    }
+
+    // Plain BFS with fresh buffers: first cell on a shortest path from `s` to `g`.
+    fn plain_first_step(maze: &Maze, s: (usize, usize), g: (usize, usize)) -> Option<(usize, usize)> {
+        let (w, h) = (maze[0].len(), maze.len());
+        let mut prev: Vec<Option<(usize, usize)>> = vec![None; w * h];
+        let mut q = VecDeque::from([s]);
+        prev[s.1 * w + s.0] = Some(s);
+        while let Some((x, y)) = q.pop_front() {
+            if (x, y) == g { break; }
+            for (dx, dy) in [(1isize, 0isize), (-1, 0), (0, 1), (0, -1)] {
+                let (nx, ny) = (x as isize + dx, y as isize + dy);
+                if nx < 0 || ny < 0 || nx as usize >= w || ny as usize >= h { continue; }
+                let (nx, ny) = (nx as usize, ny as usize);
+                if prev[ny * w + nx].is_some() || !tiles().enemy_walkable(maze[ny][nx]) { continue; }
+                prev[ny * w + nx] = Some((x, y));
+                q.push_back((nx, ny));
+            }
+        }
+        prev[g.1 * w + g.0]?;
+        let mut cur = g;
+        while let Some(p) = prev[cur.1 * w + cur.0] {
+            if p == s { return Some(cur); }
+            cur = p;
+        }
+        None
+    }
+
+    fn free_cells(maze: &Maze) -> Vec<(usize, usize)> {
+        maze.iter().enumerate()
+            .flat_map(|(j, r)| r.iter().enumerate().filter(|(_, c)| tiles().enemy_walkable(**c)).map(move |(i, _)| (i, j)))
+            .collect()
+    }
+
+    // Cell that `next_step` points into, from the centre of `s`.
+    fn scratch_first_step(scratch: &mut PathScratch, maze: &Maze, s: (usize, usize), g: (usize, usize)) -> Option<(usize, usize)> {
+        let b = 10usize;
+        let centre = |c: (usize, usize)| ((c.0 as f32 + 0.5) * b as f32, (c.1 as f32 + 0.5) * b as f32);
+        let ((sx, sy), (tx, ty)) = (centre(s), centre(g));
+        let (dx, dy) = scratch.next_step(maze, b, sx, sy, tx, ty)?;
+        Some((((sx + dx) / b as f32) as usize, ((sy + dy) / b as f32) as usize))
+    }
+
+    #[test]
+    fn reused_scratch_matches_a_plain_bfs() {
+        let maze = maze();
+        let cells = free_cells(&maze);
+        let mut scratch = PathScratch::new(0);
+        for &s in &cells {
+            for &g in &cells {
+                if s == g { continue; }
+                assert_eq!(scratch_first_step(&mut scratch, &maze, s, g), plain_first_step(&maze, s, g), "{s:?} -> {g:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn scratch_survives_maze_size_changes_and_generation_wrap() {
+        let (big, small) = (maze(), grid(&["+---+", "|   |", "| | |", "+---+"]));
+        let mut scratch = PathScratch::new(0);
+        for _ in 0..3 {
+            assert_eq!(scratch_first_step(&mut scratch, &big, (1, 1), (9, 5)), plain_first_step(&big, (1, 1), (9, 5)));
+            assert_eq!(scratch_first_step(&mut scratch, &small, (1, 2), (3, 2)), Some((1, 1)));
+        }
+        scratch.generation = u32::MAX;
+        assert_eq!(scratch_first_step(&mut scratch, &small, (3, 2), (1, 2)), Some((3, 1)));
+    }
+
+    #[test]
+    fn radius_cap_still_heads_towards_the_goal() {
+        let corridor = grid(&["+-----------+", "|           |", "+-----------+"]);
+        let mut capped = PathScratch::new(3);
+        assert_eq!(scratch_first_step(&mut capped, &corridor, (1, 1), (11, 1)), Some((2, 1)));
+    }
+
+    #[test]
+    fn walled_off_goal_has_no_step() {
+        let sealed = grid(&["+-----+", "|  |  |", "+-----+"]);
+        assert_eq!(scratch_first_step(&mut PathScratch::new(0), &sealed, (1, 1), (5, 1)), None);
+        assert_eq!(plain_first_step(&sealed, (1, 1), (5, 1)), None);
+    }
+}
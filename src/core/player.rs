@@ -1,6 +1,14 @@
 //! Player data and defaults.
 use raylib::prelude::*;
 
+/// Max gap (s) between two forward presses for the double-tap sprint gesture.
+pub const DOUBLE_TAP_SECS: f64 = 0.3;
+/// Footstep noise multiplier while holding breath.
+pub const BREATH_NOISE_MUL: f32 = 0.4;
+/// Camera shake multiplier while holding breath.
+pub const BREATH_STEADY: f32 = 0.25;
+
+#[derive(Clone)]
 pub struct Player {
     pub pos: Vector2,
     pub a: f32,
@@ -8,7 +16,26 @@ pub struct Player {
     pub speed_walk: f32,
     pub speed_sprint: f32,
     pub mouse_sens: f32,
+    pub invert_x: bool,
+    pub mouse_look: bool, // girar con el ratón (false con el cursor liberado)
     pub sprinting: bool,
+    pub speed_mul: f32, // multiplicador temporal de velocidad (efectos de estado)
+    pub trap_slow: f32, // multiplicador de velocidad mientras pisa una trampa ('x')
+    pub lean: f32,      // inclinación lateral actual (-1 izquierda .. 1 derecha), solo mueve la cámara
+    pub lean_dist: f32, // desplazamiento máximo de la cámara al inclinarse (unidades de mundo)
+    pub flashlight_on: bool,
+    pub battery: f32,        // carga de la linterna 0..1
+    pub battery_drain: f32,  // por segundo con la linterna encendida
+    pub battery_charge: f32, // por segundo con la linterna apagada
+    pub vel: Vector2,        // velocidad actual (unidades/s)
+    pub smooth_move: bool,   // aceleración/frenado suaves en lugar de velocidad instantánea
+    pub accel: f32,          // unidades/s² hacia la dirección pedida
+    pub friction: f32,       // unidades/s² de frenado sin input
+    pub double_tap_sprint: bool, // doble toque de W activa el sprint (alternativa a Shift)
+    pub tap_sprint: bool,        // sprint enganchado por doble toque; se suelta al dejar de moverse
+    pub last_forward_press: f64, // instante (s) de la última pulsación de W
+    pub hold_breath_enabled: bool, // Ctrl izquierdo contiene la respiración
+    pub holding_breath: bool,      // contiene la respiración ahora: menos ruido y cámara estable, drena cordura
 }
 
 impl Player {
@@ -20,7 +47,216 @@ impl Player {
             speed_walk: 200.0,
             speed_sprint: 340.0,
             mouse_sens: 0.0025,
+            invert_x: false,
+            mouse_look: true,
             sprinting: false,
+            speed_mul: 1.0,
+            trap_slow: 0.45,
+            lean: 0.0,
+            lean_dist: 20.0,
+            flashlight_on: true,
+            battery: 1.0,
+            battery_drain: 1.0 / 90.0,
+            battery_charge: 1.0 / 45.0,
+            vel: Vector2::zero(),
+            smooth_move: false,
+            accel: 1400.0,
+            friction: 1800.0,
+            double_tap_sprint: false,
+            tap_sprint: false,
+            last_forward_press: f64::NEG_INFINITY,
+            hold_breath_enabled: true,
+            holding_breath: false,
         }
     }
+
+    /// Move to `(x, y)` and drop transient motion (velocity, lean, latched sprint) so nothing
+    /// carries over from where the player was. Use this instead of writing `pos` directly.
+    pub fn teleport(&mut self, x: f32, y: f32) {
+        self.pos = Vector2::new(x, y);
+        self.vel = Vector2::zero();
+        self.lean = 0.0;
+        self.sprinting = false;
+        self.tap_sprint = false;
+    }
+
+    /// Forward key pressed at `now` (s): with the gesture enabled, a second press within
+    /// `DOUBLE_TAP_SECS` latches sprint until movement stops.
+    pub fn on_forward_press(&mut self, now: f64) {
+        if self.double_tap_sprint && now - self.last_forward_press <= DOUBLE_TAP_SECS { self.tap_sprint = true; }
+        self.last_forward_press = now;
+    }
+
+    /// Footstep noise multiplier (quieter while holding breath).
+    pub fn noise_mul(&self) -> f32 { if self.holding_breath { BREATH_NOISE_MUL } else { 1.0 } }
+
+    /// Camera shake multiplier (steadier while holding breath).
+    pub fn shake_mul(&self) -> f32 { if self.holding_breath { BREATH_STEADY } else { 1.0 } }
+
+    /// Toggle the flashlight; it won't turn on with an empty battery.
+    pub fn toggle_flashlight(&mut self) {
+        self.flashlight_on = !self.flashlight_on && self.battery > 0.0;
+    }
+
+    /// Update `vel` for input direction `dir` (unit or zero) and top speed `max_speed`, and
+    /// return it. Smooth mode accelerates towards `dir * max_speed` and brakes with friction
+    /// when there's no input; otherwise velocity is set instantly.
+    pub fn step_velocity(&mut self, dir: (f32, f32), max_speed: f32, dt: f32) -> Vector2 {
+        let target = Vector2::new(dir.0 * max_speed, dir.1 * max_speed);
+        if !self.smooth_move {
+            self.vel = target;
+            return self.vel;
+        }
+        let has_input = dir.0 != 0.0 || dir.1 != 0.0;
+        let (goal, rate) = if has_input { (target, self.accel) } else { (Vector2::zero(), self.friction) };
+        let (ex, ey) = (goal.x - self.vel.x, goal.y - self.vel.y);
+        let err = (ex*ex + ey*ey).sqrt();
+        let step = rate * dt;
+        if err <= step || err < 1e-4 {
+            self.vel = goal;
+        } else {
+            self.vel.x += ex / err * step;
+            self.vel.y += ey / err * step;
+        }
+        self.vel
+    }
+
+    /// Drain while on, recharge while off; an empty battery forces the light off.
+    pub fn update_battery(&mut self, dt: f32) {
+        if self.flashlight_on {
+            self.battery = (self.battery - self.battery_drain * dt).max(0.0);
+            if self.battery <= 0.0 { self.flashlight_on = false; }
+        } else {
+            self.battery = (self.battery + self.battery_charge * dt).min(1.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn teleport_moves_and_clears_transient_motion() {
+        let mut p = Player::new(10.0, 20.0, 1.0);
+        p.vel = Vector2::new(150.0, -40.0);
+        p.lean = -0.8;
+        p.sprinting = true;
+        p.tap_sprint = true;
+        p.teleport(96.0, 160.0);
+        assert_eq!((p.pos.x, p.pos.y), (96.0, 160.0));
+        assert_eq!((p.vel.x, p.vel.y), (0.0, 0.0));
+        assert_eq!(p.lean, 0.0);
+        assert!(!p.sprinting);
+        assert!(!p.tap_sprint);
+    }
+
+    #[test]
+    fn teleport_keeps_heading_and_flashlight() {
+        let mut p = Player::new(0.0, 0.0, 1.25);
+        p.flashlight_on = false;
+        p.battery = 0.3;
+        p.teleport(5.0, 5.0);
+        assert_eq!(p.a, 1.25);
+        assert!(!p.flashlight_on);
+        assert_eq!(p.battery, 0.3);
+    }
+
+    #[test]
+    fn battery_drains_while_the_light_is_on() {
+        let mut p = Player::new(0.0, 0.0, 0.0);
+        assert!(p.flashlight_on);
+        p.update_battery(10.0);
+        assert!((p.battery - (1.0 - 10.0 * p.battery_drain)).abs() < 1e-6);
+        assert!(p.flashlight_on);
+    }
+
+    #[test]
+    fn battery_recharges_while_off_up_to_full() {
+        let mut p = Player::new(0.0, 0.0, 0.0);
+        p.flashlight_on = false;
+        p.battery = 0.5;
+        p.update_battery(9.0);
+        assert!((p.battery - (0.5 + 9.0 * p.battery_charge)).abs() < 1e-6);
+        p.update_battery(1000.0);
+        assert_eq!(p.battery, 1.0);
+    }
+
+    #[test]
+    fn empty_battery_forces_the_light_off() {
+        let mut p = Player::new(0.0, 0.0, 0.0);
+        p.battery = 0.01;
+        p.update_battery(5.0);
+        assert_eq!(p.battery, 0.0);
+        assert!(!p.flashlight_on);
+        // Can't switch back on until it has some charge
+        p.toggle_flashlight();
+        assert!(!p.flashlight_on);
+        p.update_battery(1.0);
+        p.toggle_flashlight();
+        assert!(p.flashlight_on);
+    }
+
+    #[test]
+    fn smooth_movement_ramps_up_to_top_speed() {
+        let mut p = Player::new(0.0, 0.0, 0.0);
+        p.smooth_move = true;
+        let dt = 1.0 / 60.0;
+        let mut speeds = Vec::new();
+        for _ in 0..30 { speeds.push(p.step_velocity((1.0, 0.0), 200.0, dt).x); }
+        assert!((speeds[0] - p.accel * dt).abs() < 1e-3);
+        assert!(speeds.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(*speeds.last().unwrap(), 200.0);
+        assert_eq!(p.vel.y, 0.0);
+    }
+
+    #[test]
+    fn friction_brings_smooth_movement_to_rest() {
+        let mut p = Player::new(0.0, 0.0, 0.0);
+        p.smooth_move = true;
+        p.vel = Vector2::new(120.0, -160.0);
+        let dt = 1.0 / 60.0;
+        let first = p.step_velocity((0.0, 0.0), 200.0, dt);
+        let speed = (first.x * first.x + first.y * first.y).sqrt();
+        assert!((speed - (200.0 - p.friction * dt)).abs() < 1e-2);
+        for _ in 0..30 { p.step_velocity((0.0, 0.0), 200.0, dt); }
+        assert_eq!((p.vel.x, p.vel.y), (0.0, 0.0));
+    }
+
+    #[test]
+    fn instant_movement_ignores_acceleration() {
+        let mut p = Player::new(0.0, 0.0, 0.0);
+        let v = p.step_velocity((0.0, 1.0), 150.0, 1.0 / 60.0);
+        assert_eq!((v.x, v.y), (0.0, 150.0));
+        let v = p.step_velocity((0.0, 0.0), 150.0, 1.0 / 60.0);
+        assert_eq!((v.x, v.y), (0.0, 0.0));
+    }
+
+    #[test]
+    fn double_tap_forward_latches_sprint() {
+        let mut p = Player::new(0.0, 0.0, 0.0);
+        p.double_tap_sprint = true;
+        p.on_forward_press(10.0);
+        assert!(!p.tap_sprint);
+        p.on_forward_press(10.0 + DOUBLE_TAP_SECS * 0.5);
+        assert!(p.tap_sprint);
+    }
+
+    #[test]
+    fn spaced_taps_or_the_setting_off_do_not_sprint() {
+        let mut p = Player::new(0.0, 0.0, 0.0);
+        p.double_tap_sprint = true;
+        p.on_forward_press(10.0);
+        p.on_forward_press(10.0 + DOUBLE_TAP_SECS * 2.0);
+        assert!(!p.tap_sprint);
+        // Very first press never counts as the second tap
+        let mut p = Player::new(0.0, 0.0, 0.0);
+        p.double_tap_sprint = true;
+        p.on_forward_press(0.0);
+        assert!(!p.tap_sprint);
+        let mut p = Player::new(0.0, 0.0, 0.0);
+        p.on_forward_press(10.0);
+        p.on_forward_press(10.1);
+        assert!(!p.tap_sprint);
+    }
 }
@@ -1,16 +1,34 @@
 //! Input handling and movement.
 use raylib::prelude::*;
 use crate::core::player::Player;
-use crate::core::maze::Maze;
+use crate::core::maze::{Maze, cell_at, wrap_pos};
+use crate::core::tiles::tiles;
 
 fn is_free(map: &Maze, block: usize, wx: f32, wy: f32) -> bool {
+    is_free_wrapped(map, block, wx, wy, false)
+}
+
+// Like `is_free`; with `wrap` a position past an edge is checked on the opposite side.
+fn is_free_wrapped(map: &Maze, block: usize, wx: f32, wy: f32, wrap: bool) -> bool {
+    let i = (wx / block as f32).floor() as isize;
+    let j = (wy / block as f32).floor() as isize;
+    cell_at(map, i, j, wrap).is_some_and(|c| tiles().player_walkable(c))
+}
+
+fn is_trap(map: &Maze, block: usize, wx: f32, wy: f32) -> bool {
     let i = (wx / block as f32).floor() as isize;
     let j = (wy / block as f32).floor() as isize;
     if i < 0 || j < 0 { return false; }
     let (i,j)=(i as usize, j as usize);
     if j >= map.len() || i >= map[0].len() { return false; }
-    let c = map[j][i];
-    c == ' '
+    tiles().is_hazard(map[j][i])
+}
+
+/// Movement speed this frame: walk/sprint base scaled by status effects and by a trap underfoot.
+pub fn effective_speed(player: &Player, map: &Maze, block: usize) -> f32 {
+    let base = if player.sprinting { player.speed_sprint } else { player.speed_walk };
+    let trap = if is_trap(map, block, player.pos.x, player.pos.y) { player.trap_slow } else { 1.0 };
+    base * player.speed_mul * trap
 }
 
 fn is_exit(map: &Maze, block: usize, wx: f32, wy: f32) -> bool {
@@ -19,7 +37,42 @@ fn is_exit(map: &Maze, block: usize, wx: f32, wy: f32) -> bool {
     if i < 0 || j < 0 { return false; }
     let (i,j)=(i as usize, j as usize);
     if j >= map.len() || i >= map[0].len() { return false; }
-    map[j][i] == 'g'
+    tiles().is_exit(map[j][i])
+}
+
+// Sample the attempted move in small steps (like LOS sampling) so a long step can't
+// tunnel past the exit cell. Stops at the first blocking cell.
+fn segment_hits_exit(map: &Maze, block: usize, x0: f32, y0: f32, dx: f32, dy: f32) -> bool {
+    let len = (dx*dx + dy*dy).sqrt();
+    let step = (block as f32 * 0.25).max(1.0);
+    let steps = (len / step).ceil().max(1.0) as i32;
+    for k in 1..=steps {
+        let t = k as f32 / steps as f32;
+        let (sx, sy) = (x0 + dx * t, y0 + dy * t);
+        if is_exit(map, block, sx, sy) { return true; }
+        if !is_free(map, block, sx, sy) { return false; }
+    }
+    false
+}
+
+/// Rotation (radians) for a horizontal mouse delta, honoring sensitivity and inversion;
+/// zero while mouse-look is off (cursor released).
+pub fn mouse_turn(player: &Player, dx: f32) -> f32 {
+    if !player.mouse_look { return 0.0; }
+    let sign = if player.invert_x { -1.0 } else { 1.0 };
+    dx * player.mouse_sens * sign
+}
+
+/// Camera origin for rendering: the body position pushed sideways by the current lean,
+/// pulled back if the full offset would end up inside a wall. Detection keeps using `player.pos`.
+pub fn camera_pos(player: &Player, map: &Maze, block: usize) -> Vector2 {
+    let right = (-player.a.sin(), player.a.cos());
+    let off = player.lean * player.lean_dist;
+    for k in [1.0, 0.5] {
+        let (cx, cy) = (player.pos.x + right.0 * off * k, player.pos.y + right.1 * off * k);
+        if is_free(map, block, cx, cy) { return Vector2::new(cx, cy); }
+    }
+    player.pos
 }
 
 pub fn process_events(
@@ -27,9 +80,10 @@ pub fn process_events(
     player: &mut Player,
     maze: &Maze,
     block: usize,
+    wrap: bool,
 ) -> bool {
     let md = rl.get_mouse_delta();
-    player.a += md.x * player.mouse_sens;
+    player.a += mouse_turn(player, md.x);
     if player.a >  std::f32::consts::PI { player.a -= 2.0*std::f32::consts::PI; }
     if player.a < -std::f32::consts::PI { player.a += 2.0*std::f32::consts::PI; }
     let fwd = (player.a.cos(), player.a.sin());
@@ -42,17 +96,153 @@ pub fn process_events(
     let len = (dir.0*dir.0 + dir.1*dir.1).sqrt();
     if len > 0.0001 { dir.0/=len; dir.1/=len; }
     let dt = rl.get_frame_time();
-    let sprint_pressed = rl.is_key_down(KeyboardKey::KEY_LEFT_SHIFT) || rl.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT);
+    if rl.is_key_pressed(KeyboardKey::KEY_F) { player.toggle_flashlight(); }
+    // Q/E: asomarse (solo la cámara; el cuerpo no se mueve)
+    let lean_target = (rl.is_key_down(KeyboardKey::KEY_E) as i32 - rl.is_key_down(KeyboardKey::KEY_Q) as i32) as f32;
+    player.lean += (lean_target - player.lean) * (dt * 10.0).min(1.0);
+    if rl.is_key_pressed(KeyboardKey::KEY_W) { player.on_forward_press(rl.get_time()); }
+    // Ctrl izquierdo (mantenido): contener la respiración
+    player.holding_breath = player.hold_breath_enabled && rl.is_key_down(KeyboardKey::KEY_LEFT_CONTROL);
+    if len <= 0.0001 { player.tap_sprint = false; }
+    let sprint_pressed = rl.is_key_down(KeyboardKey::KEY_LEFT_SHIFT) || rl.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT) || player.tap_sprint;
     player.sprinting = sprint_pressed && len>0.0;
-    let speed = if player.sprinting { player.speed_sprint } else { player.speed_walk };
-    let dx = dir.0 * speed * dt;
-    let dy = dir.1 * speed * dt;
-    let mut touched_exit = false;
+    let speed = effective_speed(player, maze, block);
+    if len <= 0.0001 { dir = (0.0, 0.0); }
+    let vel = player.step_velocity(dir, speed, dt);
+    move_player(player, maze, block, vel.x * dt, vel.y * dt, wrap)
+}
+
+/// Apply a movement step `(dx, dy)` with per-axis wall sliding (and edge wrapping with `wrap`).
+/// True if the step touched an exit cell anywhere along its path.
+pub fn move_player(player: &mut Player, maze: &Maze, block: usize, dx: f32, dy: f32, wrap: bool) -> bool {
+    let mut touched_exit = segment_hits_exit(maze, block, player.pos.x, player.pos.y, dx, dy);
     let newx = player.pos.x + dx;
     if is_exit(maze, block, newx, player.pos.y) { touched_exit = true; }
-    if is_free(maze, block, newx, player.pos.y) { player.pos.x = newx; }
+    // Chocar contra una pared anula la velocidad en ese eje (no queda "empujando" al soltar)
+    if is_free_wrapped(maze, block, newx, player.pos.y, wrap) { player.pos.x = newx; } else { player.vel.x = 0.0; }
     let newy = player.pos.y + dy;
     if is_exit(maze, block, player.pos.x, newy) { touched_exit = true; }
-    if is_free(maze, block, player.pos.x, newy) { player.pos.y = newy; }
+    if is_free_wrapped(maze, block, player.pos.x, newy, wrap) { player.pos.y = newy; } else { player.vel.y = 0.0; }
+    // Toroidal level: walking off one edge comes back in from the opposite one
+    if wrap { (player.pos.x, player.pos.y) = wrap_pos(maze, block as f32, player.pos.x, player.pos.y); }
     touched_exit
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(rows: &[&str]) -> Maze {
+        rows.iter().map(|r| r.chars().collect()).collect()
+    }
+
+    #[test]
+    fn a_long_step_across_the_exit_still_touches_it() {
+        let maze = grid(&["+------+", "|  g   |", "+------+"]);
+        // One frame's step from cell 1 to cell 5, right over the exit at cell 3
+        let mut p = Player::new(1.5 * 64.0, 1.5 * 64.0, 0.0);
+        assert!(move_player(&mut p, &maze, 64, 4.0 * 64.0, 0.0, false));
+        let mut short = Player::new(1.5 * 64.0, 1.5 * 64.0, 0.0);
+        assert!(!move_player(&mut short, &maze, 64, 20.0, 0.0, false));
+        assert_eq!(short.pos.x, 1.5 * 64.0 + 20.0);
+    }
+
+    #[test]
+    fn a_wall_in_front_of_the_exit_blocks_the_touch() {
+        let maze = grid(&["+------+", "| | g  |", "+------+"]);
+        let mut p = Player::new(1.5 * 64.0, 1.5 * 64.0, 0.0);
+        assert!(!move_player(&mut p, &maze, 64, 3.5 * 64.0, 0.0, false));
+    }
+
+    #[test]
+    fn only_the_true_exit_counts_as_touched() {
+        // Decoy 'd' to the west, true exit 'g' to the east
+        let maze = grid(&["+-----+", "|d   g|", "+-----+"]);
+        let mut p = Player::new(2.5 * 64.0, 1.5 * 64.0, 0.0);
+        assert!(!move_player(&mut p, &maze, 64, -40.0, 0.0, false));
+        // The decoy is solid like the real one
+        assert!(p.pos.x >= 2.0 * 64.0);
+        let mut p = Player::new(4.5 * 64.0, 1.5 * 64.0, 0.0);
+        assert!(move_player(&mut p, &maze, 64, 40.0, 0.0, false));
+    }
+
+    #[test]
+    fn standing_on_a_trap_slows_the_player() {
+        let maze = grid(&["+----+", "| x  |", "+----+"]);
+        let mut p = Player::new(2.5 * 64.0, 1.5 * 64.0, 0.0);
+        let on_trap = effective_speed(&p, &maze, 64);
+        assert_eq!(on_trap, p.speed_walk * p.trap_slow);
+        p.teleport(3.5 * 64.0, 1.5 * 64.0);
+        assert_eq!(effective_speed(&p, &maze, 64), p.speed_walk);
+        p.teleport(2.5 * 64.0, 1.5 * 64.0);
+        p.sprinting = true;
+        assert_eq!(effective_speed(&p, &maze, 64), p.speed_sprint * p.trap_slow);
+    }
+
+    #[test]
+    fn inverting_x_flips_the_turn() {
+        let mut p = Player::new(0.0, 0.0, 0.0);
+        p.mouse_sens = 0.01;
+        let normal = mouse_turn(&p, 12.0);
+        assert!((normal - 0.12).abs() < 1e-6);
+        p.invert_x = true;
+        assert_eq!(mouse_turn(&p, 12.0), -normal);
+        assert_eq!(mouse_turn(&p, -12.0), normal);
+    }
+
+    #[test]
+    fn peeking_shows_the_enemy_without_exposing_the_body() {
+        use crate::core::enemy::Enemy;
+        let maze = grid(&[
+            "+------+",
+            "|      |",
+            "| +----+",
+            "| |",
+            "+-+",
+        ]);
+        // Body tucked below the corner, facing east into the wall, leaning left (north)
+        let mut p = Player::new(1.5 * 64.0, 2.2 * 64.0, 0.0);
+        p.lean = -1.0;
+        let cam = camera_pos(&p, &maze, 64);
+        assert!(cam.y < 2.0 * 64.0, "camera didn't clear the corner: {:?}", cam);
+        let mut e = Enemy::new(5.5 * 64.0, 1.5 * 64.0, std::f32::consts::PI);
+        e.active = true;
+        // The peeking camera sees down the corridor...
+        assert!(e.player_sees_enemy(&maze, cam.x, cam.y, std::f32::consts::PI * 1.9, std::f32::consts::FRAC_PI_2, 64));
+        // ...but detection uses the hidden body, which the enemy can't see
+        assert!(e.sees_player(&maze, cam.x, cam.y, 64));
+        assert!(!e.sees_player(&maze, p.pos.x, p.pos.y, 64));
+        e.update(&maze, p.pos.x, p.pos.y, 64, 0.05, None);
+        assert!(!e.is_chasing());
+    }
+
+    #[test]
+    fn toroidal_movement_comes_back_in_from_the_opposite_edge() {
+        let maze = grid(&["#####", "     ", "#####"]);
+        let mut p = Player::new(4.5 * 64.0, 1.5 * 64.0, 0.0);
+        move_player(&mut p, &maze, 64, 64.0, 0.0, true);
+        assert!((p.pos.x - 0.5 * 64.0).abs() < 1e-3 && p.pos.y == 1.5 * 64.0);
+        move_player(&mut p, &maze, 64, -64.0, 0.0, true);
+        assert!((p.pos.x - 4.5 * 64.0).abs() < 1e-3);
+        // Without wrapping the edge stops the player
+        move_player(&mut p, &maze, 64, 64.0, 0.0, false);
+        assert!((p.pos.x - 4.5 * 64.0).abs() < 1e-3);
+        // Wrapping doesn't open walls: rows 0 and 2 are still solid across the top/bottom edge
+        move_player(&mut p, &maze, 64, 0.0, 64.0, true);
+        assert_eq!(p.pos.y, 1.5 * 64.0);
+    }
+
+    #[test]
+    fn mouse_look_turns_only_while_captured() {
+        let mut p = Player::new(0.0, 0.0, 0.0);
+        assert!(p.mouse_look);
+        assert_eq!(mouse_turn(&p, 40.0), 40.0 * p.mouse_sens);
+        // Cursor released: the mouse moves freely without turning the view
+        p.mouse_look = false;
+        assert_eq!(mouse_turn(&p, 40.0), 0.0);
+        p.invert_x = true;
+        assert_eq!(mouse_turn(&p, -40.0), 0.0);
+        p.mouse_look = true;
+        assert_eq!(mouse_turn(&p, -40.0), 40.0 * p.mouse_sens);
+    }
+}
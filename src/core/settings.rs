@@ -0,0 +1,85 @@
+//! Player settings persisted to a small `key = value` text file.
+use std::fs;
+use crate::core::player::Player;
+
+pub const SETTINGS_FILE: &str = "settings.txt";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    pub mouse_sens: f32,
+    pub invert_x: bool,
+    pub smooth_move: bool,
+    pub double_tap_sprint: bool,
+    /// CRT scanline post effect.
+    pub scanlines: bool,
+    /// How dark the scanlines get (0..1).
+    pub scanline_intensity: f32,
+    /// Add the faint vertical RGB mask to the scanlines.
+    pub crt_mask: bool,
+    /// Pause the game while the window is unfocused.
+    pub auto_pause: bool,
+    /// Blit the framebuffer at the largest whole-number scale that fits (sharp pixels, wider bars).
+    pub pixel_perfect: bool,
+    /// Light beam over the way out while escaping.
+    pub exit_beam: bool,
+    /// Holding left Ctrl holds the breath (quieter steps, steadier view, drains sanity).
+    pub hold_breath: bool,
+    /// Append game events (level start, orbs, spawn, seen, caught, won) to `events.log`.
+    pub event_log: bool,
+    /// Anti-camp: standing still too long sends the enemy to search where the player is.
+    pub anti_camp: bool,
+    /// Seconds without moving before the anti-camp nudge.
+    pub anti_camp_secs: f32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self { mouse_sens: 0.0025, invert_x: false, smooth_move: false, double_tap_sprint: false, scanlines: false, scanline_intensity: 0.3, crt_mask: false, auto_pause: true, pixel_perfect: false, hold_breath: true, exit_beam: true, event_log: false, anti_camp: false, anti_camp_secs: 15.0 }
+    }
+}
+
+impl Settings {
+    /// Read `path`; missing file, unknown keys or bad values keep the defaults.
+    pub fn load(path: &str) -> Self {
+        let mut s = Self::default();
+        let Ok(text) = fs::read_to_string(path) else { return s; };
+        for line in text.lines() {
+            let Some((k, v)) = line.split_once('=') else { continue; };
+            let (k, v) = (k.trim(), v.trim());
+            match k {
+                "mouse_sens" => if let Ok(x) = v.parse::<f32>() { s.mouse_sens = x.clamp(0.0002, 0.02); },
+                "invert_x" => if let Ok(b) = v.parse::<bool>() { s.invert_x = b; },
+                "smooth_move" => if let Ok(b) = v.parse::<bool>() { s.smooth_move = b; },
+                "double_tap_sprint" => if let Ok(b) = v.parse::<bool>() { s.double_tap_sprint = b; },
+                "scanlines" => if let Ok(b) = v.parse::<bool>() { s.scanlines = b; },
+                "scanline_intensity" => if let Ok(x) = v.parse::<f32>() { s.scanline_intensity = x.clamp(0.0, 1.0); },
+                "crt_mask" => if let Ok(b) = v.parse::<bool>() { s.crt_mask = b; },
+                "auto_pause" => if let Ok(b) = v.parse::<bool>() { s.auto_pause = b; },
+                "pixel_perfect" => if let Ok(b) = v.parse::<bool>() { s.pixel_perfect = b; },
+                "hold_breath" => if let Ok(b) = v.parse::<bool>() { s.hold_breath = b; },
+                "exit_beam" => if let Ok(b) = v.parse::<bool>() { s.exit_beam = b; },
+                "event_log" => if let Ok(b) = v.parse::<bool>() { s.event_log = b; },
+                "anti_camp" => if let Ok(b) = v.parse::<bool>() { s.anti_camp = b; },
+                "anti_camp_secs" => if let Ok(x) = v.parse::<f32>() { s.anti_camp_secs = x.clamp(3.0, 120.0); },
+                _ => {}
+            }
+        }
+        s
+    }
+
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        fs::write(path, format!(
+            "mouse_sens = {}\ninvert_x = {}\nsmooth_move = {}\ndouble_tap_sprint = {}\nscanlines = {}\nscanline_intensity = {}\ncrt_mask = {}\nauto_pause = {}\npixel_perfect = {}\nhold_breath = {}\nexit_beam = {}\nevent_log = {}\nanti_camp = {}\nanti_camp_secs = {}\n",
+            self.mouse_sens, self.invert_x, self.smooth_move, self.double_tap_sprint, self.scanlines, self.scanline_intensity, self.crt_mask, self.auto_pause, self.pixel_perfect, self.hold_breath, self.exit_beam, self.event_log, self.anti_camp, self.anti_camp_secs,
+        ))
+    }
+
+    /// Copy the input settings onto a (freshly created) player.
+    pub fn apply_to(&self, player: &mut Player) {
+        player.mouse_sens = self.mouse_sens;
+        player.invert_x = self.invert_x;
+        player.smooth_move = self.smooth_move;
+        player.double_tap_sprint = self.double_tap_sprint;
+        player.hold_breath_enabled = self.hold_breath;
+    }
+}
@@ -0,0 +1,72 @@
+//! Trauma-based camera shake (short view kicks, e.g. on orb pickup).
+//!
+//! Events add trauma; it decays linearly and the kick scales with trauma², so small hits
+//! stay subtle. Accumulated trauma is clamped so rapid events can't stack into nausea.
+
+/// Upper bound for accumulated trauma.
+pub const MAX_TRAUMA: f32 = 0.35;
+/// Trauma lost per second.
+pub const TRAUMA_DECAY: f32 = 2.5;
+/// View yaw kick (radians) at trauma 1.
+pub const MAX_YAW_KICK: f32 = 0.2;
+
+#[derive(Clone, Debug, Default)]
+pub struct CameraShake {
+    trauma: f32,
+    time: f32,
+}
+
+impl CameraShake {
+    pub fn new() -> Self { Self::default() }
+
+    /// Add `amount` of trauma, capped at `MAX_TRAUMA`.
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount.max(0.0)).min(MAX_TRAUMA);
+    }
+
+    pub fn trauma(&self) -> f32 { self.trauma }
+
+    pub fn update(&mut self, dt: f32) {
+        self.trauma = (self.trauma - TRAUMA_DECAY * dt).max(0.0);
+        self.time += dt;
+    }
+
+    /// Current yaw offset: smooth (sum of sines) so the kick is interpolated, not jittery.
+    pub fn yaw_offset(&self) -> f32 {
+        if self.trauma <= 0.0 { return 0.0; }
+        let t = self.time;
+        let wave = (t * 37.0).sin() * 0.7 + (t * 23.0).cos() * 0.3;
+        MAX_YAW_KICK * self.trauma * self.trauma * wave
+    }
+
+    pub fn reset(&mut self) { self.trauma = 0.0; }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rapid_pickups_stay_under_the_trauma_cap() {
+        let mut s = CameraShake::new();
+        s.add_trauma(0.25);
+        assert_eq!(s.trauma(), 0.25);
+        for _ in 0..10 {
+            s.add_trauma(0.25);
+            s.update(1.0 / 60.0);
+            assert!(s.trauma() <= MAX_TRAUMA);
+        }
+        assert!(s.yaw_offset().abs() <= MAX_YAW_KICK * MAX_TRAUMA * MAX_TRAUMA);
+        s.add_trauma(-1.0);
+        assert!(s.trauma() > 0.0);
+    }
+
+    #[test]
+    fn the_kick_dies_out_quickly() {
+        let mut s = CameraShake::new();
+        s.add_trauma(MAX_TRAUMA);
+        for _ in 0..12 { s.update(1.0 / 60.0); }
+        assert_eq!(s.trauma(), 0.0);
+        assert_eq!(s.yaw_offset(), 0.0);
+    }
+}
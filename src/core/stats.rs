@@ -0,0 +1,70 @@
+//! Per-level death counter persisted across runs.
+use std::collections::BTreeMap;
+use std::fs;
+
+pub const STATS_FILE: &str = "stats.txt";
+
+/// Times caught per level, keyed by the level's maze file name without `.txt` (e.g. `maze1`).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    deaths: BTreeMap<String, u32>,
+}
+
+fn level_key(file: &str) -> &str { file.trim_end_matches(".txt") }
+
+impl Stats {
+    /// Read `path` (`deaths.<level> = N` lines); a missing file or bad lines count as zero.
+    pub fn load(path: &str) -> Self {
+        let mut s = Self::default();
+        let Ok(text) = fs::read_to_string(path) else { return s; };
+        for line in text.lines() {
+            let Some((k, v)) = line.split_once('=') else { continue; };
+            let Some(level) = k.trim().strip_prefix("deaths.") else { continue; };
+            if let Ok(n) = v.trim().parse::<u32>() { s.deaths.insert(level.to_string(), n); }
+        }
+        s
+    }
+
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        let text: String = self.deaths.iter().map(|(k, n)| format!("deaths.{} = {}\n", k, n)).collect();
+        fs::write(path, text)
+    }
+
+    /// Deaths recorded for the level loaded from `file`.
+    pub fn deaths(&self, file: &str) -> u32 {
+        self.deaths.get(level_key(file)).copied().unwrap_or(0)
+    }
+
+    /// Count one more death on the level loaded from `file`; call once per caught transition.
+    pub fn record_death(&mut self, file: &str) {
+        let n = self.deaths.entry(level_key(file).to_string()).or_insert(0);
+        *n = n.saturating_add(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_file_loads_as_no_deaths() {
+        let s = Stats::load("definitely_missing_stats.txt");
+        assert_eq!(s, Stats::default());
+        assert_eq!(s.deaths("maze1.txt"), 0);
+    }
+
+    #[test]
+    fn deaths_survive_a_save_and_load() {
+        let path = std::env::temp_dir().join(format!("stats_test_{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut s = Stats::default();
+        s.record_death("maze1.txt");
+        s.record_death("maze1.txt");
+        s.record_death("maze3");
+        s.save(path).unwrap();
+        let loaded = Stats::load(path);
+        let _ = fs::remove_file(path);
+        assert_eq!(loaded, s);
+        assert_eq!((loaded.deaths("maze1"), loaded.deaths("maze3.txt")), (2, 1));
+    }
+}
@@ -0,0 +1,232 @@
+//! Maze tile legend: what each map char means to movement, AI, sight and rendering.
+//!
+//! Every subsystem asks `tiles()` instead of matching chars itself, so a new tile is one
+//! entry in `TileTable::builtin`. Chars not in the table behave as plain walls.
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use crate::core::maze::{Maze, TRAP, BANISH};
+use crate::core::player::Player;
+
+/// Floor tile.
+pub const FLOOR: char = ' ';
+/// Exit tile: solid for the player (touching it wins), open to the enemy and to sight.
+pub const EXIT: char = 'g';
+/// Decoy exit: looks and behaves like `EXIT`, but touching it doesn't win.
+pub const DECOY_EXIT: char = 'd';
+/// Explicit orb spawn marker: plain floor that always gets an orb at level start.
+pub const ORB_SPAWN: char = '*';
+/// Safe room floor: while the player stands here an unseeing enemy forgets them faster.
+pub const SAFE_ROOM: char = 'R';
+/// Shallow water floor: walkable, splashing footsteps.
+pub const WATER: char = '~';
+/// Metal grating floor: walkable, clanking footsteps.
+pub const METAL: char = 'M';
+/// Generic wall; unknown chars are loaded as this.
+pub const WALL: char = '#';
+
+/// How far (in cells, from the player to the tile centre) an interactable tile can be used.
+pub const INTERACT_REACH: f32 = 1.3;
+/// Half-angle (radians) of the cone in front of the player that counts as "facing" a tile.
+pub const INTERACT_CONE: f32 = 0.6;
+
+/// Action run when the player uses an interactable tile.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Interaction {
+    /// Send the enemy away for a while (banish switch).
+    Banish,
+}
+
+impl Interaction {
+    /// Verb shown in the HUD prompt.
+    pub fn label(self) -> &'static str {
+        match self {
+            Interaction::Banish => "banish the enemy",
+        }
+    }
+}
+
+/// What the floor sounds like underfoot; picks the player's footstep sample set.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Surface {
+    #[default]
+    Default,
+    Water,
+    Metal,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TileProps {
+    pub player_walkable: bool,
+    pub enemy_walkable: bool,
+    pub blocks_los: bool,
+    /// Texture used when the tile is drawn as a wall; `None` = pick a generic wall variant.
+    pub tex_key: Option<char>,
+    /// Reaching it wins the level.
+    pub is_exit: bool,
+    /// Drawn like an exit (world, minimap) without being one.
+    pub is_decoy: bool,
+    pub is_hazard: bool,
+    /// Hiding spot: speeds up the enemy losing interest.
+    pub is_safe: bool,
+    /// Usable with the interact key when in reach and faced.
+    pub interaction: Option<Interaction>,
+    /// Footstep surface when walked on.
+    pub surface: Surface,
+}
+
+impl TileProps {
+    pub const fn floor() -> Self {
+        Self { player_walkable: true, enemy_walkable: true, blocks_los: false, tex_key: None, is_exit: false, is_decoy: false, is_hazard: false, is_safe: false, interaction: None, surface: Surface::Default }
+    }
+    pub const fn wall() -> Self {
+        Self { player_walkable: false, enemy_walkable: false, blocks_los: true, tex_key: None, is_exit: false, is_decoy: false, is_hazard: false, is_safe: false, interaction: None, surface: Surface::Default }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TileTable {
+    entries: HashMap<char, TileProps>,
+}
+
+impl TileTable {
+    /// Built-in legend used by the game.
+    pub fn builtin() -> Self {
+        Self { entries: HashMap::new() }
+            .with(FLOOR, TileProps::floor())
+            .with(ORB_SPAWN, TileProps::floor())
+            .with(SAFE_ROOM, TileProps { is_safe: true, ..TileProps::floor() })
+            .with(WATER, TileProps { surface: Surface::Water, ..TileProps::floor() })
+            .with(METAL, TileProps { surface: Surface::Metal, ..TileProps::floor() })
+            .with(TRAP, TileProps { is_hazard: true, ..TileProps::floor() })
+            .with(EXIT, TileProps { player_walkable: false, tex_key: Some(EXIT), is_exit: true, ..TileProps::floor() })
+            .with(DECOY_EXIT, TileProps { player_walkable: false, tex_key: Some(EXIT), is_decoy: true, ..TileProps::floor() })
+            .with(BANISH, TileProps { tex_key: Some(BANISH), interaction: Some(Interaction::Banish), ..TileProps::wall() })
+            .with(WALL, TileProps::wall())
+            .with('+', TileProps::wall())
+            .with('-', TileProps::wall())
+            .with('|', TileProps::wall())
+    }
+
+    /// Add or replace the entry for `ch`.
+    pub fn with(mut self, ch: char, props: TileProps) -> Self {
+        self.entries.insert(ch, props);
+        self
+    }
+
+    /// True if `ch` has its own entry (the maze loader keeps only these chars).
+    pub fn knows(&self, ch: char) -> bool { self.entries.contains_key(&ch) }
+
+    pub fn get(&self, ch: char) -> TileProps {
+        self.entries.get(&ch).copied().unwrap_or(TileProps::wall())
+    }
+
+    pub fn player_walkable(&self, ch: char) -> bool { self.get(ch).player_walkable }
+    pub fn enemy_walkable(&self, ch: char) -> bool { self.get(ch).enemy_walkable }
+    pub fn blocks_los(&self, ch: char) -> bool { self.get(ch).blocks_los }
+    pub fn tex_key(&self, ch: char) -> Option<char> { self.get(ch).tex_key }
+    pub fn is_exit(&self, ch: char) -> bool { self.get(ch).is_exit }
+    /// True exit or decoy: anything the player should not be able to tell apart by looking.
+    pub fn looks_like_exit(&self, ch: char) -> bool { let t = self.get(ch); t.is_exit || t.is_decoy }
+    pub fn is_hazard(&self, ch: char) -> bool { self.get(ch).is_hazard }
+    pub fn is_safe(&self, ch: char) -> bool { self.get(ch).is_safe }
+    pub fn interaction(&self, ch: char) -> Option<Interaction> { self.get(ch).interaction }
+    pub fn surface(&self, ch: char) -> Surface { self.get(ch).surface }
+}
+
+/// Footstep surface under world position `(x, y)`; off the map counts as `Surface::Default`.
+pub fn surface_at(maze: &Maze, block: f32, x: f32, y: f32) -> Surface {
+    let (i, j) = ((x / block).floor(), (y / block).floor());
+    if i < 0.0 || j < 0.0 { return Surface::Default; }
+    maze.get(j as usize).and_then(|row| row.get(i as usize)).map_or(Surface::Default, |&c| tiles().surface(c))
+}
+
+/// Shared built-in table.
+pub fn tiles() -> &'static TileTable {
+    static TABLE: OnceLock<TileTable> = OnceLock::new();
+    TABLE.get_or_init(TileTable::builtin)
+}
+
+/// Interactable cell `(i, j)` the player is in reach of and roughly facing; the most centred
+/// one wins if several qualify. `block` is the cell size in world units.
+pub fn find_interactable_in_front(maze: &Maze, player: &Player, block: f32) -> Option<(usize, usize)> {
+    let (ci, cj) = ((player.pos.x / block).floor() as isize, (player.pos.y / block).floor() as isize);
+    let r = INTERACT_REACH.ceil() as isize;
+    let mut best: Option<((usize, usize), f32)> = None;
+    for j in (cj - r)..=(cj + r) {
+        for i in (ci - r)..=(ci + r) {
+            if i < 0 || j < 0 { continue; }
+            let (iu, ju) = (i as usize, j as usize);
+            let Some(&c) = maze.get(ju).and_then(|row| row.get(iu)) else { continue };
+            if tiles().interaction(c).is_none() { continue; }
+            let (dx, dy) = ((i as f32 + 0.5) * block - player.pos.x, (j as f32 + 0.5) * block - player.pos.y);
+            if dx * dx + dy * dy > (INTERACT_REACH * block).powi(2) { continue; }
+            let off = (dy.atan2(dx) - player.a + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU) - std::f32::consts::PI;
+            if off.abs() > INTERACT_CONE { continue; }
+            if best.is_none_or(|(_, b)| off.abs() < b) { best = Some(((iu, ju), off.abs())); }
+        }
+    }
+    best.map(|(cell, _)| cell)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_tiles_are_classified() {
+        let t = TileTable::builtin();
+        for c in [FLOOR, ORB_SPAWN, SAFE_ROOM, WATER, METAL, TRAP] {
+            assert!(t.player_walkable(c) && t.enemy_walkable(c) && !t.blocks_los(c), "{:?}", c);
+        }
+        for c in [WALL, '+', '-', '|', BANISH] {
+            assert!(!t.player_walkable(c) && !t.enemy_walkable(c) && t.blocks_los(c), "{:?}", c);
+        }
+        // The exit stops the player but not the enemy or sight
+        assert!(!t.player_walkable(EXIT) && t.enemy_walkable(EXIT) && !t.blocks_los(EXIT));
+        assert!(t.is_exit(EXIT) && !t.is_exit(DECOY_EXIT));
+        assert!(t.looks_like_exit(DECOY_EXIT));
+        assert_eq!(t.tex_key(DECOY_EXIT), Some(EXIT));
+        assert!(t.is_hazard(TRAP) && !t.is_hazard(FLOOR));
+        assert!(t.is_safe(SAFE_ROOM));
+        assert_eq!(t.surface(WATER), Surface::Water);
+        assert_eq!(t.interaction(BANISH), Some(Interaction::Banish));
+    }
+
+    #[test]
+    fn unknown_chars_are_walls_until_given_an_entry() {
+        let t = TileTable::builtin();
+        assert!(!t.knows('D'));
+        assert_eq!(t.get('D'), TileProps::wall());
+        let door = TileProps { tex_key: Some('1'), blocks_los: true, ..TileProps::floor() };
+        let t = t.with('D', door);
+        assert!(t.knows('D'));
+        assert!(t.player_walkable('D') && t.blocks_los('D'));
+        assert_eq!(t.tex_key('D'), Some('1'));
+        // Replacing a built-in entry
+        let t = t.with(FLOOR, TileProps { is_hazard: true, ..TileProps::floor() });
+        assert!(t.is_hazard(FLOOR));
+    }
+
+    #[test]
+    fn finds_the_switch_the_player_faces() {
+        use std::f32::consts::{FRAC_PI_2, PI};
+        let maze: Maze = ["#####", "#  B#", "#   #", "#####"].iter().map(|r| r.chars().collect()).collect();
+        let at = |x: f32, y: f32, a: f32| find_interactable_in_front(&maze, &Player::new(x * 64.0, y * 64.0, a), 64.0);
+        // Next to the switch at (3, 1), facing it
+        assert_eq!(at(2.5, 1.5, 0.0), Some((3, 1)));
+        // Facing away, or facing a plain wall
+        assert_eq!(at(2.5, 1.5, PI), None);
+        assert_eq!(at(2.5, 1.5, -FRAC_PI_2), None);
+        // Facing it but out of reach
+        assert_eq!(at(1.5, 1.5, 0.0), None);
+    }
+
+    #[test]
+    fn the_better_centred_switch_wins() {
+        let maze: Maze = ["#####", "#  B#", "#  B#", "#####"].iter().map(|r| r.chars().collect()).collect();
+        let at = |a: f32| find_interactable_in_front(&maze, &Player::new(2.7 * 64.0, 1.9 * 64.0, a), 64.0);
+        // Both switches are in reach and inside the cone for these headings
+        assert_eq!(at(0.07), Some((3, 1)));
+        assert_eq!(at(0.11), Some((3, 2)));
+    }
+}
@@ -16,57 +16,229 @@ mod audio;
 
 use crate::render::textures::TextureManager;
 use raylib::prelude::*;
-use crate::audio::manager::AudioManager;
+use crate::audio::manager::{AudioManager, ReinitBackoff};
 use std::thread;
 use std::time::Duration;
 use crate::render::framebuffer::Framebuffer;
-use crate::core::maze::{Maze, load_maze};
+use crate::core::maze::{Maze, cell_at_world, load_maze_with, BANISH};
+use crate::core::tiles::{tiles, find_interactable_in_front, Interaction, Surface, surface_at, FLOOR, ORB_SPAWN, WALL};
 use crate::core::player::Player;
-use crate::core::process_events::process_events;
+use crate::core::process_events::{process_events, camera_pos};
 use crate::render::casters::cast_ray;
-use crate::render::render3d::render_3d;
+use crate::render::render3d::{render_3d, WallStyle, MAX_RAY_FACTOR, FLAT_COLUMN_PX, draw_ground_ring, draw_ground_shadow, draw_light_beam, draw_depth_view, draw_trap_markers};
 use crate::render::sprites::{draw_sprite_world, draw_sprites_sorted};
+use crate::render::flashlight::{make_gradient_texture, draw_flashlight};
+use crate::render::budget::{EffectBudget, Effect};
 use rand::seq::SliceRandom;
-use crate::core::enemy::Enemy;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use crate::core::enemy::{auto_patrol_route, orb_cluster_target, Enemy, EnemyKind};
+use crate::core::difficulty::{Difficulty, orbs_to_unlock};
+use crate::core::stats::{Stats, STATS_FILE};
+use crate::core::aggression::{Aggression, AGGRESSION_FILE};
+use crate::core::events::{GameEvent, GameEventSink, LogSink, NullSink, EVENT_LOG_FILE};
+use crate::core::distance_field::DistanceField;
+use crate::core::settings::{Settings, SETTINGS_FILE};
+use crate::core::assets::{AssetReport, AssetSource};
+use crate::core::meta::{Meta, META_FILE, THEME_UNLOCK_ORBS, BONUS_UNLOCK_ORBS};
+use crate::core::shake::CameraShake;
 use std::path::Path;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
-enum GameState { Menu, Playing, Escaping, Won, Caught }
+enum GameState { Menu, Playing, Escaping, Paused, Won, Caught }
+
+impl GameState {
+    // Estados en los que corre la partida (entrada, enemigo, capturas); Paused la congela
+    fn in_play(self) -> bool { matches!(self, GameState::Playing | GameState::Escaping) }
+}
 
 // Menu state: simple "Play" entry that cycles through preset levels.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 enum MenuItem { Play }
 
+// Cómo se comporta el enemigo durante la huida (Escaping)
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum EscapeMode {
+    Unchanged,    // igual que al recoger orbs
+    Enrage(f32),  // persigue con este multiplicador de velocidad
+    Despawn,      // desaparece: huida tranquila
+}
+
+impl EscapeMode {
+    // Multiplicador de velocidad de persecución en el estado dado
+    fn chase_scale(self, state: GameState) -> f32 {
+        match self {
+            EscapeMode::Enrage(k) if state == GameState::Escaping => k,
+            _ => 1.0,
+        }
+    }
+}
+
 #[derive(Clone)]
 struct LevelCfg {
     file: &'static str,
     enemy_enabled: bool,
     show_minimap: bool,
     brightness: f32, // multiplicador para paredes (líneas azules más intensas)
+    block: f32,      // tamaño de celda en unidades de mundo
+    music: Option<&'static str>, // pista propia del nivel (None = lista automática)
+    spawn_min_cells: f32, // distancia mínima (en celdas) entre el jugador y el spawn del enemigo
+    enemy_turn_rate: f32, // giro máximo del enemigo (rad/s): menor = más fácil de esquivar
+    alert_radius_cells: f32, // al ver al jugador, avisa a enemigos en este radio (celdas; 0 = sin aviso)
+    mist: f32, // intensidad de la niebla en el horizonte (0 = sin niebla)
+    escape_sight: f32, // alcance de visión del enemigo durante la huida (multiplicador; 1 = igual)
+    orb_fill_random: bool, // con marcas '*' en el mapa, completar con orbs al azar (false = solo marcas)
+    safe_decay: f32, // en una sala segura ('R') el enemigo olvida este múltiplo más rápido (1 = sin efecto)
+    extraction: bool, // con todos los orbs se gana volviendo a la celda de inicio, no en la salida 'g'
+    route_patrol: bool, // el enemigo patrulla una ruta fija calculada del laberinto (si no, deambula)
+    spawn_unseen: bool, // el enemigo no aparece en una celda que el jugador esté viendo (si hay otra opción)
+    arcade: bool, // contrarreloj: los orbs reaparecen y se puntúa cuántos recoges (sin salida)
+    orb_lure: bool, // los orbs atraen al enemigo: de vez en cuando patrulla hacia el grupo de orbs más cercano
+    toroidal: bool, // los bordes se conectan (sin sellar el perímetro); por ahora solo movimiento y render
+    escape_mode: EscapeMode, // comportamiento del enemigo durante la huida
+    light_speed: Option<(f32, f32)>, // (iluminado, a oscuras): multiplicador de la velocidad de persecución según la linterna
+}
+
+impl LevelCfg {
+    // Multiplicador del alcance de visión del enemigo en el estado dado (reducido en la huida)
+    fn sight_scale(&self, state: GameState) -> f32 {
+        if state == GameState::Escaping { self.escape_sight } else { 1.0 }
+    }
+    // Multiplicador de persecución según esté el enemigo bajo la linterna (1 sin enemigo fotosensible)
+    fn light_scale(&self, lit: bool) -> f32 {
+        match self.light_speed { Some((l, d)) => if lit { l } else { d }, None => 1.0 }
+    }
+    // ¿Escapa el jugador en (x, y)? Extracción: de vuelta en la celda de inicio; si no, tocando una salida que cuenta
+    fn escaped(&self, block: f32, x: f32, y: f32, spawn: (usize, usize), exit_touch: bool) -> bool {
+        if self.extraction { at_spawn_cell(block, x, y, spawn) } else { exit_touch }
+    }
 }
 
 fn level_cfg(idx: i32) -> LevelCfg {
     match idx {
     // L1: enemigo activo y minimapa ON; brillo base 1.0
-    0 => LevelCfg { file: "maze1.txt", enemy_enabled: true,  show_minimap: true,  brightness: 1.0 },
+    0 => LevelCfg { file: "maze1.txt", enemy_enabled: true,  show_minimap: true,  brightness: 1.0,  block: BLOCK, music: None, spawn_min_cells: ENEMY_SPAWN_MIN_CELLS, enemy_turn_rate: 2.4, alert_radius_cells: 6.0, mist: 0.35, escape_sight: 0.85, orb_fill_random: true, safe_decay: 3.0, extraction: false, route_patrol: false, spawn_unseen: true, arcade: false, orb_lure: false, toroidal: false, escape_mode: EscapeMode::Unchanged, light_speed: None },
     // L2: enemigo ON; brillo un poco más fuerte
-    1 => LevelCfg { file: "maze2.txt", enemy_enabled: true,  show_minimap: true,  brightness: 1.15 },
+    1 => LevelCfg { file: "maze2.txt", enemy_enabled: true,  show_minimap: true,  brightness: 1.15, block: BLOCK, music: None, spawn_min_cells: ENEMY_SPAWN_MIN_CELLS, enemy_turn_rate: 2.8, alert_radius_cells: 8.0, mist: 0.5, escape_sight: 0.6, orb_fill_random: true, safe_decay: 3.0, extraction: false, route_patrol: false, spawn_unseen: true, arcade: false, orb_lure: true, toroidal: false, escape_mode: EscapeMode::Unchanged, light_speed: Some((0.7, 1.25)) },
     // L3: enemigo ON; con minimapa; un poco más intenso
-    2 => LevelCfg { file: "maze3.txt", enemy_enabled: true,  show_minimap: true,  brightness: 1.25 },
-    _ => LevelCfg { file: "maze1.txt", enemy_enabled: true,  show_minimap: true,  brightness: 1.0 },
+    2 => LevelCfg { file: "maze3.txt", enemy_enabled: true,  show_minimap: true,  brightness: 1.25, block: L3_BLOCK, music: None, spawn_min_cells: ENEMY_SPAWN_MIN_CELLS, enemy_turn_rate: 3.4, alert_radius_cells: 10.0, mist: 0.65, escape_sight: 0.8, orb_fill_random: true, safe_decay: 3.0, extraction: false, route_patrol: true, spawn_unseen: true, arcade: false, orb_lure: false, toroidal: false, escape_mode: EscapeMode::Enrage(1.2), light_speed: None },
+    _ => LevelCfg { file: "maze1.txt", enemy_enabled: true,  show_minimap: true,  brightness: 1.0,  block: BLOCK, music: None, spawn_min_cells: ENEMY_SPAWN_MIN_CELLS, enemy_turn_rate: 2.4, alert_radius_cells: 6.0, mist: 0.35, escape_sight: 0.85, orb_fill_random: true, safe_decay: 3.0, extraction: false, route_patrol: false, spawn_unseen: true, arcade: false, orb_lure: false, toroidal: false, escape_mode: EscapeMode::Unchanged, light_speed: None },
     }
 }
 
-// Tamaño de celda en unidades de mundo
+// Modo práctica (P en el menú): sin enemigo y con el minimapa completo (orbs y salida) desde el
+// inicio para aprender rutas. La partida no puntúa (no suma a la meta-progresión).
+fn practice_cfg(mut cfg: LevelCfg) -> LevelCfg {
+    cfg.enemy_enabled = false;
+    cfg.show_minimap = true;
+    cfg
+}
+
+// Partida ganada: suma a la meta-progresión y a la dificultad adaptativa si están activas.
+// Las de práctica no puntúan. Devuelve qué hay que guardar: (meta, agresividad)
+fn record_won_run(meta: &mut Meta, aggression: &mut Aggression, score: usize, times_spotted: u32, practice: bool) -> (bool, bool) {
+    if practice { return (false, false); }
+    meta.record_win(score as u32);
+    if aggression.enabled { aggression.record_level(times_spotted); }
+    (meta.enabled, aggression.enabled)
+}
+
+// Auto-pausa por foco: en partida y sin foco pasa a Paused (guardando a qué estado volver);
+// en Paused con foco lo recupera. Some(true) al pausar, Some(false) al reanudar, None si nada cambia
+fn focus_pause(state: &mut GameState, resume: &mut GameState, auto_pause: bool, focused: bool) -> Option<bool> {
+    if auto_pause && !focused && state.in_play() {
+        *resume = *state;
+        *state = GameState::Paused;
+        Some(true)
+    } else if *state == GameState::Paused && focused {
+        *state = *resume;
+        Some(false)
+    } else {
+        None
+    }
+}
+
+// Anti-campeo: suma el tiempo quieto (moverse lo pone a 0); true al pasar `limit` segundos con la
+// regla activa, y vuelve a contar desde 0 para el siguiente aviso
+fn anti_camp_due(idle: &mut f32, moved: bool, dt: f32, enabled: bool, limit: f32) -> bool {
+    *idle = if moved { 0.0 } else { *idle + dt };
+    if !enabled || *idle < limit { return false; }
+    *idle = 0.0;
+    true
+}
+
+// Flanco de subida del enemigo: true solo en la primera aparición del nivel (no al volver del destierro)
+fn spawn_announce_edge(active: bool, announced: &mut bool) -> bool {
+    if !active || *announced { return false; }
+    *announced = true;
+    true
+}
+
+// Captura: pasa a Caught y apunta la muerte solo en el flanco; ya en Caught no vuelve a contar
+fn catch_player(state: &mut GameState, stats: &mut Stats, file: &str) -> bool {
+    if !state.in_play() { return false; }
+    *state = GameState::Caught;
+    stats.record_death(file);
+    true
+}
+
+// Extracción: ¿está (x, y) en la celda de inicio?
+fn at_spawn_cell(block: f32, x: f32, y: f32, spawn: (usize, usize)) -> bool {
+    x >= 0.0 && y >= 0.0 && ((x / block) as usize, (y / block) as usize) == spawn
+}
+
+// Tamaño de celda por defecto en unidades de mundo (cada nivel puede usar otro vía `LevelCfg::block`)
 pub const BLOCK: f32 = 64.0;
+// L3 usa celdas más grandes: pasillos más anchos y espacio para esquivar en el nivel más duro
+const L3_BLOCK: f32 = 80.0;
 
 // ---------- ORBS ----------
-struct Orb { x: f32, y: f32, active: bool }
+struct Orb { x: f32, y: f32, active: bool, respawn: f32 } // respawn: segundos hasta reaparecer (modo arcade; 0 = no)
+
+// Radio de recogida de orbs (unidades de mundo)
+const ORB_PICKUP_RADIUS: f32 = 18.0;
+// Penalización al recoger: durante unos segundos el enemigo detecta desde más lejos y oye al jugador
+const GRAB_REVEAL_SECS: f32 = 1.0;
+// Trauma añadida a la cámara por cada orb recogido (el total se limita en CameraShake)
+const ORB_PICKUP_TRAUMA: f32 = 0.25;
+const GRAB_REVEAL_BOOST: f32 = 0.5;
+
+// Refuerzo de detección del enemigo con `left` segundos de penalización por recoger
+fn grab_reveal_boost(left: f32) -> f32 { if left > 0.0 { GRAB_REVEAL_BOOST } else { 0.0 } }
+// Aviso de aparición del enemigo: duración del destello/distorsión y trauma de cámara
+const SPAWN_ANNOUNCE_SECS: f32 = 0.8;
+const SPAWN_ANNOUNCE_TRAUMA: f32 = 0.5;
+// Segundos que parpadea el aviso "salida abierta" al alcanzar los orbs necesarios (sin tenerlos todos)
+const UNLOCK_HINT_SECS: f32 = 2.5;
+// Radio (celdas) que agrupa orbs cercanos en un mismo cebo para el enemigo (LevelCfg::orb_lure)
+const ORB_LURE_CLUSTER_CELLS: f32 = 4.0;
+// Alcance (celdas, atraviesa paredes) del ruido de los pasos del jugador al andar / esprintar
+const FOOTSTEP_NOISE_CELLS: f32 = 3.5;
+const FOOTSTEP_NOISE_SPRINT_CELLS: f32 = 7.0;
+// Cordura que cuesta por segundo contener la respiración; por debajo de BREATH_MIN_SANITY hay que soltarla
+const BREATH_SANITY_DRAIN: f32 = 0.05;
+const BREATH_MIN_SANITY: f32 = 0.1;
+// Cada cuánto (s) se comprueba que el dispositivo de audio sigue conectado
+const AUDIO_CHECK_SECS: f32 = 3.0;
+// Máximo de sprites rasterizados por frame (los más cercanos); el resto, lejanos y diminutos, se omite
+const MAX_DRAWN_SPRITES: usize = 64;
+// Interruptor de destierro ('B', se usa con la tecla de interacción): recarga y tiempo que el enemigo queda fuera
+const BANISH_RECHARGE_SECS: f32 = 25.0;
+const BANISH_AWAY_SECS: f32 = 8.0;
+
+// Usa el interruptor si está cargado y el enemigo activo: lo destierra y arranca recarga y ausencia
+fn try_banish(enemy: &mut Enemy, recharge: &mut f32, away: &mut f32) -> bool {
+    if !enemy.active || *recharge > 0.0 { return false; }
+    enemy.banish();
+    *recharge = BANISH_RECHARGE_SECS;
+    *away = BANISH_AWAY_SECS;
+    true
+}
 
 fn is_free_cell(maze: &Maze, i: usize, j: usize) -> bool {
     if j >= maze.len() || i >= maze[j].len() { return false; }
-    let c = maze[j][i];
-    c == ' ' || c == 'g'
+    let t = tiles().get(maze[j][i]);
+    t.enemy_walkable && !t.is_hazard
 }
 fn is_safe_cell(maze: &Maze, i: usize, j: usize) -> bool {
     if !is_free_cell(maze, i, j) { return false; }
@@ -77,33 +249,189 @@ fn is_safe_cell(maze: &Maze, i: usize, j: usize) -> bool {
         if ni < 0 || nj < 0 { continue; }
         let (ni, nj) = (ni as usize, nj as usize);
         if nj < maze.len() && ni < maze[nj].len() {
-            let c = maze[nj][ni];
-            if c != ' ' && c != 'g' { return false; }
+            if !is_free_cell(maze, ni, nj) { return false; }
         }
     }
     true
 }
-fn spawn_orbs_in_empty_cells(maze: &Maze, block: f32, count: usize) -> Vec<Orb> {
+fn spawn_orbs_in_empty_cells(maze: &Maze, block: f32, count: usize, rng: &mut StdRng) -> Vec<Orb> {
     let mut free_cells: Vec<(usize,usize)> = Vec::new();
     for (j, row) in maze.iter().enumerate() {
-        for (i, _c) in row.iter().enumerate() {
-            if is_safe_cell(maze, i, j) {
+        for (i, &c) in row.iter().enumerate() {
+            if c != ORB_SPAWN && is_safe_cell(maze, i, j) {
                 free_cells.push((i, j));
             }
         }
     }
-    let mut rng = rand::thread_rng();
-    free_cells.shuffle(&mut rng);
+    free_cells.shuffle(rng);
     free_cells.into_iter()
         .take(count)
         .map(|(i,j)| Orb {
             x: (i as f32 + 0.5) * block,
             y: (j as f32 + 0.5) * block,
             active: true,
+            respawn: 0.0,
         })
         .collect()
 }
 
+// Orbs en las marcas '*' del mapa (en orden de lectura)
+fn orbs_at_markers(maze: &Maze, block: f32) -> Vec<Orb> {
+    maze.iter().enumerate()
+        .flat_map(|(j, row)| row.iter().enumerate().filter(|(_, c)| **c == ORB_SPAWN).map(move |(i, _)| (i, j)))
+        .map(|(i, j)| Orb { x: (i as f32 + 0.5) * block, y: (j as f32 + 0.5) * block, active: true, respawn: 0.0 })
+        .collect()
+}
+
+// ---------- ARCADE ----------
+// Modo arcade: partida a contrarreloj; los orbs recogidos reaparecen en otra celda tras un rato
+const ARCADE_SECS: f32 = 120.0;
+const ORB_RESPAWN_SECS: f32 = 6.0;
+// Distancia mínima (celdas) entre el jugador y un orb que reaparece
+const ORB_RESPAWN_MIN_CELLS: f32 = 3.0;
+
+// Celda segura al azar para un orb que reaparece: sin otro orb activo y lejos del jugador
+fn orb_respawn_cell(maze: &Maze, orbs: &[Orb], block: f32, px: f32, py: f32, rng: &mut StdRng) -> Option<(usize, usize)> {
+    let center = |i: usize, j: usize| ((i as f32 + 0.5) * block, (j as f32 + 0.5) * block);
+    let min_d = ORB_RESPAWN_MIN_CELLS * block;
+    let cells: Vec<(usize, usize)> = maze.iter().enumerate()
+        .flat_map(|(j, row)| (0..row.len()).map(move |i| (i, j)))
+        .filter(|&(i, j)| is_safe_cell(maze, i, j))
+        .filter(|&(i, j)| { let (x, y) = center(i, j); (x - px).powi(2) + (y - py).powi(2) >= min_d * min_d })
+        .filter(|&(i, j)| { let (x, y) = center(i, j); !orbs.iter().any(|o| o.active && (o.x - x).abs() < 1.0 && (o.y - y).abs() < 1.0) })
+        .collect();
+    cells.choose(rng).copied()
+}
+
+// Cuenta atrás de los orbs recogidos; al llegar a 0 reaparecen en una celda segura lejos de (px, py)
+fn tick_orb_respawns(orbs: &mut [Orb], maze: &Maze, block: f32, px: f32, py: f32, dt: f32, rng: &mut StdRng) {
+    for k in 0..orbs.len() {
+        if orbs[k].active || orbs[k].respawn <= 0.0 { continue; }
+        orbs[k].respawn -= dt;
+        if orbs[k].respawn > 0.0 { continue; }
+        match orb_respawn_cell(maze, orbs, block, px, py, rng) {
+            Some((i, j)) => orbs[k] = Orb { x: (i as f32 + 0.5) * block, y: (j as f32 + 0.5) * block, active: true, respawn: 0.0 },
+            None => orbs[k].respawn = 1.0, // sin sitio libre: reintentar en un segundo
+        }
+    }
+}
+
+// Recoge los orbs activos al alcance de (px, py): suma al marcador, programa la reaparición
+// (`respawn_secs`, 0 fuera del modo arcade) y emite OrbCollected por cada uno. Devuelve cuántos.
+fn collect_orbs(orbs: &mut [Orb], px: f32, py: f32, respawn_secs: f32, score: &mut usize, events: &mut dyn GameEventSink) -> usize {
+    let mut picked = 0;
+    for o in orbs.iter_mut().filter(|o| o.active) {
+        let (dx, dy) = (o.x - px, o.y - py);
+        if (dx*dx + dy*dy).sqrt() > ORB_PICKUP_RADIUS { continue; }
+        o.active = false;
+        o.respawn = respawn_secs;
+        *score += 1;
+        picked += 1;
+        events.emit(GameEvent::OrbCollected { score: *score });
+    }
+    picked
+}
+
+// ---------- SPAWN ENEMIGO ----------
+// Distancia mínima por defecto (en celdas) entre el jugador y el spawn del enemigo
+const ENEMY_SPAWN_MIN_CELLS: f32 = 8.0;
+
+// Margen angular (rad) fuera del FOV que aún cuenta como "a la vista" (el sprite tiene anchura)
+const SPAWN_VIEW_MARGIN: f32 = 0.15;
+
+// ¿Ve el jugador (en (px, py) mirando a `pa` con apertura `fov`) el centro de la celda (i, j)?
+fn cell_in_view(maze: &Maze, block: f32, px: f32, py: f32, pa: f32, fov: f32, (i, j): (usize, usize)) -> bool {
+    let (dx, dy) = ((i as f32 + 0.5) * block - px, (j as f32 + 0.5) * block - py);
+    let ad = (dy.atan2(dx) - pa + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU) - std::f32::consts::PI;
+    if ad.abs() > fov * 0.5 + SPAWN_VIEW_MARGIN { return false; }
+    let steps = ((dx*dx + dy*dy).sqrt() / (block * 0.25)).ceil().max(1.0) as i32;
+    for k in 1..steps {
+        let t = k as f32 / steps as f32;
+        let (ci, cj) = (((px + dx * t) / block).floor() as usize, ((py + dy * t) / block).floor() as usize);
+        if (ci, cj) == (i, j) { break; }
+        let cell = maze.get(cj).and_then(|r| r.get(ci)).copied().unwrap_or(WALL);
+        if tiles().blocks_los(cell) { return false; }
+    }
+    true
+}
+
+// Candidatos de spawn sin los que el jugador ve ahora; si todos están a la vista, se quedan todos
+fn unseen_cells(cells: &[(usize,usize)], maze: &Maze, block: f32, player: &Player) -> Vec<(usize,usize)> {
+    let unseen: Vec<(usize,usize)> = cells.iter().copied()
+        .filter(|&c| !cell_in_view(maze, block, player.pos.x, player.pos.y, player.a, player.fov, c))
+        .collect();
+    if unseen.is_empty() { cells.to_vec() } else { unseen }
+}
+
+// Regla única de spawn: la celda de `cells` más lejana a (px, py) (empates al azar con `rng`)
+// y si respeta la distancia mínima de `min_cells`. Si ninguna la respeta, sigue siendo la más lejana.
+fn pick_spawn_cell(cells: &[(usize,usize)], block: f32, px: f32, py: f32, min_cells: f32, rng: &mut StdRng) -> Option<((usize,usize), bool)> {
+    let mut best: Vec<(usize,usize)> = Vec::new();
+    let mut best_d2 = -1.0f32;
+    for &(i, j) in cells {
+        let dx = (i as f32 + 0.5) * block - px;
+        let dy = (j as f32 + 0.5) * block - py;
+        let d2 = dx*dx + dy*dy;
+        if d2 > best_d2 { best.clear(); best_d2 = d2; }
+        if d2 == best_d2 { best.push((i, j)); }
+    }
+    let min = min_cells * block;
+    best.choose(rng).map(|&c| (c, best_d2 >= min * min))
+}
+
+// ---------- SALIDA VISTA ----------
+// Regla opcional: la salida solo cuenta si se vio hace poco (segundos de margen y alcance de la linterna en celdas)
+const EXIT_SEEN_GRACE: f32 = 1.5;
+const EXIT_SIGHT_CELLS: f32 = 8.0;
+
+// Margen de "salida vista": se recarga al verla y se consume con el tiempo
+fn tick_exit_seen(timer: f32, in_view: bool, dt: f32) -> f32 {
+    if in_view { EXIT_SEEN_GRACE } else { (timer - dt).max(0.0) }
+}
+
+// ¿Cuenta tocar la salida? Con la regla activa, solo si se vio hace poco
+fn exit_counts(touched: bool, must_be_seen: bool, seen_timer: f32) -> bool {
+    touched && (!must_be_seen || seen_timer > 0.0)
+}
+
+// Haz de luz sobre la salida: solo en la huida; en extracción marca la celda de inicio en lugar de las 'g'
+fn exit_beacons(maze: &Maze, block: f32, state: GameState, home: Option<(usize, usize)>) -> Vec<(f32, f32)> {
+    if state != GameState::Escaping { return Vec::new(); }
+    let center = |i: usize, j: usize| ((i as f32 + 0.5) * block, (j as f32 + 0.5) * block);
+    if let Some((i, j)) = home { return vec![center(i, j)]; }
+    maze.iter().enumerate()
+        .flat_map(|(j, row)| row.iter().enumerate().filter(|(_, c)| tiles().is_exit(**c)).map(move |(i, _)| (i, j)))
+        .map(|(i, j)| center(i, j))
+        .collect()
+}
+
+// ¿Hay alguna celda 'g' dentro del FOV del jugador y sin paredes delante (hasta `max_dist`)?
+fn exit_in_view(maze: &Maze, block: f32, px: f32, py: f32, pa: f32, fov: f32, max_dist: f32) -> bool {
+    for (j, row) in maze.iter().enumerate() {
+        for (i, &c) in row.iter().enumerate() {
+            if !tiles().is_exit(c) { continue; }
+            let (tx, ty) = ((i as f32 + 0.5) * block, (j as f32 + 0.5) * block);
+            let (dx, dy) = (tx - px, ty - py);
+            let dist = (dx*dx + dy*dy).sqrt();
+            if dist > max_dist { continue; }
+            let mut ad = dy.atan2(dx) - pa;
+            while ad >  std::f32::consts::PI { ad -= std::f32::consts::TAU; }
+            while ad < -std::f32::consts::PI { ad += std::f32::consts::TAU; }
+            if ad.abs() > fov * 0.5 { continue; }
+            // Avanzar hacia el centro: visible si se entra en la salida antes de chocar con otra pared
+            let steps = (dist / (block * 0.25)).ceil().max(1.0) as i32;
+            for k in 1..=steps {
+                let t = k as f32 / steps as f32;
+                let (ci, cj) = (((px + dx * t) / block).floor() as usize, ((py + dy * t) / block).floor() as usize);
+                let cell = maze.get(cj).and_then(|r| r.get(ci)).copied().unwrap_or('#');
+                if tiles().is_exit(cell) { return true; }
+                if tiles().blocks_los(cell) { break; }
+            }
+        }
+    }
+    false
+}
+
 // ---------- 2D DEBUG ----------
 fn draw_cell(
     framebuffer: &mut Framebuffer,
@@ -112,7 +440,7 @@ fn draw_cell(
     block_size: usize,
     cell: char,
 ) {
-    if cell == ' ' { return; }
+    if tiles().player_walkable(cell) { return; }
     framebuffer.set_current_color(Color::RED);
     for x in xo..xo + block_size {
         for y in yo..yo + block_size {
@@ -135,13 +463,41 @@ pub fn render_maze(
 }
 
 // ---------- MINIMAPA ----------
+// Color de una celda en los mapas (minimapa y miniaturas del menú); None = suelo sin marcar
+fn map_cell_color(c: char) -> Option<Color> {
+    if c == BANISH {
+        Some(Color::new(170, 60, 230, 240))
+    } else if tiles().is_safe(c) {
+        // sala segura: verde tenue
+        Some(Color::new(40, 140, 80, 150))
+    } else if tiles().surface(c) == Surface::Water {
+        // agua: azul
+        Some(Color::new(40, 90, 200, 150))
+    } else if tiles().surface(c) == Surface::Metal {
+        // rejilla metálica: gris azulado
+        Some(Color::new(90, 110, 130, 150))
+    } else if tiles().is_hazard(c) {
+        // trampa: naranja oscuro
+        Some(Color::new(200, 90, 20, 220))
+    } else if tiles().looks_like_exit(c) {
+        // salida (o señuelo, indistinguible): blanco brillante
+        Some(Color::new(255, 255, 255, 240))
+    } else if !tiles().player_walkable(c) {
+        Some(Color::new(120, 120, 140, 230))
+    } else {
+        None
+    }
+}
+
 fn draw_minimap(
     d: &mut RaylibDrawHandle,
     maze: &Maze,
     player: &Player,
     orbs: &[Orb],
     enemy: &Enemy,
+    block: f32,
     window_width: i32,
+    show_speeds: bool,
 ) {
     let cell_px: i32 = 9;
     let margin: i32 = 10;
@@ -157,26 +513,27 @@ fn draw_minimap(
         for (i, &c) in row.iter().enumerate() {
             let x = origin_x + (i as i32) * cell_px;
             let y = origin_y + (j as i32) * cell_px;
-            if c != ' ' && c != 'g' {
-                d.draw_rectangle(x, y, cell_px, cell_px, Color::new(120, 120, 140, 230));
-            } else if c == 'g' {
-                // salida: destacar en blanco brillante
-                d.draw_rectangle(x, y, cell_px, cell_px, Color::new(255, 255, 255, 240));
+            let Some(col) = map_cell_color(c) else { continue };
+            if tiles().is_hazard(c) {
+                // trampa: marca más pequeña que la celda
+                d.draw_rectangle(x + 2, y + 2, cell_px - 4, cell_px - 4, col);
+            } else {
+                d.draw_rectangle(x, y, cell_px, cell_px, col);
             }
         }
     }
 
     for o in orbs.iter().filter(|o| o.active) {
-        let i = (o.x / BLOCK).floor() as i32;
-        let j = (o.y / BLOCK).floor() as i32;
+        let i = (o.x / block).floor() as i32;
+        let j = (o.y / block).floor() as i32;
         let cx = origin_x + i * cell_px + cell_px / 2;
         let cy = origin_y + j * cell_px + cell_px / 2;
         d.draw_circle(cx, cy, (cell_px as f32) * 0.25, Color::YELLOW);
     }
 
     // Jugador
-    let pi = (player.pos.x / BLOCK).floor() as i32;
-    let pj = (player.pos.y / BLOCK).floor() as i32;
+    let pi = (player.pos.x / block).floor() as i32;
+    let pj = (player.pos.y / block).floor() as i32;
     let px = origin_x + pi * cell_px + cell_px / 2;
     let py = origin_y + pj * cell_px + cell_px / 2;
 
@@ -188,238 +545,1004 @@ fn draw_minimap(
 
     // Enemy marker (no radius visualization)
     if enemy.active {
-        let ei = (enemy.x / BLOCK).floor() as i32;
-        let ej = (enemy.y / BLOCK).floor() as i32;
+        let ei = (enemy.x / block).floor() as i32;
+        let ej = (enemy.y / block).floor() as i32;
         let ex = origin_x + ei * cell_px + cell_px / 2;
         let ey = origin_y + ej * cell_px + cell_px / 2;
         d.draw_circle(ex, ey, (cell_px as f32) * 0.35, Color::RED);
+        // Última posición donde el enemigo te vio: anillo que se desvanece con su memoria
+        if let Some((lx, ly)) = enemy.last_seen().filter(|_| !enemy.sees_player(maze, player.pos.x, player.pos.y, block as usize)) {
+            let lx = origin_x + (lx / block).floor() as i32 * cell_px + cell_px / 2;
+            let ly = origin_y + (ly / block).floor() as i32 * cell_px + cell_px / 2;
+            let alpha = (60.0 + 180.0 * enemy.memory_fraction()) as u8;
+            d.draw_circle_lines(lx, ly, cell_px as f32 * 0.45, Color::new(255, 150, 40, alpha));
+        }
     }
 
     d.draw_rectangle_lines(origin_x - 4, origin_y - 4, map_w + 8, map_h + 8, Color::WHITE);
+
+    // Velocidades actuales del enemigo (ajustables en vivo desde el panel de debug)
+    if show_speeds {
+        let txt = format!("patrol {:.0}  chase {:.0}", enemy.speed_patrol(), enemy.speed_chase());
+        let tw = d.measure_text(&txt, 18);
+        d.draw_text(&txt, origin_x + map_w - tw, origin_y + map_h + 10, 18, Color::new(255, 200, 200, 255));
+    }
+}
+
+// ---------- MINIATURAS DEL MENÚ ----------
+// Hueco máximo (px) de la miniatura de cada nivel junto a su nombre en el menú
+const THUMB_W: i32 = 84;
+const THUMB_H: i32 = 40;
+
+/// Size (px) of a `cols`×`rows` maze scaled with square cells to fit `max_w`×`max_h`.
+fn thumb_fit(cols: usize, rows: usize, max_w: i32, max_h: i32) -> (i32, i32) {
+    if cols == 0 || rows == 0 { return (0, 0); }
+    let s = (max_w as f32 / cols as f32).min(max_h as f32 / rows as f32);
+    (((cols as f32 * s) as i32).min(max_w), ((rows as f32 * s) as i32).min(max_h))
+}
+
+// Textura con un píxel por celda y los colores del minimapa; se genera una vez al arrancar
+fn maze_thumbnail(rl: &mut RaylibHandle, thread: &RaylibThread, file: &str) -> Option<Texture2D> {
+    if !std::path::Path::new(file).is_file() { return None; }
+    let maze = load_maze_with(file, true);
+    let cols = maze.iter().map(|r| r.len()).max().unwrap_or(0);
+    if maze.is_empty() || cols == 0 { return None; }
+    let mut img = Image::gen_image_color(cols as i32, maze.len() as i32, Color::new(0, 0, 0, 0));
+    for (j, row) in maze.iter().enumerate() {
+        for (i, &c) in row.iter().enumerate() {
+            if let Some(col) = map_cell_color(c) { img.draw_pixel(i as i32, j as i32, col); }
+        }
+    }
+    rl.load_texture_from_image(thread, &img).ok()
+}
+
+// ---------- PANEL DE AJUSTE DEL ENEMIGO ----------
+// Toggle with ` (backtick); Up/Down selects a field, +/- adjusts the live enemy.
+const ENEMY_TUNE_FIELDS: [&str; 6] = ["fov", "range", "speed_patrol", "speed_chase", "turn_rate", "memory"];
+
+fn enemy_tune_value(enemy: &Enemy, field: usize) -> f32 {
+    match field {
+        0 => enemy.fov.to_degrees(),
+        1 => enemy.range,
+        2 => enemy.speed_patrol(),
+        3 => enemy.speed_chase(),
+        4 => enemy.turn_rate(),
+        _ => enemy.memory_max(),
+    }
 }
 
-fn reset_game(maze: &Maze, _block_size: usize) -> (Vec<Orb>, usize, Player, Enemy) {
+fn enemy_tune_adjust(enemy: &mut Enemy, field: usize, dir: f32) {
+    match field {
+        0 => enemy.set_fov(enemy.fov + dir * 5f32.to_radians()),
+        1 => enemy.set_range(enemy.range + dir * 50.0),
+        2 => enemy.set_speeds(enemy.speed_patrol() + dir * 5.0, enemy.speed_chase()),
+        3 => enemy.set_speeds(enemy.speed_patrol(), enemy.speed_chase() + dir * 5.0),
+        4 => enemy.set_turn_rate(enemy.turn_rate() + dir * 0.2),
+        _ => enemy.set_memory_max(enemy.memory_max() + dir * 0.5),
+    }
+}
+
+fn draw_enemy_tuning(d: &mut RaylibDrawHandle, enemy: &Enemy, selected: usize, x: i32, y: i32) {
+    let row_h = 22;
+    let panel_h = 34 + row_h * ENEMY_TUNE_FIELDS.len() as i32;
+    d.draw_rectangle(x - 6, y - 6, 300, panel_h, Color::new(0, 0, 0, 190));
+    d.draw_text("Enemy tuning  (Up/Down, +/-)", x, y, 18, Color::new(255, 200, 200, 255));
+    for (k, name) in ENEMY_TUNE_FIELDS.iter().enumerate() {
+        let ry = y + 26 + k as i32 * row_h;
+        let col = if k == selected { Color::YELLOW } else { Color::WHITE };
+        if k == selected { d.draw_text(">", x, ry, 18, col); }
+        d.draw_text(&format!("{:<13}{:>8.1}", name, enemy_tune_value(enemy, k)), x + 16, ry, 18, col);
+    }
+    d.draw_rectangle_lines(x - 6, y - 6, 300, panel_h, Color::WHITE);
+}
+
+fn draw_asset_report(d: &mut RaylibDrawHandle, report: &AssetReport, x: i32, y: i32) {
+    let row_h = 18;
+    let panel_h = 30 + row_h * report.entries.len() as i32;
+    d.draw_rectangle(x - 6, y - 6, 420, panel_h, Color::new(0, 0, 0, 190));
+    d.draw_text("Assets (F4)", x, y, 18, Color::new(200, 220, 255, 255));
+    for (k, ((_, src), line)) in report.entries.iter().zip(report.lines()).enumerate() {
+        let col = match src { AssetSource::Disk(_) => Color::WHITE, AssetSource::Procedural => Color::YELLOW, AssetSource::Missing => Color::RED };
+        d.draw_text(&line, x, y + 24 + k as i32 * row_h, 16, col);
+    }
+}
+
+// ---------- AVISO ENTRE ENEMIGOS ----------
+// Cuando `spotter` acaba de ver al jugador en (x, y), los demás enemigos a menos de `radius`
+// (unidades de mundo) buscan en ese punto. Devuelve cuántos recibieron el aviso.
+fn broadcast_alert(enemies: &mut [Enemy], spotter: usize, x: f32, y: f32, radius: f32) -> usize {
+    let Some(src) = enemies.get(spotter) else { return 0; };
+    let (sx, sy) = (src.x, src.y);
+    let mut alerted = 0;
+    for (k, e) in enemies.iter_mut().enumerate() {
+        if k == spotter { continue; }
+        let (dx, dy) = (e.x - sx, e.y - sy);
+        if dx*dx + dy*dy > radius * radius { continue; }
+        let was_chasing = e.is_chasing();
+        e.alert_to(x, y);
+        if !was_chasing && e.is_chasing() { alerted += 1; }
+    }
+    alerted
+}
+
+// ---------- TRANSICIÓN ----------
+// Fundido a negro entre estados: sube 0→255 en la primera mitad, baja 255→0 en la segunda.
+// El cambio de estado se hace en el punto medio (pantalla negra).
+const TRANSITION_SECS: f32 = 0.6;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum TransitionKind { StartLevel, ToMenu }
+
+#[derive(Default)]
+struct Transition {
+    kind: Option<TransitionKind>,
+    t: f32,
+    switched: bool,
+}
+
+impl Transition {
+    // Ignorado si ya hay una transición en curso
+    fn start(&mut self, kind: TransitionKind) {
+        if self.active() { return; }
+        self.kind = Some(kind);
+        self.t = 0.0;
+        self.switched = false;
+    }
+    fn active(&self) -> bool { self.kind.is_some() }
+    // Avanza `dt` y devuelve la opacidad del negro para este frame (0 sin transición)
+    fn alpha(&mut self, dt: f32) -> u8 {
+        if self.kind.is_none() { return 0; }
+        self.t += dt;
+        if self.t >= TRANSITION_SECS { self.kind = None; return 0; }
+        let p = self.t / TRANSITION_SECS;
+        ((1.0 - (2.0 * p - 1.0).abs()) * 255.0).round() as u8
+    }
+    // Una sola vez por transición, al pasar el punto medio: qué cambio de estado aplicar
+    fn take_midpoint(&mut self) -> Option<TransitionKind> {
+        if self.switched || self.t < TRANSITION_SECS * 0.5 { return None; }
+        self.switched = true;
+        self.kind
+    }
+}
+
+// ---------- BRÚJULA ----------
+// Tira horizontal arriba al centro: el centro es hacia donde mira el jugador.
+// Ángulos del mundo con y hacia abajo: E = 0, S = +90°, W = 180°, N = -90°.
+const COMPASS_W: i32 = 360;
+const COMPASS_SPAN: f32 = 2.0 * std::f32::consts::FRAC_PI_3; // 120° visibles en la tira
+
+// Desplazamiento horizontal (px desde el centro de la tira) de un rumbo `heading` visto con ángulo `player_a`.
+// None si queda fuera de la tira.
+fn compass_offset(player_a: f32, heading: f32) -> Option<f32> {
+    let mut diff = heading - player_a;
+    while diff >  std::f32::consts::PI { diff -= std::f32::consts::TAU; }
+    while diff < -std::f32::consts::PI { diff += std::f32::consts::TAU; }
+    if diff.abs() > COMPASS_SPAN * 0.5 { return None; }
+    Some(diff / COMPASS_SPAN * COMPASS_W as f32)
+}
+
+// `goal`: rumbo (rad) a un objetivo; se marca en verde, pegado al borde si queda fuera del arco
+fn draw_compass(d: &mut RaylibDrawHandle, player_a: f32, cx: i32, y: i32, goal: Option<f32>) {
+    let h = 30;
+    let x0 = cx - COMPASS_W / 2;
+    d.draw_rectangle(x0, y, COMPASS_W, h, Color::new(0, 0, 0, 150));
+    for k in 0..24 {
+        let heading = (k as f32 * 15.0).to_radians();
+        let Some(off) = compass_offset(player_a, heading) else { continue; };
+        let x = cx + off.round() as i32;
+        let label = match k { 0 => "E", 6 => "S", 12 => "W", 18 => "N", _ => "" };
+        if label.is_empty() {
+            let tick = if k % 3 == 0 { 10 } else { 5 };
+            d.draw_line(x, y + h - tick, x, y + h, Color::new(220, 220, 220, 200));
+        } else {
+            let col = if label == "N" { Color::new(255, 90, 90, 255) } else { Color::WHITE };
+            let tw = d.measure_text(label, 20);
+            d.draw_text(label, x - tw / 2, y + 5, 20, col);
+        }
+    }
+    if let Some(g) = goal {
+        let half = COMPASS_W as f32 * 0.5 - 4.0;
+        let off = compass_offset(player_a, g).unwrap_or_else(|| {
+            let diff = (g - player_a + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU) - std::f32::consts::PI;
+            half.copysign(diff)
+        }).clamp(-half, half);
+        let gx = cx + off.round() as i32;
+        d.draw_rectangle(gx - 3, y + 2, 6, h - 4, Color::new(90, 255, 140, 230));
+    }
+    // marcador central (rumbo actual)
+    d.draw_triangle(Vector2::new(cx as f32, (y + h - 6) as f32), Vector2::new((cx - 5) as f32, (y + h + 2) as f32), Vector2::new((cx + 5) as f32, (y + h + 2) as f32), Color::new(255, 200, 80, 255));
+    d.draw_rectangle_lines(x0, y, COMPASS_W, h, Color::new(200, 200, 200, 160));
+}
+
+// ---------- INDICADORES DE ORBS FUERA DE PANTALLA ----------
+const ORB_INDICATOR_MAX: usize = 3;       // solo los más cercanos, para no saturar
+const ORB_INDICATOR_MARGIN: f32 = 28.0;   // px desde el borde de la ventana
+const ORB_INDICATOR_FADE_CELLS: f32 = 20.0; // a esta distancia (celdas) la flecha casi desaparece
+
+// Posición en el borde de la pantalla para un rumbo `bearing` fuera del FOV: se lanza un rayo
+// desde el centro (adelante = arriba, derecha = derecha) y se corta con el rectángulo interior.
+// Devuelve (x, y, ángulo relativo) o None si el rumbo está dentro del FOV.
+fn orb_edge_position(player_a: f32, fov: f32, bearing: f32, w: f32, h: f32) -> Option<(f32, f32, f32)> {
+    let mut diff = bearing - player_a;
+    while diff >  std::f32::consts::PI { diff -= std::f32::consts::TAU; }
+    while diff < -std::f32::consts::PI { diff += std::f32::consts::TAU; }
+    if diff.abs() <= fov * 0.5 { return None; }
+    let (dx, dy) = (diff.sin(), -diff.cos());
+    let (hw, hh) = (w * 0.5 - ORB_INDICATOR_MARGIN, h * 0.5 - ORB_INDICATOR_MARGIN);
+    let sx = if dx.abs() > 1e-6 { hw / dx.abs() } else { f32::INFINITY };
+    let sy = if dy.abs() > 1e-6 { hh / dy.abs() } else { f32::INFINITY };
+    let s = sx.min(sy);
+    Some((w * 0.5 + dx * s, h * 0.5 + dy * s, diff))
+}
+
+fn draw_orb_indicators(d: &mut RaylibDrawHandle, player: &Player, orbs: &[Orb], block: f32, w: i32, h: i32) {
+    let mut near: Vec<(f32, f32)> = orbs.iter().filter(|o| o.active).map(|o| {
+        let (dx, dy) = (o.x - player.pos.x, o.y - player.pos.y);
+        ((dx*dx + dy*dy).sqrt(), dy.atan2(dx))
+    }).filter(|&(_, bearing)| orb_edge_position(player.a, player.fov, bearing, w as f32, h as f32).is_some()).collect();
+    near.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+    for &(dist, bearing) in near.iter().take(ORB_INDICATOR_MAX) {
+        let Some((x, y, rel)) = orb_edge_position(player.a, player.fov, bearing, w as f32, h as f32) else { continue; };
+        let fade = (1.0 - dist / (ORB_INDICATOR_FADE_CELLS * block)).clamp(0.15, 1.0);
+        let col = Color::new(255, 240, 80, (230.0 * fade) as u8);
+        // Flecha apuntando hacia fuera (misma dirección que el rumbo relativo en pantalla)
+        let (fx, fy) = (rel.sin(), -rel.cos());
+        let (sx, sy) = (-fy, fx); // lateral: a la derecha cuando la flecha apunta arriba
+        let tip = Vector2::new(x + fx * 10.0, y + fy * 10.0);
+        let left = Vector2::new(x - fx * 6.0 - sx * 7.0, y - fy * 6.0 - sy * 7.0);
+        let right = Vector2::new(x - fx * 6.0 + sx * 7.0, y - fy * 6.0 + sy * 7.0);
+        d.draw_triangle(tip, left, right, col); // orden antihorario en pantalla, como pide raylib
+    }
+}
+
+// ---------- VIÑETA DE PÁNICO ----------
+// Oscurece los bordes (apply_vignette) según lo cerca que está el enemigo y si te ve/persigue.
+const PANIC_VIGNETTE_MAX: f32 = 0.9;
+const PANIC_VIGNETTE_RANGE: f32 = 500.0; // distancia (mundo) a la que empieza a cerrarse
+const PANIC_VIGNETTE_RATE: f32 = 3.0;    // suavizado: fracción por segundo hacia el objetivo
+
+// Intensidad objetivo 0..PANIC_VIGNETTE_MAX para la distancia al enemigo y su estado
+fn panic_vignette_target(dist: f32, seen: bool, chasing: bool) -> f32 {
+    let near_t = (1.0 - dist / PANIC_VIGNETTE_RANGE).clamp(0.0, 1.0);
+    let base = if seen { 0.5 } else if chasing { 0.3 } else { 0.0 };
+    (base + 0.5 * near_t).min(1.0) * PANIC_VIGNETTE_MAX
+}
+
+// Acercamiento exponencial a `target`, independiente del frame rate
+fn smooth_toward(current: f32, target: f32, rate: f32, dt: f32) -> f32 {
+    current + (target - current) * (1.0 - (-rate * dt.max(0.0)).exp())
+}
+
+// ---------- CORDURA ----------
+// Sanity 1.0 = calm, 0.0 = broken. Below half it drives (desaturation, heartbeat intensity, speed multiplier).
+fn sanity_effects(sanity: f32) -> (f32, f32, f32) {
+    let k = ((0.5 - sanity.clamp(0.0, 1.0)) / 0.5).clamp(0.0, 1.0);
+    (0.7 * k, k, 1.0 - 0.15 * k)
+}
+
+// Contener la respiración cuesta cordura (por segundo); agotada, se suelta sola
+fn breath_drain(player: &mut Player, sanity: f32) -> f32 {
+    if sanity <= BREATH_MIN_SANITY { player.holding_breath = false; }
+    if player.holding_breath { BREATH_SANITY_DRAIN } else { 0.0 }
+}
+
+// ---------- LINTERNA / DETECCIÓN ----------
+// Cuánto se hace notar el jugador según la luz de la linterna (0 = apagada, 1 = a tope)
+fn flashlight_conspicuity(light: f32) -> f32 {
+    0.55 + 0.45 * light.clamp(0.0, 1.0)
+}
+
+// Haz de la linterna: semiángulo (rad) y alcance (celdas) dentro de los que el enemigo cuenta como iluminado
+const FLASHLIGHT_CONE: f32 = 0.35;
+const FLASHLIGHT_RANGE_CELLS: f32 = 8.0;
+
+// ¿Está el enemigo dentro del haz? Linterna encendida, en alcance, dentro del cono y con línea de visión
+fn enemy_in_flashlight(maze: &Maze, player: &Player, enemy: &Enemy, block_size: usize) -> bool {
+    if !player.flashlight_on || !enemy.active { return false; }
+    let range = FLASHLIGHT_RANGE_CELLS * block_size as f32;
+    let (dx, dy) = (enemy.x - player.pos.x, enemy.y - player.pos.y);
+    if dx * dx + dy * dy > range * range { return false; }
+    enemy.player_sees_enemy(maze, player.pos.x, player.pos.y, player.a, FLASHLIGHT_CONE * 2.0, block_size)
+}
+
+// Examinar: celda y tile del impacto (hx, hy) de un rayo con ángulo `a`. El impacto cae justo en la
+// cara de la pared; se mira un poco más allá a lo largo del rayo para no quedarse en la celda libre de delante
+fn examine_cell(maze: &Maze, block: f32, hx: f32, hy: f32, a: f32, wrap: bool) -> Option<(usize, usize, char)> {
+    cell_at_world(maze, block, hx + a.cos() * 0.5, hy + a.sin() * 0.5, wrap)
+}
+
+// ---------- RADAR ----------
+// Seconds between radar pings: fast when the enemy is close, slow when far.
+fn radar_ping_interval(dist: f32) -> f32 {
+    let t = (dist / 1200.0).clamp(0.0, 1.0);
+    0.3 + 1.7 * t
+}
+
+// ---------- PATROL AMBIENT ----------
+// Alcance (unidades de mundo) y volumen máximo de la respiración del enemigo patrullando
+const PATROL_AMBIENT_RANGE: f32 = 900.0;
+const PATROL_AMBIENT_MAX: f32 = 0.5;
+// Volumen de la respiración del enemigo: crece al acercarse y se calla al perseguir (mandan los pasos)
+fn patrol_ambient_volume(dist: f32, chasing: bool) -> f32 {
+    if chasing { return 0.0; }
+    (1.0 - dist / PATROL_AMBIENT_RANGE).clamp(0.0, 1.0) * PATROL_AMBIENT_MAX
+}
+
+// ---------- HEATMAP ----------
+// Segundos que el jugador pasa en cada celda durante la partida (analítica de niveles)
+fn heatmap_for(maze: &Maze) -> Vec<Vec<f32>> {
+    maze.iter().map(|row| vec![0.0; row.len()]).collect()
+}
+
+fn heatmap_add(heat: &mut [Vec<f32>], block: f32, x: f32, y: f32, dt: f32) {
+    if x < 0.0 || y < 0.0 { return; }
+    let (i, j) = ((x / block) as usize, (y / block) as usize);
+    if let Some(c) = heat.get_mut(j).and_then(|r| r.get_mut(i)) { *c += dt; }
+}
+
+// CSV: una fila por fila del laberinto, segundos con 2 decimales
+fn export_heatmap_csv(heat: &[Vec<f32>], path: &str) -> std::io::Result<()> {
+    let mut out = String::new();
+    for row in heat {
+        let cells: Vec<String> = row.iter().map(|v| format!("{:.2}", v)).collect();
+        out.push_str(&cells.join(","));
+        out.push('\n');
+    }
+    std::fs::write(path, out)
+}
+
+// Seed for the game RNG: `--seed N` / `--seed=N` on the command line, else a fixed default
+const DEFAULT_SEED: u64 = 0x7E70;
+fn seed_from_args() -> u64 {
+    let args: Vec<String> = std::env::args().collect();
+    for (k, a) in args.iter().enumerate() {
+        let v = if let Some(v) = a.strip_prefix("--seed=") { Some(v) } else if a == "--seed" { args.get(k + 1).map(|s| s.as_str()) } else { None };
+        if let Some(n) = v.and_then(|v| v.parse::<u64>().ok()) { return n; }
+    }
+    DEFAULT_SEED
+}
+
+// Celda de inicio del jugador en todos los niveles
+const PLAYER_START_CELL: (usize, usize) = (1, 1);
+
+// `fill_random`: con marcas '*' en el mapa, completar hasta la cantidad habitual con orbs al azar
+// (false = solo las marcas). Sin marcas siempre se reparten al azar. El jugador empieza en el centro de `spawn`.
+fn reset_game(maze: &Maze, block_size: usize, spawn: (usize, usize), rng: &mut StdRng, fill_random: bool) -> (Vec<Orb>, usize, Player, Enemy) {
+    let block = block_size as f32;
     // Much more orbs: roughly 20% of free cells, capped to avoid extremes
-    let free_cells = maze.iter().flatten().filter(|&&c| c == ' ' || c == 'g').count();
+    let free_cells = maze.iter().flatten().filter(|&&c| { let t = tiles().get(c); t.enemy_walkable && !t.is_hazard }).count();
     let desired = ((free_cells as f32) * 0.20).clamp(20.0, 180.0) as usize;
-    let orbs = spawn_orbs_in_empty_cells(maze, BLOCK, desired);
+    let mut orbs = orbs_at_markers(maze, block);
+    if orbs.is_empty() || fill_random {
+        let rest = desired.saturating_sub(orbs.len());
+        orbs.extend(spawn_orbs_in_empty_cells(maze, block, rest, rng));
+    }
     let score: usize = 0;
-    let player = Player::new(1.5 * BLOCK, 1.5 * BLOCK, 0.0);
-    let enemy = Enemy::new(2.5 * BLOCK, 2.5 * BLOCK, 0.0);
+    let mut player = Player::new(0.0, 0.0, 0.0);
+    player.teleport((spawn.0 as f32 + 0.5) * block, (spawn.1 as f32 + 0.5) * block);
+    let enemy = Enemy::new(2.5 * block, 2.5 * block, 0.0);
     (orbs, score, player, enemy)
 }
 
+// Framebuffer size for a window size at the given internal render scale
+fn fb_size_for(window_width: i32, window_height: i32, render_scale: f32) -> (i32, i32) {
+    let fb_w = ((window_width as f32) * render_scale).round().max(1.0) as i32;
+    let fb_h = ((window_height as f32) * render_scale).round().max(1.0) as i32;
+    (fb_w, fb_h)
+}
+
+// Escalas de resolución interna que recorren - y = (con el panel de ajuste del enemigo cerrado)
+const RENDER_SCALES: [f32; 4] = [0.5, 0.66, 0.8, 1.0];
+const DEFAULT_RENDER_SCALE_IDX: usize = 1;
+// Segundos que el HUD muestra la escala tras cambiarla
+const RENDER_SCALE_MSG_SECS: f32 = 1.5;
+
+// Índice de RENDER_SCALES tras pulsar subir/bajar, sin salirse de la tabla
+fn step_render_scale(idx: usize, up: bool, down: bool) -> usize {
+    if up { (idx + 1).min(RENDER_SCALES.len() - 1) } else if down { idx.saturating_sub(1) } else { idx }
+}
+
+// Framebuffer, z-buffer y textura de blit a (w, h): mismo camino al arrancar y al cambiar de tamaño o escala.
+// `None` si no se pudo crear la textura (el framebuffer y el z-buffer ya quedan al tamaño nuevo).
+fn resize_render_target(rl: &mut RaylibHandle, thread: &RaylibThread, fb: &mut Framebuffer, zbuffer: &mut Vec<f32>, w: i32, h: i32) -> Option<Texture2D> {
+    fb.resize(w as u32, h as u32);
+    zbuffer.clear();
+    zbuffer.resize(fb.width as usize, f32::INFINITY);
+    let img = Image::gen_image_color(w, h, Color::BLACK);
+    rl.load_texture_from_image(thread, &img).ok()
+}
+
+// Destino del blit que conserva el aspecto del framebuffer (barras negras en el sobrante)
+fn letterbox_rect(window_width: f32, window_height: f32, src_w: f32, src_h: f32) -> Rectangle {
+    if src_w <= 0.0 || src_h <= 0.0 { return Rectangle { x: 0.0, y: 0.0, width: window_width, height: window_height }; }
+    let scale = (window_width / src_w).min(window_height / src_h);
+    let (w, h) = (src_w * scale, src_h * scale);
+    Rectangle { x: ((window_width - w) * 0.5).floor(), y: ((window_height - h) * 0.5).floor(), width: w, height: h }
+}
+
+// Como letterbox_rect pero con escala entera (la mayor que quepa) para píxeles nítidos.
+// Si el framebuffer no cabe ni a escala 1, vuelve al escalado libre.
+fn integer_scale_rect(window_width: f32, window_height: f32, src_w: f32, src_h: f32) -> Rectangle {
+    if src_w <= 0.0 || src_h <= 0.0 { return Rectangle { x: 0.0, y: 0.0, width: window_width, height: window_height }; }
+    let scale = (window_width / src_w).min(window_height / src_h).floor();
+    if scale < 1.0 { return letterbox_rect(window_width, window_height, src_w, src_h); }
+    let (w, h) = (src_w * scale, src_h * scale);
+    Rectangle { x: ((window_width - w) * 0.5).floor(), y: ((window_height - h) * 0.5).floor(), width: w, height: h }
+}
+
+// Resolución objetivo fija que activa F2 (desactivada: el framebuffer sigue el tamaño de la ventana)
+const TARGET_RES: (i32, i32) = (1300, 900);
+
 fn main() {
-    let window_width = 1300;
-    let window_height = 900;
-    // Internal render scale (lower than 1.0 to boost FPS). 0.66 ~ 66% resolution.
-    let render_scale: f32 = 0.66;
-    let fb_w = ((window_width as f32) * render_scale).round() as i32;
-    let fb_h = ((window_height as f32) * render_scale).round() as i32;
-    let block_size = BLOCK as usize;
+    let mut window_width = 1300;
+    let mut window_height = 900;
+    // Internal render scale (lower than 1.0 to boost FPS). 0.66 ~ 66% resolution; - / = change it at runtime.
+    let mut render_scale_idx = DEFAULT_RENDER_SCALE_IDX;
+    let mut render_scale: f32 = RENDER_SCALES[render_scale_idx];
+    let mut render_scale_msg_timer: f32 = 0.0;
+    let (fb_w, fb_h) = fb_size_for(window_width, window_height, render_scale);
 
     let (mut window, raylib_thread) = raylib::init()
         .size(window_width, window_height)
         .title("Teto´s Escape Game")
+        .resizable()
         .build();
 
     window.disable_cursor();
+    let mut mouse_captured = true; // TAB libera/captura el cursor (sin captura no hay giro con ratón)
     window.set_target_fps(60);
 
     // Audio manager (rodio)
     let mut audio = AudioManager::new();
+    // Informe de carga de assets (stderr al arrancar; F4 lo muestra en pantalla)
+    let mut asset_report = AssetReport::new();
     if let Some(a) = audio.as_mut() {
-        a.load_sfx_auto();
+        asset_report = a.load_sfx_auto();
         a.play_music_loop_auto();
+    } else {
+        asset_report.push("audio device", AssetSource::Missing);
     }
     let mut caught_sfx_played = false;
+    // Sin dispositivo de audio se reintenta abrirlo (espera creciente); con él, se comprueba que sigue ahí
+    let mut audio_backoff = ReinitBackoff::new();
+    let mut audio_check_timer: f32 = AUDIO_CHECK_SECS;
 
     let mut texman = TextureManager::new(&mut window, &raylib_thread);
+    asset_report.entries.extend(texman.report().entries.iter().cloned());
+    asset_report.eprint();
+    let mut show_asset_report = false;
+    // Degradado radial de la linterna: se genera una vez y se escala cada frame
+    let flashlight_tex = make_gradient_texture(&mut window, &raylib_thread);
     let mut framebuffer = Framebuffer::new(fb_w as u32, fb_h as u32);
     framebuffer.set_background_color(Color::new(20, 20, 30, 255));
 
-    // Textura persistente para blitear el framebuffer cada frame
-    let img = Image::gen_image_color(fb_w, fb_h, Color::BLACK);
-    let mut fb_tex = window
-        .load_texture_from_image(&raylib_thread, &img)
+    // Textura persistente para blitear el framebuffer cada frame (y z-buffer por columna)
+    let mut zbuffer = Vec::new();
+    let mut fb_tex = resize_render_target(&mut window, &raylib_thread, &mut framebuffer, &mut zbuffer, fb_w, fb_h)
         .expect("crear texture framebuffer");
 
     // Cargar nivel por defecto (Level 1)
     let mut selected_level: i32 = 0;
     let mut cfg = level_cfg(selected_level);
-    let mut maze = load_maze(cfg.file);
+    let mut maze = load_maze_with(cfg.file, !cfg.toroidal);
+    let mut block_size = cfg.block as usize;
 
-    let (mut orbs, mut score, mut player, mut enemy) = reset_game(&maze, block_size);
+    // Única fuente de aleatoriedad: misma semilla => misma partida
+    let seed = seed_from_args();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (mut orbs, mut score, mut player, mut enemy) = reset_game(&maze, block_size, PLAYER_START_CELL, &mut rng, cfg.orb_fill_random);
+    // Ajustes persistentes del jugador (sensibilidad / inversión del ratón)
+    let mut settings = Settings::load(SETTINGS_FILE);
+    // Eventos para integraciones externas: a events.log si está activado en settings.txt
+    let mut events: Box<dyn GameEventSink> = match settings.event_log.then(|| LogSink::open(EVENT_LOG_FILE)) {
+        Some(Ok(sink)) => Box::new(sink),
+        Some(Err(e)) => { eprintln!("events: {}", e); Box::new(NullSink) }
+        None => Box::new(NullSink),
+    };
+    settings.apply_to(&mut player);
+    // Meta-progresión opcional (M en el menú): contador acumulado de orbs entre partidas
+    let mut meta = Meta::load(META_FILE);
+    let mut aggression = Aggression::load(AGGRESSION_FILE);
+    let mut times_spotted: u32 = 0; // veces que el enemigo te ha descubierto en este nivel (dificultad adaptativa)
+    // Muertes por nivel (stats.txt), en el menú y en la pantalla de Caught
+    let mut stats = Stats::load(STATS_FILE);
     enemy.active = false; // spawn retardado
+    // Dificultad (D en el menú): preset elegido y valores aplicados en la partida en curso
+    let mut difficulty = Difficulty::default();
+    let mut diff = difficulty.cfg();
+    let mut required_orbs = orbs.len(); // orbs necesarios para abrir la salida
+    let mut unlock_hint_timer: f32 = 0.0; // aviso de salida abierta con orbs pendientes
     let mut enemy_spawn_timer: f32 = 1.8; // aparece tras ~1.8s
+    let mut grab_reveal_timer: f32 = 0.0;
+    let mut shake = CameraShake::new(); // pequeño golpe de cámara al recoger orbs
+    let mut banish_recharge: f32 = 0.0; // segundos hasta poder usar otra vez el interruptor
+    let mut banish_away: f32 = 0.0;     // segundos que el enemigo sigue desterrado
+    let mut enemy_kind = EnemyKind::Hunter; // menú: V alterna cazador / ángel / espejo (contrario) / espejo (igual) / ciego
+    let mut mirror_same = false;
+    let mut target_res: Option<(i32, i32)> = None;
+    let mut wall_style = WallStyle::Textured; // F3 cicla texturas / color plano / alambre
+    let mut exit_must_be_seen = false; // menú: L activa la regla "salida vista"
+    let mut practice_mode = false; // menú: P activa el modo práctica para la próxima partida
+    let mut extraction_mode = false; // menú: X fuerza la extracción (volver al inicio) en cualquier nivel
+    let mut arcade_mode = false; // menú: A activa el modo arcade (contrarreloj con orbs que reaparecen)
+    let mut arcade_time_left: f32 = 0.0;
+    let mut spawn_cell: (usize, usize) = PLAYER_START_CELL; // celda de inicio del jugador (objetivo de la extracción)
+    let mut practice_run = false;  // la partida actual es de práctica (no puntúa)
+    let mut exit_seen_timer: f32 = 0.0;
+    let mut ray_factor: u32 = 1; // F5: rayos por columna (supersampling horizontal)
+    let mut show_render_stats = false; // F7: contadores de render por frame (rayos, pasos, píxeles de sprite, blur)
+    let mut show_examine = false; // F11: coordenadas de mundo/celda y tile que toca el rayo central
+    let mut aniso_on = true; // F6: filtrado anisótropo barato en paredes vistas de refilón
+    let mut heat = heatmap_for(&maze);
+    let mut depth_debug = false; // F1: vista de profundidad (zbuffer) en lugar de texturas
+    let mut sanity: f32 = 1.0;
+    let mut heartbeat_timer: f32 = 0.0;
+    let mut calm_cue_timer: f32 = 0.0;
     let mut level_start_time = window.get_time() as f32;
     // Preload `teto.gif` for the menu (single frame; GIF animation not handled)
     let tex_teto = Image::load_image("assets/teto.gif")
         .ok()
         .and_then(|img| window.load_texture_from_image(&raylib_thread, &img).ok());
+    // Miniatura de cada nivel para la lista del menú (cacheada)
+    let level_thumbs: Vec<Option<Texture2D>> = (0..3).map(|i| maze_thumbnail(&mut window, &raylib_thread, level_cfg(i).file)).collect();
 
-    let mut zbuffer = vec![f32::INFINITY; framebuffer.width as usize];
     let mode_3d = true;
+    // Campo de distancias compartido para la IA (un BFS por cambio de celda del jugador)
+    let use_distance_field = true;
+    let mut dist_field = DistanceField::new();
     let mut game_state = GameState::Menu;
+    let mut resume_state = GameState::Playing; // estado al que vuelve la auto-pausa por foco
+    let mut paused_at: f32 = 0.0;
     // Simplified menu: Enter starts next level; no menu index needed
+    // Panel de ajuste del enemigo (debug)
+    let mut show_enemy_tuning = false;
+    let mut enemy_tune_sel: usize = 0;
+    // Radar sonoro opcional (R): pan = dirección del enemigo, tono/ritmo = distancia
+    let mut radar_ping_on = false;
+    let mut radar_ping_timer: f32 = 0.0;
+    // Anillo en el suelo con el radio de recogida de los orbs cercanos (O)
+    let mut show_pickup_rings = false;
+    let mut show_compass = true; // C alterna la brújula superior
+    let mut show_orb_indicators = false; // T: flechas en el borde hacia los orbs fuera de vista
+    let mut panic_vignette_on = true; // H: viñeta que se cierra con el enemigo cerca
+    let mut spawn_announce_on = true; // Y: destello + rumor grave cuando el enemigo aparece
+    let mut spawn_announced = false; // ya se avisó en este nivel (solo la primera aparición)
+    let mut spawn_announce_timer: f32 = 0.0; // decae de SPAWN_ANNOUNCE_SECS a 0
+    let mut panic_vignette: f32 = 0.0; // intensidad suavizada actual
+    let mut enemy_walk_anim = true; // N: estirar/aplastar el sprite del enemigo al caminar
+    let mut enemy_shadow_on = true; // F8: sombra direccional del enemigo en el suelo
+    let mut idle_timer: f32 = 0.0; // segundos que el jugador lleva sin moverse (anti-campeo)
+    let mut patrol_ambient_on = true; // Z: respiración audible del enemigo mientras patrulla
 
     // Delta time tracking
     let mut last_time = window.get_time();
+    // Presupuesto de efectos: media móvil del frame time; apaga efectos opcionales si el equipo no llega
+    let mut budget = EffectBudget::new();
+    let mut transition = Transition::default();
 
     while !window.window_should_close() {
         // dt
     let now = window.get_time();
     let dt = (now - last_time) as f32;
     last_time = now;
+    budget.update(dt);
+    // Fundido entre estados: mientras dura se ignora la entrada
+    let fade_alpha = transition.alpha(dt);
+    let pending = transition.take_midpoint();
+    let input_ok = !transition.active();
+    if window.is_key_pressed(KeyboardKey::KEY_TAB) {
+        mouse_captured = !mouse_captured;
+        if mouse_captured { window.disable_cursor(); } else { window.enable_cursor(); }
+    }
+    player.mouse_look = mouse_captured;
+
+    // Auto-pausa al perder el foco (alt-tab): la partida se congela (Paused no entra en la
+    // actualización) y los bucles de audio se paran; al volver se reanuda donde estaba
+    let focused = window.is_window_focused();
+    match focus_pause(&mut game_state, &mut resume_state, settings.auto_pause, focused) {
+        Some(true) => {
+            paused_at = window.get_time() as f32;
+            if let Some(a) = audio.as_mut() { a.set_paused(true); }
+        }
+        Some(false) => {
+            level_start_time += window.get_time() as f32 - paused_at; // la pausa no cuenta para la aparición del enemigo
+            if let Some(a) = audio.as_mut() { a.set_paused(false); }
+        }
+        None => {}
+    }
+
+    // Respiración del enemigo patrullando: se oye antes de que te persiga (fuera de partida, silencio)
+    if let Some(a) = audio.as_mut() {
+        let live = patrol_ambient_on && cfg.enemy_enabled && enemy.active && game_state.in_play();
+        let dist = ((enemy.x - player.pos.x).powi(2) + (enemy.y - player.pos.y).powi(2)).sqrt();
+        let breath = 0.7 + 0.3 * (window.get_time() as f32 * 1.6).sin();
+        a.set_patrol_ambient(if live { patrol_ambient_volume(dist, enemy.is_chasing()) * breath } else { 0.0 });
+    }
+
+    // Audio: reabrir el dispositivo si falta; si desaparece a mitad de partida, soltarlo (los sinks se cierran limpios)
+    if audio.is_none() {
+        if audio_backoff.tick(dt) {
+            audio = AudioManager::reopen(cfg.music);
+            if audio.is_some() { audio_backoff.reset(); } else { audio_backoff.failed(); }
+        }
+    } else {
+        audio_check_timer -= dt;
+        if audio_check_timer <= 0.0 {
+            audio_check_timer = AUDIO_CHECK_SECS;
+            if !AudioManager::device_present() { audio = None; audio_backoff.reset(); }
+        }
+    }
+
+    // Resize: el layout (HUD, minimapa, linterna) lee window_width/height; el framebuffer mantiene la escala
+    let (sw, sh) = (window.get_screen_width(), window.get_screen_height());
+    if sw > 0 && sh > 0 { window_width = sw; window_height = sh; }
+    if window.is_key_pressed(KeyboardKey::KEY_F2) { target_res = if target_res.is_some() { None } else { Some(TARGET_RES) }; }
+    // - / = bajan/suben la resolución interna (con el panel de ajuste abierto, esas teclas ajustan al enemigo)
+    if !show_enemy_tuning {
+        let up = window.is_key_pressed(KeyboardKey::KEY_EQUAL) || window.is_key_pressed(KeyboardKey::KEY_KP_ADD);
+        let down = window.is_key_pressed(KeyboardKey::KEY_MINUS) || window.is_key_pressed(KeyboardKey::KEY_KP_SUBTRACT);
+        if up || down { render_scale_msg_timer = RENDER_SCALE_MSG_SECS; }
+        render_scale_idx = step_render_scale(render_scale_idx, up, down);
+        render_scale = RENDER_SCALES[render_scale_idx];
+    }
+    render_scale_msg_timer = (render_scale_msg_timer - dt).max(0.0);
+    let (base_w, base_h) = target_res.unwrap_or((window_width, window_height));
+    let (fb_w, fb_h) = fb_size_for(base_w, base_h, render_scale);
+    let resized = fb_w as u32 != framebuffer.width || fb_h as u32 != framebuffer.height;
+    let new_tex = if resized { resize_render_target(&mut window, &raylib_thread, &mut framebuffer, &mut zbuffer, fb_w, fb_h) } else { None };
+    if let Some(t) = new_tex { fb_tex = t; }
 
     // Menu input & drawing
     let mut touched_exit = false;
+    let player_prev = player.pos; // para el enemigo espejo: paso del jugador en este frame
     if matches!(game_state, GameState::Menu) {
-        // Level selection shortcuts on menu
-        if window.is_key_pressed(KeyboardKey::KEY_ONE) { selected_level = 0; }
-        if window.is_key_pressed(KeyboardKey::KEY_TWO) { selected_level = 1; }
-        if window.is_key_pressed(KeyboardKey::KEY_THREE) { selected_level = 2; }
-        if window.is_key_pressed(KeyboardKey::KEY_ENTER) || window.is_key_pressed(KeyboardKey::KEY_KP_ENTER) {
+        // Teclas del menú: ignoradas durante el fundido (el nivel arranca con lo elegido antes)
+        if input_ok {
+            // Level selection shortcuts on menu
+            if window.is_key_pressed(KeyboardKey::KEY_ONE) { selected_level = 0; }
+            if window.is_key_pressed(KeyboardKey::KEY_TWO) { selected_level = 1; }
+            if window.is_key_pressed(KeyboardKey::KEY_THREE) { selected_level = 2; }
+            if window.is_key_pressed(KeyboardKey::KEY_M) {
+                meta.enabled = !meta.enabled;
+                let _ = meta.save(META_FILE);
+            }
+            if window.is_key_pressed(KeyboardKey::KEY_G) {
+                aggression.enabled = !aggression.enabled;
+                let _ = aggression.save(AGGRESSION_FILE);
+            }
+            if window.is_key_pressed(KeyboardKey::KEY_L) { exit_must_be_seen = !exit_must_be_seen; }
+            if window.is_key_pressed(KeyboardKey::KEY_P) { practice_mode = !practice_mode; }
+            if window.is_key_pressed(KeyboardKey::KEY_X) { extraction_mode = !extraction_mode; }
+            if window.is_key_pressed(KeyboardKey::KEY_A) { arcade_mode = !arcade_mode; }
+            if window.is_key_pressed(KeyboardKey::KEY_D) { difficulty = difficulty.next(); }
+            if window.is_key_pressed(KeyboardKey::KEY_V) {
+                (enemy_kind, mirror_same) = match (enemy_kind, mirror_same) {
+                    (EnemyKind::Hunter, _) => (EnemyKind::Angel, false),
+                    (EnemyKind::Angel, _) => (EnemyKind::Mirror, false),
+                    (EnemyKind::Mirror, false) => (EnemyKind::Mirror, true),
+                    (EnemyKind::Mirror, true) => (EnemyKind::Blind, false),
+                    (EnemyKind::Blind, _) => (EnemyKind::Hunter, false),
+                };
+            }
+            if window.is_key_pressed(KeyboardKey::KEY_ENTER) || window.is_key_pressed(KeyboardKey::KEY_KP_ENTER) {
+                transition.start(TransitionKind::StartLevel);
+            }
+        }
+        if pending == Some(TransitionKind::StartLevel) {
             let start_idx = selected_level.clamp(0, 2);
             cfg = level_cfg(start_idx);
-            maze = load_maze(cfg.file);
-            let (o, s, p, e) = reset_game(&maze, block_size);
+            practice_run = practice_mode;
+            if practice_run { cfg = practice_cfg(cfg); }
+            if extraction_mode { cfg.extraction = true; }
+            if arcade_mode { cfg.arcade = true; }
+            maze = load_maze_with(cfg.file, !cfg.toroidal);
+            block_size = cfg.block as usize;
+            dist_field = DistanceField::new();
+            spawn_cell = PLAYER_START_CELL;
+            let (o, s, p, e) = reset_game(&maze, block_size, spawn_cell, &mut rng, cfg.orb_fill_random);
             orbs = o; score = s; player = p; enemy = e;
+            enemy.kind = enemy_kind;
+            enemy.set_mirror_same(mirror_same);
+            enemy.set_turn_rate(cfg.enemy_turn_rate);
+            enemy.set_safe_decay(cfg.safe_decay);
+            if cfg.route_patrol { enemy.set_patrol_route(auto_patrol_route(&maze)); }
+            settings.apply_to(&mut player);
+            if meta.unlocked(BONUS_UNLOCK_ORBS) { player.speed_walk *= 1.08; player.speed_sprint *= 1.08; }
+            diff = difficulty.cfg();
+            enemy.set_speeds(enemy.speed_patrol() * diff.enemy_speed, enemy.speed_chase() * diff.enemy_speed);
+            // Dificultad adaptativa: más lento tras un nivel limpio, más rápido si te vio a menudo
+            let agg = aggression.speed_mul();
+            enemy.set_speeds(enemy.speed_patrol() * agg, enemy.speed_chase() * agg);
+            enemy.set_range(enemy.range * diff.enemy_range);
+            player.battery_drain *= diff.battery_drain;
+            required_orbs = diff.required_orbs(orbs.len());
             enemy.active = false;
             // Spawn earlier on L1 and L2; keep later on L3
-            enemy_spawn_timer = if start_idx == 0 || start_idx == 1 { 0.5 } else { 12.0 };
+            enemy_spawn_timer = (if start_idx == 0 || start_idx == 1 { 0.5 } else { 12.0 }) * diff.spawn_delay;
+            grab_reveal_timer = 0.0;
+            spawn_announced = false;
+            unlock_hint_timer = 0.0;
+            arcade_time_left = ARCADE_SECS;
+            idle_timer = 0.0;
+            times_spotted = 0;
+            spawn_announce_timer = 0.0;
+            shake.reset();
+            banish_recharge = 0.0;
+            exit_seen_timer = 0.0;
+            heat = heatmap_for(&maze);
+            banish_away = 0.0;
+            sanity = 1.0;
             level_start_time = window.get_time() as f32;
+            events.emit(GameEvent::LevelStarted { file: cfg.file.to_string() });
+            if let Some(a) = audio.as_mut() { a.switch_music(cfg.music); }
             game_state = GameState::Playing;
             // Next time in menu, advance to next level
             selected_level = (start_idx + 1) % 3;
         }
     } else {
     // Entrada jugador solo cuando estamos jugando/escapando; bloqueado si "Caught"
-        if matches!(game_state, GameState::Playing | GameState::Escaping) {
-            touched_exit = process_events(&mut window, &mut player, &maze, block_size);
+        if input_ok && game_state.in_play() {
+            touched_exit = process_events(&mut window, &mut player, &maze, block_size, cfg.toroidal);
         }
-        // ENTER para volver al menú desde el juego o desde Caught
-        if window.is_key_pressed(KeyboardKey::KEY_ENTER) || window.is_key_pressed(KeyboardKey::KEY_KP_ENTER) {
+        if window.is_key_pressed(KeyboardKey::KEY_GRAVE) { show_enemy_tuning = !show_enemy_tuning; }
+        if window.is_key_pressed(KeyboardKey::KEY_B) { let on = !texman.is_bilinear(); texman.set_bilinear(on); }
+        if window.is_key_pressed(KeyboardKey::KEY_F1) { depth_debug = !depth_debug; }
+        if window.is_key_pressed(KeyboardKey::KEY_F3) { wall_style = wall_style.next(); }
+        if window.is_key_pressed(KeyboardKey::KEY_F4) { show_asset_report = !show_asset_report; }
+        if window.is_key_pressed(KeyboardKey::KEY_F5) { ray_factor = ray_factor % MAX_RAY_FACTOR + 1; }
+        if window.is_key_pressed(KeyboardKey::KEY_F6) { aniso_on = !aniso_on; }
+        if window.is_key_pressed(KeyboardKey::KEY_F7) { show_render_stats = !show_render_stats; }
+        if window.is_key_pressed(KeyboardKey::KEY_F11) { show_examine = !show_examine; }
+        if window.is_key_pressed(KeyboardKey::KEY_F8) { enemy_shadow_on = !enemy_shadow_on; }
+        // Ajustes: I invierte el eje X, J movimiento suave, K sprint con doble toque de W, U scanlines CRT, F9 anti-campeo, F10 escala entera, [ / ] bajan/suben la sensibilidad (se guardan en settings.txt)
+        {
+            let mut changed = false;
+            if window.is_key_pressed(KeyboardKey::KEY_I) { settings.invert_x = !settings.invert_x; changed = true; }
+            if window.is_key_pressed(KeyboardKey::KEY_J) { settings.smooth_move = !settings.smooth_move; changed = true; }
+            if window.is_key_pressed(KeyboardKey::KEY_K) { settings.double_tap_sprint = !settings.double_tap_sprint; changed = true; }
+            if window.is_key_pressed(KeyboardKey::KEY_U) { settings.scanlines = !settings.scanlines; changed = true; }
+            if window.is_key_pressed(KeyboardKey::KEY_F9) { settings.anti_camp = !settings.anti_camp; changed = true; }
+            if window.is_key_pressed(KeyboardKey::KEY_F10) { settings.pixel_perfect = !settings.pixel_perfect; changed = true; }
+            if window.is_key_pressed(KeyboardKey::KEY_LEFT_BRACKET) { settings.mouse_sens = (settings.mouse_sens * 0.9).max(0.0002); changed = true; }
+            if window.is_key_pressed(KeyboardKey::KEY_RIGHT_BRACKET) { settings.mouse_sens = (settings.mouse_sens * 1.1).min(0.02); changed = true; }
+            if changed {
+                settings.apply_to(&mut player);
+                let _ = settings.save(SETTINGS_FILE);
+            }
+        }
+        if window.is_key_pressed(KeyboardKey::KEY_O) { show_pickup_rings = !show_pickup_rings; }
+        if window.is_key_pressed(KeyboardKey::KEY_C) { show_compass = !show_compass; }
+        if window.is_key_pressed(KeyboardKey::KEY_T) { show_orb_indicators = !show_orb_indicators; }
+        if window.is_key_pressed(KeyboardKey::KEY_H) { panic_vignette_on = !panic_vignette_on; }
+        if window.is_key_pressed(KeyboardKey::KEY_Y) { spawn_announce_on = !spawn_announce_on; }
+        if window.is_key_pressed(KeyboardKey::KEY_Z) { patrol_ambient_on = !patrol_ambient_on; }
+        if window.is_key_pressed(KeyboardKey::KEY_N) { enemy_walk_anim = !enemy_walk_anim; }
+        if window.is_key_pressed(KeyboardKey::KEY_R) { radar_ping_on = !radar_ping_on; radar_ping_timer = 0.0; }
+        if show_enemy_tuning {
+            let n = ENEMY_TUNE_FIELDS.len();
+            if window.is_key_pressed(KeyboardKey::KEY_UP) { enemy_tune_sel = (enemy_tune_sel + n - 1) % n; }
+            if window.is_key_pressed(KeyboardKey::KEY_DOWN) { enemy_tune_sel = (enemy_tune_sel + 1) % n; }
+            let plus = window.is_key_pressed(KeyboardKey::KEY_EQUAL) || window.is_key_pressed(KeyboardKey::KEY_KP_ADD);
+            let minus = window.is_key_pressed(KeyboardKey::KEY_MINUS) || window.is_key_pressed(KeyboardKey::KEY_KP_SUBTRACT);
+            if plus { enemy_tune_adjust(&mut enemy, enemy_tune_sel, 1.0); }
+            if minus { enemy_tune_adjust(&mut enemy, enemy_tune_sel, -1.0); }
+            // Depuración: Inicio devuelve al jugador a su celda de salida
+            if window.is_key_pressed(KeyboardKey::KEY_HOME) {
+                let b = block_size as f32;
+                player.teleport((spawn_cell.0 as f32 + 0.5) * b, (spawn_cell.1 as f32 + 0.5) * b);
+            }
+        }
+        // ENTER para volver al menú desde el juego, Won o Caught (con fundido)
+        if input_ok && (window.is_key_pressed(KeyboardKey::KEY_ENTER) || window.is_key_pressed(KeyboardKey::KEY_KP_ENTER)) {
+            transition.start(TransitionKind::ToMenu);
+        }
+        if pending == Some(TransitionKind::ToMenu) {
             game_state = GameState::Menu;
             continue;
         }
     }
 
+    // Interacción: casilla usable (interruptor, etc.) en alcance y de frente; G la activa
+        let interact_target = if game_state.in_play() {
+            find_interactable_in_front(&maze, &player, block_size as f32).map(|(i, j)| maze[j][i]).and_then(|c| tiles().interaction(c))
+        } else { None };
+        let interact_pressed = input_ok && interact_target.is_some() && window.is_key_pressed(KeyboardKey::KEY_G);
+
     // Lógica de enemigo
-        if matches!(game_state, GameState::Playing | GameState::Escaping) {
+        if game_state.in_play() {
             // activar enemigo tras un pequeño retraso, y colocarlo lejos del jugador
             if cfg.enemy_enabled {
-                if !enemy.active {
+                // Huida relajada: el enemigo se retira y no vuelve a aparecer
+                let escape_despawn = game_state == GameState::Escaping && cfg.escape_mode == EscapeMode::Despawn;
+                if escape_despawn { enemy.active = false; }
+                // Interruptor de destierro: con el enemigo activo y el interruptor cargado
+                banish_recharge = (banish_recharge - dt).max(0.0);
+                banish_away = (banish_away - dt).max(0.0);
+                if interact_pressed && interact_target == Some(Interaction::Banish) {
+                    try_banish(&mut enemy, &mut banish_recharge, &mut banish_away);
+                }
+                if !enemy.active && banish_away <= 0.0 && !escape_despawn {
+                    let block = block_size as f32;
                     // para L2/L3: aparece hacia media partida: por tiempo o por progreso de orbs
                     let elapsed = window.get_time() as f32 - level_start_time;
                     let total = (orbs.len() + score) as i32; // total inicial de orbs
                     let collected = score as i32;
                     let mid_orbs = total.max(1) / 2;
                     enemy_spawn_timer -= dt;
-                    let time_gate = if selected_level == 1 { elapsed >= 12.0 } else { elapsed >= 10.0 };
+                    let time_gate = elapsed >= (if selected_level == 1 { 12.0 } else { 10.0 }) * diff.spawn_delay;
                     let progress_gate = collected >= mid_orbs;
                     if enemy_spawn_timer <= 0.0 || time_gate || progress_gate {
                         enemy.active = true;
                         // Prefer spawn near the exit on Level 2, otherwise far from player
-                        let mut placed = false;
+                        let (px, py) = (player.pos.x, player.pos.y);
+                        let mut spawn: Option<(usize,usize)> = None;
                         if selected_level == 1 {
                             // buscar 'g' y elegir una celda libre en un anillo alrededor
                             let mut exit_pos: Option<(usize,usize)> = None;
                             'outer: for (j,row) in maze.iter().enumerate() {
                                 for (i,&c) in row.iter().enumerate() {
-                                    if c == 'g' { exit_pos = Some((i,j)); break 'outer; }
+                                    if tiles().is_exit(c) { exit_pos = Some((i,j)); break 'outer; }
                                 }
                             }
                             if let Some((gi, gj)) = exit_pos {
-                                let h = maze.len();
-                                let w = maze[0].len();
-                                // probar anillos de radio 1..=6, eligiendo el más lejos del jugador dentro del primer anillo con candidatos
-                                for r in 1..=6 {
-                                    let mut ring_best: Option<(usize,usize,f32)> = None;
-                                    let r_i = r as isize;
-                                    for dy in -r_i..=r_i {
-                                        for dx in -r_i..=r_i {
-                                            if dx.abs().max(dy.abs()) != r_i { continue; }
+                                // probar anillos de radio 1..=6: el primero cuyo candidato más lejano respete la distancia mínima
+                                for r in 1..=6isize {
+                                    let mut ring: Vec<(usize,usize)> = Vec::new();
+                                    for dy in -r..=r {
+                                        for dx in -r..=r {
+                                            if dx.abs().max(dy.abs()) != r { continue; }
                                             let ii = gi as isize + dx;
                                             let jj = gj as isize + dy;
                                             if ii < 0 || jj < 0 { continue; }
                                             let (ii, jj) = (ii as usize, jj as usize);
-                                            if jj >= h || ii >= maze[jj].len() { continue; }
-                                            if maze[jj][ii] != ' ' { continue; }
-                                            let wx = (ii as f32 + 0.5) * BLOCK;
-                                            let wy = (jj as f32 + 0.5) * BLOCK;
-                                            let dxp = wx - player.pos.x; let dyp = wy - player.pos.y;
-                                            let d2p = dxp*dxp + dyp*dyp;
-                                            // evitar spawns demasiado cerca del jugador (< 6 celdas)
-                                            if d2p < (6.0*BLOCK)*(6.0*BLOCK) { continue; }
-                                            if ring_best.map(|b| d2p > b.2).unwrap_or(true) {
-                                                ring_best = Some((ii,jj,d2p));
-                                            }
+                                            if jj >= maze.len() || ii >= maze[jj].len() { continue; }
+                                            if maze[jj][ii] == FLOOR { ring.push((ii, jj)); }
                                         }
                                     }
-                                    if let Some((ii,jj,_)) = ring_best {
-                                        enemy.x = (ii as f32 + 0.5) * BLOCK;
-                                        enemy.y = (jj as f32 + 0.5) * BLOCK;
-                                        placed = true;
+                                    if cfg.spawn_unseen { ring = unseen_cells(&ring, &maze, block, &player); }
+                                    if let Some((c, true)) = pick_spawn_cell(&ring, block, px, py, cfg.spawn_min_cells, &mut rng) {
+                                        spawn = Some(c);
                                         break;
                                     }
                                 }
                             }
                         }
-                        if !placed {
-                            // fallback: buscar celda libre lejana al jugador
-                            let mut best: Option<(usize,usize,f32)> = None;
-                            for (j,row) in maze.iter().enumerate() {
-                                for (i,&c) in row.iter().enumerate() {
-                                    if c == ' ' {
-                                        let wx = (i as f32 + 0.5) * BLOCK;
-                                        let wy = (j as f32 + 0.5) * BLOCK;
-                                        let dx = wx - player.pos.x; let dy = wy - player.pos.y;
-                                        let d2 = dx*dx + dy*dy;
-                                        if d2 > 10.0*BLOCK*10.0*BLOCK {
-                                            if best.map(|b| d2 > b.2).unwrap_or(true) { best = Some((i,j,d2)); }
-                                        }
-                                    }
-                                }
-                            }
-                            if let Some((i,j,_)) = best {
-                                enemy.x = (i as f32 + 0.5) * BLOCK;
-                                enemy.y = (j as f32 + 0.5) * BLOCK;
-                            }
+                        if spawn.is_none() {
+                            // fallback: celda libre más lejana (cumple el mínimo si alguna puede)
+                            let mut cells: Vec<(usize,usize)> = maze.iter().enumerate()
+                                .flat_map(|(j,row)| row.iter().enumerate().filter(|(_,c)| **c == FLOOR).map(move |(i,_)| (i,j)))
+                                .collect();
+                            if cfg.spawn_unseen { cells = unseen_cells(&cells, &maze, block, &player); }
+                            spawn = pick_spawn_cell(&cells, block, px, py, cfg.spawn_min_cells, &mut rng).map(|(c, _)| c);
                         }
+                        if let Some((i,j)) = spawn {
+                            enemy.x = (i as f32 + 0.5) * block;
+                            enemy.y = (j as f32 + 0.5) * block;
+                        }
+                        events.emit(GameEvent::EnemySpawned { x: enemy.x, y: enemy.y });
+                    }
+                }
+                if spawn_announce_edge(enemy.active, &mut spawn_announced) && spawn_announce_on {
+                    spawn_announce_timer = SPAWN_ANNOUNCE_SECS;
+                    shake.add_trauma(SPAWN_ANNOUNCE_TRAUMA);
+                    if let Some(a) = audio.as_ref() { a.play_spawn_cue(); }
+                }
+                // Cordura: baja al ser visto o con el enemigo cerca, se recupera lentamente
+                {
+                    let seen = enemy.active && enemy.sees_player(&maze, player.pos.x, player.pos.y, block_size);
+                    let (dx, dy) = (enemy.x - player.pos.x, enemy.y - player.pos.y);
+                    let near = enemy.active && dx*dx + dy*dy < 300.0*300.0;
+                    let rate = if seen { -0.12 } else if near { -0.05 } else { 0.04 };
+                    let breath = breath_drain(&mut player, sanity);
+                    sanity = (sanity + (rate - breath) * dt).clamp(0.0, 1.0);
+                    let (_, beat, speed_mul) = sanity_effects(sanity);
+                    player.speed_mul = speed_mul;
+                    heartbeat_timer -= dt;
+                    if beat > 0.0 && heartbeat_timer <= 0.0 {
+                        if let Some(a) = audio.as_ref() { a.play_heartbeat(beat); }
+                        heartbeat_timer = 1.2 - 0.55 * beat;
                     }
                 }
+                // Enemigo calmándose: tono suave cada vez más débil según avanza el cooldown
+                calm_cue_timer -= dt;
+                if enemy.active && enemy.is_cooling_down() && calm_cue_timer <= 0.0 {
+                    if let Some(a) = audio.as_ref() { a.play_calm_cue(1.0 - enemy.cooldown_progress()); }
+                    calm_cue_timer = 0.8;
+                }
+                grab_reveal_timer = (grab_reveal_timer - dt).max(0.0);
+                spawn_announce_timer = (spawn_announce_timer - dt).max(0.0);
+                shake.update(dt);
+                enemy.set_detect_boost(grab_reveal_boost(grab_reveal_timer));
+                player.update_battery(dt);
+                enemy.set_conspicuity(flashlight_conspicuity(if player.flashlight_on { 1.0 } else { 0.0 }));
+                // Huida: el enemigo ve menos lejos para dar una oportunidad al salir (p. ej. L2, que lo pone junto a la salida)
+                enemy.set_sight_scale(cfg.sight_scale(game_state));
+                enemy.set_chase_scale(cfg.escape_mode.chase_scale(game_state));
+                // Enemigo fotosensible: más lento bajo el haz de la linterna, más rápido a oscuras
+                enemy.set_light_scale(cfg.light_scale(enemy_in_flashlight(&maze, &player, &enemy, block_size)));
+                // Anti-campeo: quieto demasiado tiempo, el enemigo viene a buscar a tu posición
+                let player_moved = (player.pos.x - player_prev.x).abs() + (player.pos.y - player_prev.y).abs() > 0.01;
+                if anti_camp_due(&mut idle_timer, player_moved, dt, settings.anti_camp, settings.anti_camp_secs) {
+                    enemy.nudge_to(player.pos.x, player.pos.y);
+                }
                 if enemy.active {
-                    enemy.update(&maze, player.pos.x, player.pos.y, block_size, dt);
+                    if use_distance_field { dist_field.update(&maze, block_size, player.pos.x, player.pos.y); }
+                    let field = if use_distance_field { Some(&dist_field) } else { None };
+                    enemy.set_player_view(player.a, player.fov);
+                    // Orbs como cebo: el enemigo ronda el grupo de orbs activos más cercano
+                    enemy.set_orb_lure(if cfg.orb_lure {
+                        let active: Vec<(f32, f32)> = orbs.iter().filter(|o| o.active).map(|o| (o.x, o.y)).collect();
+                        orb_cluster_target(&active, (enemy.x, enemy.y), ORB_LURE_CLUSTER_CELLS * block_size as f32)
+                    } else { None });
+                    enemy.set_player_step(player.pos.x - player_prev.x, player.pos.y - player_prev.y);
+                    // Pasos del jugador: ruido que oye el enemigo ciego (quieto eres invisible para él)
+                    if player_moved {
+                        let cells = if player.sprinting { FOOTSTEP_NOISE_SPRINT_CELLS } else { FOOTSTEP_NOISE_CELLS };
+                        enemy.hear_noise(player.pos.x, player.pos.y, cells * player.noise_mul() * block_size as f32);
+                    }
+                    let (pi, pj) = ((player.pos.x / block_size as f32).floor() as usize, (player.pos.y / block_size as f32).floor() as usize);
+                    enemy.set_player_safe(maze.get(pj).and_then(|r| r.get(pi)).is_some_and(|&c| tiles().is_safe(c)));
+                    enemy.update(&maze, player.pos.x, player.pos.y, block_size, dt, field);
+                    if enemy.just_spotted() {
+                        times_spotted += 1;
+                        events.emit(GameEvent::PlayerSeen);
+                        // Avisa a los demás enemigos en el radio del nivel (hoy la lista tiene solo este)
+                        let radius = cfg.alert_radius_cells * block_size as f32;
+                        broadcast_alert(std::slice::from_mut(&mut enemy), 0, player.pos.x, player.pos.y, radius);
+                    }
                 }
             }
         }
 
     // Recoger orbs
         {
-            let pr = 18.0;
-            for (_idx, o) in orbs.iter_mut().enumerate() {
-                if o.active {
-                    let dx = o.x - player.pos.x;
-                    let dy = o.y - player.pos.y;
-                    if (dx*dx + dy*dy).sqrt() <= pr {
-                        o.active = false;
-                        score += 1;
-                        if let Some(a) = audio.as_mut() { a.play_orb(); }
-                    }
-                }
+            let respawn = if cfg.arcade { ORB_RESPAWN_SECS } else { 0.0 };
+            for _ in 0..collect_orbs(&mut orbs, player.pos.x, player.pos.y, respawn, &mut score, events.as_mut()) {
+                grab_reveal_timer = GRAB_REVEAL_SECS;
+                shake.add_trauma(ORB_PICKUP_TRAUMA);
+                if let Some(a) = audio.as_mut() { a.play_orb(); }
             }
         }
 
+    // Arcade: reaparición de orbs y cuenta atrás (se acaba el tiempo = fin de partida con la puntuación)
+    if cfg.arcade && game_state == GameState::Playing {
+        tick_orb_respawns(&mut orbs, &maze, block_size as f32, player.pos.x, player.pos.y, dt, &mut rng);
+        arcade_time_left = (arcade_time_left - dt).max(0.0);
+        if arcade_time_left <= 0.0 {
+            game_state = GameState::Won;
+            events.emit(GameEvent::LevelWon { score, secs: window.get_time() as f32 - level_start_time });
+        }
+    }
+
     // Estado de juego
-    if game_state == GameState::Playing && !orbs.iter().any(|o| o.active) {
+    if game_state == GameState::Playing && !cfg.arcade && (score >= required_orbs || !orbs.iter().any(|o| o.active)) {
             game_state = GameState::Escaping;
+            // Con orbs aún en el mapa no es obvio que la salida ya se abrió: avisar
+            if orbs.iter().any(|o| o.active) { unlock_hint_timer = UNLOCK_HINT_SECS; }
         }
-    if game_state == GameState::Escaping && touched_exit {
+    unlock_hint_timer = (unlock_hint_timer - dt).max(0.0);
+    if game_state.in_play() {
+        let block = block_size as f32;
+        let in_view = exit_in_view(&maze, block, player.pos.x, player.pos.y, player.a, player.fov, EXIT_SIGHT_CELLS * block);
+        exit_seen_timer = tick_exit_seen(exit_seen_timer, in_view, dt);
+    }
+    if game_state.in_play() {
+        heatmap_add(&mut heat, block_size as f32, player.pos.x, player.pos.y, dt);
+    }
+    let escaped = cfg.escaped(block_size as f32, player.pos.x, player.pos.y, spawn_cell, exit_counts(touched_exit, exit_must_be_seen, exit_seen_timer));
+    if game_state == GameState::Escaping && escaped {
             game_state = GameState::Won;
+            events.emit(GameEvent::LevelWon { score, secs: window.get_time() as f32 - level_start_time });
+            let heat_path = format!("heatmap_{}.csv", cfg.file.trim_end_matches(".txt"));
+            if let Err(e) = export_heatmap_csv(&heat, &heat_path) { eprintln!("heatmap: {}", e); }
+            let (save_meta, save_aggression) = record_won_run(&mut meta, &mut aggression, score, times_spotted, practice_run);
+            if save_meta { let _ = meta.save(META_FILE); }
+            if save_aggression { let _ = aggression.save(AGGRESSION_FILE); }
         }
 
         framebuffer.clear();
+        framebuffer.stats.reset();
+        let mut color_graded = false;
 
         if matches!(game_state, GameState::Menu) {
             // Menu screen: enhanced red-themed look with level list
@@ -471,8 +1594,42 @@ fn main() {
                 } else {
                     d.draw_text(&label, base_x, y, 34, Color::new(230, 220, 220, 220));
                 }
+                let deaths = stats.deaths(level_cfg(i).file);
+                if deaths > 0 {
+                    d.draw_text(&format!("Deaths: {}", deaths), base_x + 200 + THUMB_W + 12, y + 8, 20, Color::new(255, 160, 160, 220));
+                }
+                if let Some(Some(thumb)) = level_thumbs.get(i as usize) {
+                    let (tw, th) = thumb_fit(thumb.width() as usize, thumb.height() as usize, THUMB_W, THUMB_H);
+                    let (tx, ty) = (base_x + 200, y - 4 + (THUMB_H - th) / 2);
+                    d.draw_rectangle(tx - 2, ty - 2, tw + 4, th + 4, Color::new(0, 0, 0, 180));
+                    d.draw_texture_pro(thumb,
+                        Rectangle { x: 0.0, y: 0.0, width: thumb.width() as f32, height: thumb.height() as f32 },
+                        Rectangle { x: tx as f32, y: ty as f32, width: tw as f32, height: th as f32 },
+                        Vector2::zero(), 0.0, Color::WHITE);
+                }
             }
             d.draw_text("1/2/3: Choose | ENTER: Play | ESC: Exit", base_x, base_y + 3*48 + 20, 22, Color::new(230,230,230,220));
+            let kind_label = match (enemy_kind, mirror_same) {
+                (EnemyKind::Hunter, _) => "V: Enemy = Hunter",
+                (EnemyKind::Angel, _) => "V: Enemy = Angel (moves when unseen)",
+                (EnemyKind::Mirror, false) => "V: Enemy = Mirror (moves opposite to you)",
+                (EnemyKind::Mirror, true) => "V: Enemy = Mirror (copies your moves)",
+                (EnemyKind::Blind, _) => "V: Enemy = Blind (hunts your footsteps)",
+            };
+            d.draw_text(kind_label, base_x, base_y + 3*48 + 52, 22, Color::new(255,180,180,220));
+            let meta_label = if meta.enabled { format!("M: Meta ON ({} orbs)", meta.total_orbs) } else { "M: Meta OFF".to_string() };
+            d.draw_text(&meta_label, base_x, base_y + 3*48 + 84, 22, Color::new(255,180,180,220));
+            let exit_label = if exit_must_be_seen { "L: Exit must be seen ON" } else { "L: Exit must be seen OFF" };
+            d.draw_text(exit_label, base_x, base_y + 3*48 + 116, 22, Color::new(255,180,180,220));
+            let practice_label = if practice_mode { "P: Practice ON (no enemy, full map, not scored)" } else { "P: Practice OFF" };
+            d.draw_text(practice_label, base_x, base_y + 3*48 + 148, 22, Color::new(255,180,180,220));
+            let extraction_label = if extraction_mode { "X: Extraction ON (return to start to win)" } else { "X: Extraction OFF" };
+            d.draw_text(extraction_label, base_x, base_y + 3*48 + 180, 22, Color::new(255,180,180,220));
+            d.draw_text(&format!("D: Difficulty = {}", difficulty.label()), base_x, base_y + 3*48 + 212, 22, Color::new(255,180,180,220));
+            let aggression_label = if aggression.enabled { format!("G: Adaptive enemy ON (aggression {:+.2})", aggression.level) } else { "G: Adaptive enemy OFF".to_string() };
+            d.draw_text(&aggression_label, base_x, base_y + 3*48 + 276, 22, Color::new(255,180,180,220));
+            let arcade_label = if arcade_mode { "A: Arcade ON (timed, orbs respawn, score)" } else { "A: Arcade OFF" };
+            d.draw_text(arcade_label, base_x, base_y + 3*48 + 244, 22, Color::new(255,180,180,220));
 
             // Right panel for teto.gif with slight bobbing animation & red tint
             let panel_x = (window_width as f32 * 0.55) as i32;
@@ -500,6 +1657,7 @@ fn main() {
                 let tw = d.measure_text(msg, 24);
                 d.draw_text(msg, panel_x + (window_width - panel_x - tw)/2, window_height/2, 24, Color::RED);
             }
+            if fade_alpha > 0 { d.draw_rectangle(0, 0, window_width, window_height, Color::new(0, 0, 0, fade_alpha)); }
             continue; // skip rest of render loop while in menu
         } else if !mode_3d {
             // Vista 2D debug
@@ -511,7 +1669,7 @@ fn main() {
             for i in 0..num_rays {
                 let t = i as f32 / num_rays as f32;
                 let ray_angle = player.a - (player.fov / 2.0) + (player.fov * t);
-                cast_ray(&mut framebuffer, &maze, &player, ray_angle, block_size, true);
+                cast_ray(&mut framebuffer, &maze, &player, ray_angle, block_size, true, cfg.toroidal);
             }
         } else {
             // 3D + sprites
@@ -526,67 +1684,140 @@ fn main() {
             let near = dist_now < 200.0;
             let panic_mode = enemy_sees || near;
             texman.set_alert_mode(panic_mode);
+            let vig_target = if panic_vignette_on && enemy.active { panic_vignette_target(dist_now, enemy_sees, enemy.is_chasing()) } else { 0.0 };
+            panic_vignette = smooth_toward(panic_vignette, vig_target, PANIC_VIGNETTE_RATE, dt);
             // Sin tinte verde en el enemigo cuando persigue
 
+            // Cámara: el cuerpo (player.pos) decide la detección; al asomarse solo se desplaza la vista
+            let mut cam = player.clone();
+            cam.pos = camera_pos(&player, &maze, block_size);
+            cam.a += shake.yaw_offset() * player.shake_mul();
+
             // Render principal
             render_3d(
                 &mut framebuffer,
                 &maze,
                 block_size,
-                &player,
+                &cam,
                 &texman,
                 &mut zbuffer,
                 time_sec,
                 panic_mode,
                 cfg.brightness,
+                wall_style,
+                if budget.enabled(Effect::Supersampling) { ray_factor } else { 1 },
+                cfg.mist,
+                aniso_on && budget.enabled(Effect::Anisotropic),
+                cfg.toroidal,
+                FLAT_COLUMN_PX,
             );
+            if depth_debug {
+                draw_depth_view(&mut framebuffer, &zbuffer, block_size, 16.0 * block_size as f32);
+            }
 
             // While seen: play continuous loop (enemy_seen). Stop when not seen. (No player alert sound.)
             if let Some(a) = audio.as_mut() {
-                if enemy_sees {
+                if enemy_sees && game_state != GameState::Paused {
                     a.start_enemy_seen_loop();
                 } else {
                     a.stop_enemy_seen_loop();
                 }
             }
 
-            // Scale blur with proximity but gate by performance: only apply when running ~55+ FPS
+            // Scale blur with proximity; the effect budget turns it off when frames get slow
             let strong_range = 200.0; // strongest effect here
             let far_range = 600.0;    // very light effect up to here
             let t_close = (1.0 - (dist_now / strong_range)).clamp(0.0, 1.0);
             let t_far = (1.0 - (dist_now / far_range)).clamp(0.0, 1.0);
             let t = (0.5 * t_far + 0.5 * t_close).clamp(0.0, 1.0);
-            let perf_ok = dt <= (1.0 / 55.0) as f32;
-            if perf_ok && t > 0.05 {
+            // Sin post-procesado en la vista de profundidad
+            if budget.enabled(Effect::Blur) && !depth_debug && t > 0.05 {
                 // Single-pass lighter blur to reduce CPU cost
                 let strength = (0.35 + 0.45 * t).min(0.8);
                 let passes = 1;
                 let radius = (0.60 + 0.25 * t).min(0.85);
                 framebuffer.apply_circular_blur(strength, passes, radius);
             }
+            // Aviso de aparición: distorsión breve que se desvanece con el temporizador
+            if spawn_announce_timer > 0.0 && !depth_debug {
+                framebuffer.apply_anxiety_blur(spawn_announce_timer / SPAWN_ANNOUNCE_SECS, 2);
+            }
             // Flashlight overlay is drawn later to sit above the world
 
+            // Trampas marcadas en el suelo (cruz dentro de la celda)
+            if !depth_debug {
+                draw_trap_markers(&mut framebuffer, &maze, &cam, block_size, &zbuffer, 8.0, Color::new(220, 100, 30, 255));
+            }
+
+            // Pickup radius rings on the ground around nearby orbs
+            if show_pickup_rings {
+                let near = 4.0 * block_size as f32;
+                for o in orbs.iter().filter(|o| o.active) {
+                    let (dx, dy) = (o.x - player.pos.x, o.y - player.pos.y);
+                    if dx*dx + dy*dy > near*near { continue; }
+                    draw_ground_ring(&mut framebuffer, &cam, block_size, &zbuffer, o.x, o.y, ORB_PICKUP_RADIUS, Color::new(255, 240, 80, 255));
+                }
+            }
+
+            // Sombra del enemigo: elipse oscura en el suelo, alargada en sentido contrario al jugador
+            if enemy_shadow_on && cfg.enemy_enabled && enemy.active && !depth_debug {
+                draw_ground_shadow(&mut framebuffer, &cam, block_size, &zbuffer, enemy.x, enemy.y, 0.55);
+            }
+
+            // Haz de luz sobre la salida durante la huida (ocluido por las paredes más cercanas)
+            if settings.exit_beam && !depth_debug {
+                let home = if cfg.extraction { Some(spawn_cell) } else { None };
+                let glow = (200.0 + 55.0 * (time_sec * 2.0).sin()) as u8;
+                for (bx, by) in exit_beacons(&maze, block_size as f32, game_state, home) {
+                    draw_light_beam(&mut framebuffer, &cam, block_size, &zbuffer, bx, by, Color::new(glow, glow, (glow as f32 * 0.8) as u8, 255));
+                }
+            }
+
             // sprites depth-sorted
-            let mut sprites: Vec<(&str, f32, f32, char, f32, f32)> = Vec::new();
+            let mut sprites: Vec<(&str, f32, f32, char, f32, f32, Color, f32)> = Vec::new();
+            // Tintes: los orbs laten suavemente, el enemigo se enrojece en pánico
+            let pulse = (215.0 + 40.0 * (time_sec * 4.0).sin()) as u8;
+            let orb_tint = Color::new(pulse, pulse, pulse, 255);
+            let enemy_tint = if panic_mode { Color::new(255, 110, 110, 255) } else { Color::WHITE };
+            // Sprite sizes are tuned for the default cell; scale so they keep their look on other grids
+            let sprite_scale = block_size as f32 / BLOCK;
             for (_idx, o) in orbs.iter().enumerate().filter(|(_,o)| o.active).map(|(i,o)|(i,o)) {
                 // Orbs baseline at v_offset ~0.10
-                sprites.push(("orb", o.x, o.y, 'o', 28.0, 0.10));
+                sprites.push(("orb", o.x, o.y, 'o', 28.0 * sprite_scale, 0.10, orb_tint, 1.0));
             }
             if cfg.enemy_enabled && enemy.active {
                 // Enemy aligned at the same baseline as orbs for cohesion
-                sprites.push(("enemy", enemy.x, enemy.y, 'N', 90.0, 0.10));
+                let squash = if enemy_walk_anim { enemy.walk_squash() } else { 1.0 };
+                sprites.push(("enemy", enemy.x, enemy.y, 'N', 90.0 * sprite_scale, 0.10, enemy_tint, squash));
+            }
+            if !depth_debug { draw_sprites_sorted(&mut framebuffer, &cam, &texman, &zbuffer, &mut sprites, Some(MAX_DRAWN_SPRITES)); }
+
+            // Color grade: cool and slightly muted when calm, desaturated red as panic rises
+            if budget.enabled(Effect::ColorGrade) && !depth_debug {
+                let panic_t = if enemy_sees { 1.0 } else { (1.0 - (dist_now / 600.0)).clamp(0.0, 1.0) };
+                let mix = |a: u8, b: u8| -> u8 { (a as f32 + (b as f32 - a as f32) * panic_t) as u8 };
+                // Tema cálido desbloqueado por la meta-progresión
+                let tint = if meta.unlocked(THEME_UNLOCK_ORBS) { Color::new(mix(255, 255), mix(225, 150), mix(190, 150), 255) } else { Color::new(mix(225, 255), mix(235, 150), mix(255, 150), 255) };
+                let (desat, _, _) = sanity_effects(sanity);
+                framebuffer.apply_color_grade(tint, (0.9 - 0.45 * panic_t) * (1.0 - desat), 1.0 + 0.1 * panic_t);
+                color_graded = true;
             }
-            draw_sprites_sorted(&mut framebuffer, &player, &texman, &zbuffer, &mut sprites);
+            // Viñeta de pánico: el borde se cierra durante la persecución y se abre al estar a salvo
+            if panic_vignette > 0.01 && !depth_debug { framebuffer.apply_vignette(panic_vignette); }
         }
+        // Scanlines CRT: último post-proceso, justo antes de subir el framebuffer
+        if settings.scanlines { framebuffer.apply_scanlines(settings.scanline_intensity, settings.crt_mask); }
 
     // HUD + MINIMAPA
     let fps_now = window.get_fps();
     // Transición a estado Caught cuando el enemigo te alcanza
-    if matches!(game_state, GameState::Playing | GameState::Escaping) && cfg.enemy_enabled {
+    if game_state.in_play() && cfg.enemy_enabled {
             let dx = enemy.x - player.pos.x;
             let dy = enemy.y - player.pos.y;
-            if (dx*dx + dy*dy).sqrt() < 26.0 {
-                game_state = GameState::Caught;
+            if (dx*dx + dy*dy).sqrt() < diff.catch_dist {
+                catch_player(&mut game_state, &mut stats, cfg.file);
+                events.emit(GameEvent::PlayerCaught);
+                let _ = stats.save(STATS_FILE);
                 if !caught_sfx_played {
                     if let Some(a) = audio.as_mut() { a.play_player_caught(); }
                     caught_sfx_played = true;
@@ -607,18 +1838,21 @@ fn main() {
             d.clear_background(Color::BLACK);
 
             // Actualizar audio (no-op para rodio, placeholder)
-            if let Some(a) = audio.as_ref() { a.update(); }
+            if let Some(a) = audio.as_mut() { a.update(); }
             // Subir framebuffer a textura y dibujar de un golpe (rápido)
             framebuffer.upload_to_texture(&mut fb_tex);
             // Escalar la textura low-res del framebuffer a la ventana completa
             let src = Rectangle { x: 0.0, y: 0.0, width: fb_tex.width() as f32, height: fb_tex.height() as f32 };
-            let dst = Rectangle { x: 0.0, y: 0.0, width: window_width as f32, height: window_height as f32 };
+            // F10: escala entera (el framebuffer ya se filtra por vecino más cercano)
+            let blit = if settings.pixel_perfect { integer_scale_rect } else { letterbox_rect };
+            let dst = blit(window_width as f32, window_height as f32, fb_tex.width() as f32, fb_tex.height() as f32);
             let origin = Vector2 { x: 0.0, y: 0.0 };
             d.draw_texture_pro(&fb_tex, src, dst, origin, 0.0, Color::WHITE);
 
             // Footsteps SFX solo cuando hay movimiento con WASD
             if let Some(a) = audio.as_mut() {
                 let moving_keys = { let (w,a_key,s,d_key) = wasd_state; w || a_key || s || d_key };
+                let surface = surface_at(&maze, block_size as f32, player.pos.x, player.pos.y);
                 static mut WAS_MOVING: bool = false;
                 static mut LAST_PX: f32 = 0.0;
                 static mut LAST_PY: f32 = 0.0;
@@ -632,13 +1866,13 @@ fn main() {
                         LAST_PX = player.pos.x; LAST_PY = player.pos.y;
                         if !WAS_MOVING {
                             // immediate first step on movement start
-                            a.force_player_step();
+                            a.force_player_step(surface);
                             ACCUM = 0.0;
                             WAS_MOVING = true;
                         } else {
                             let stride = if player.sprinting { 22.0 } else { 34.0 };
                             if ACCUM >= stride {
-                                a.play_player_step(player.sprinting);
+                                a.play_player_step(surface, player.sprinting);
                                 ACCUM -= stride;
                             }
                         }
@@ -649,7 +1883,7 @@ fn main() {
                         a.stop_player_steps(); // hard stop foot audio when idle
                     }
                 }
-                if enemy.active {
+                if enemy.active && game_state != GameState::Paused {
                     // Scale enemy step volume by distance (closer = louder)
                     let dx = enemy.x - player.pos.x;
                     let dy = enemy.y - player.pos.y;
@@ -659,12 +1893,24 @@ fn main() {
                         let t = (1.0 - ((dist - 30.0) / (450.0 - 30.0))).clamp(0.0, 1.0);
                         0.25 + t * 1.45
                     };
-                    a.play_enemy_step_with_volume(vol);
+                    // Solo si de verdad se movió (parado, escaneando o bloqueado no hay pasos)
+                    if enemy.moved_last_update() { a.play_enemy_step_with_volume(vol); }
+                    if radar_ping_on {
+                        radar_ping_timer -= dt;
+                        if radar_ping_timer <= 0.0 {
+                            let mut rel = dy.atan2(dx) - player.a;
+                            while rel >  std::f32::consts::PI { rel -= 2.0*std::f32::consts::PI; }
+                            while rel < -std::f32::consts::PI { rel += 2.0*std::f32::consts::PI; }
+                            let pitch = 0.8 + 0.8 * (1.0 - dist / 1200.0).clamp(0.0, 1.0);
+                            a.play_radar_ping(rel.sin(), pitch);
+                            radar_ping_timer = radar_ping_interval(dist);
+                        }
+                    }
                 }
             }
 
             // Flashlight overlay (dibujar ANTES del HUD/minimapa para que la UI quede encima)
-            {
+            if !depth_debug {
                 // Centro desplazado hacia delante + sacudida si te persigue/ve
                 let look_dx = player.a.cos();
                 let look_dy = player.a.sin();
@@ -696,58 +1942,25 @@ fn main() {
                 let base_r = 300.0;     // much darker baseline
                 let min_r = 140.0;      // much tighter minimum
                 let t = if seen { (0.6 + 0.6 * proximity).clamp(0.0, 1.0) } else { 0.0 };
-                let r0 = base_r * (1.0 - t) + min_r * t;
-                let hw = (window_width as f32) * 0.5;
-                let hh = (window_height as f32) * 0.5;
-                let r_max = (hw*hw + hh*hh).sqrt() + 64.0; // asegurar esquinas cubiertas
-                let segs: i32 = 96; // fewer segments for performance
-                // Aplicar ~70% de oscuridad fuera del radio con borde suave
-                let base_alpha: u8 = 178; // ~70% darkness (0.7 * 255)
-                let feather: f32 = 36.0;  // slightly narrower feather for fewer ring draws
-                let inner_soft_start = r0.max(0.0);
-                let inner_soft_end = (r0 + feather).min(r_max);
-
-                // 1) Borde suave: de 0 -> base_alpha en [r0 .. r0+feather]
-                let steps = 6; // fewer steps to reduce draw calls
-                for s in 0..steps {
-                    let t0 = s as f32 / steps as f32;
-                    let t1 = (s + 1) as f32 / steps as f32;
-                    let ri = inner_soft_start + (inner_soft_end - inner_soft_start) * t0;
-                    let ro = inner_soft_start + (inner_soft_end - inner_soft_start) * t1;
-                    let a = ((base_alpha as f32) * t1).round().clamp(0.0, 255.0) as u8;
-                    d.draw_ring(
-                        Vector2 { x: cx, y: cy },
-                        ri,
-                        ro,
-                        0.0,
-                        360.0,
-                        segs,
-                        Color::new(0, 0, 0, a),
-                    );
-                }
-
-                // 2) Sólido exterior: un anillo grande con ~70% de oscuridad
-                if inner_soft_end < r_max {
-                    d.draw_ring(
-                        Vector2 { x: cx, y: cy },
-                        inner_soft_end,
-                        r_max,
-                        0.0,
-                        360.0,
-                        segs,
-                        Color::new(0, 0, 0, base_alpha),
-                    );
+                let mut r0 = base_r * (1.0 - t) + min_r * t;
+                // Linterna apagada: casi a oscuras, solo un pequeño halo alrededor
+                if !player.flashlight_on { r0 = 70.0; }
+                // ~70% de oscuridad fuera del radio (algo más y rojiza al ser visto), borde suave del degradado
+                let base_alpha: u8 = if !player.flashlight_on { 238 } else if seen { 200 } else { 178 };
+                let tint = if seen { Color::new(24, 0, 4, base_alpha) } else { Color::new(0, 0, 0, base_alpha) };
+                if let Some(tex) = flashlight_tex.as_ref() {
+                    draw_flashlight(&mut d, tex, cx, cy, r0, window_width, window_height, tint);
                 }
             }
 
-            // Panic red tint overlay when seen or very near
+            // Panic red tint overlay when seen or very near (fallback when the color grade was skipped)
             {
                 let enemy_sees = enemy.sees_player(&maze, player.pos.x, player.pos.y, block_size);
                 let dx = enemy.x - player.pos.x;
                 let dy = enemy.y - player.pos.y;
                 let dist = (dx*dx + dy*dy).sqrt();
                 let near_t = (1.0 - (dist / 600.0)).clamp(0.0, 1.0);
-                if enemy_sees || near_t > 0.0 {
+                if !color_graded && !depth_debug && (enemy_sees || near_t > 0.0) {
                     // Blend intensity: stronger when seen, otherwise scale by proximity
                     let base = if enemy_sees { 110 } else { 0 };
                     let extra = (near_t * 120.0) as i32;
@@ -757,9 +1970,39 @@ fn main() {
                     d.draw_rectangle(0, 0, window_width, window_height, Color::new(180, 10, 24, alpha));
                 }
             }
+            // Destello del aviso de aparición (se apaga con el temporizador)
+            if spawn_announce_timer > 0.0 {
+                let k = spawn_announce_timer / SPAWN_ANNOUNCE_SECS;
+                d.draw_rectangle(0, 0, window_width, window_height, Color::new(255, 230, 230, (k * k * 150.0) as u8));
+            }
 
             // HUD: simple FPS only
-            d.draw_text(&format!("FPS: {}", fps_now), 10, 10, 20, Color::WHITE);
+            let fps_txt = if budget.shed() > 0 { format!("FPS: {}  (fx -{})", fps_now, budget.shed()) } else { format!("FPS: {}", fps_now) };
+            d.draw_text(&fps_txt, 10, 10, 20, Color::WHITE);
+            if show_render_stats {
+                d.draw_text(&framebuffer.stats.summary(), 140, 12, 18, Color::new(160, 255, 200, 255));
+            }
+            if render_scale_msg_timer > 0.0 {
+                let msg = format!("Render scale {}% ({}x{})", (render_scale * 100.0).round() as i32, framebuffer.width, framebuffer.height);
+                let tw = d.measure_text(&msg, 24);
+                let alpha = (255.0 * (render_scale_msg_timer / RENDER_SCALE_MSG_SECS).min(1.0)) as u8;
+                d.draw_text(&msg, (window_width - tw)/2, 60, 24, Color::new(255, 255, 255, alpha));
+            }
+            // Examinar: qué pared toca el rayo central (para cuadrar la vista 3D con el fichero del laberinto)
+            if show_examine {
+                let dist = cast_ray(&mut framebuffer, &maze, &player, player.a, block_size, false, cfg.toroidal);
+                let txt = if dist > 0.0 {
+                    let (hx, hy) = (player.pos.x + player.a.cos() * dist, player.pos.y + player.a.sin() * dist);
+                    match examine_cell(&maze, block_size as f32, hx, hy, player.a, cfg.toroidal) {
+                        Some((i, j, c)) => format!("hit ({:.0}, {:.0})  cell ({}, {})  tile '{}'", hx, hy, i, j, c),
+                        None => format!("hit ({:.0}, {:.0})  off the map", hx, hy),
+                    }
+                } else { "no hit".to_string() };
+                let tw = d.measure_text(&txt, 20);
+                d.draw_line(window_width/2 - 6, window_height/2, window_width/2 + 6, window_height/2, Color::YELLOW);
+                d.draw_line(window_width/2, window_height/2 - 6, window_width/2, window_height/2 + 6, Color::YELLOW);
+                d.draw_text(&txt, (window_width - tw)/2, window_height/2 + 14, 20, Color::YELLOW);
+            }
             // HUD pequeño: estado de audio y bandera "Seen"
             let audio_ok = if audio.is_some() { "Audio: OK" } else { "Audio: OFF" };
             d.draw_text(audio_ok, 10, 30, 18, Color::WHITE);
@@ -769,16 +2012,79 @@ fn main() {
             if player.sprinting {
                 d.draw_text("SPRINT", 10, 40, 20, Color::RED);
             }
+            if player.holding_breath {
+                d.draw_text("Holding breath", 10, 70, 18, Color::new(160, 200, 255, 255));
+            }
             let remaining = orbs.iter().filter(|o| o.active).count();
             let bottom_y = window_height - 28;
-            d.draw_text(&format!("Orbs: {} / {}", score, score + remaining), 10, bottom_y, 22, Color::WHITE);
+            let to_unlock = orbs_to_unlock(required_orbs, score);
+            let orbs_txt = if cfg.arcade {
+                let t = arcade_time_left.ceil() as i32;
+                format!("Score: {}   Time: {}:{:02}", score, t / 60, t % 60)
+            } else if required_orbs < score + remaining && game_state == GameState::Playing {
+                format!("Orbs: {} / {} ({} more to open the exit)", score, score + remaining, to_unlock)
+            } else { format!("Orbs: {} / {}", score, score + remaining) };
+            d.draw_text(&orbs_txt, 10, bottom_y, 22, Color::WHITE);
+            if practice_run {
+                let tw = d.measure_text("PRACTICE", 26);
+                d.draw_text("PRACTICE", (window_width - tw) / 2, bottom_y - 30, 26, Color::new(255, 220, 60, 255));
+            }
+            d.draw_text(&format!("Sanity: {:.0}%", sanity * 100.0), 200, bottom_y, 22, if sanity < 0.5 { Color::RED } else { Color::WHITE });
+            if radar_ping_on {
+                d.draw_text("RADAR", 10, bottom_y - 24, 18, Color::new(120, 255, 160, 255));
+            }
+            // Batería de la linterna
+            {
+                let (bx, by) = (window_width - 150, bottom_y);
+                let col = if player.battery < 0.2 { Color::RED } else if player.flashlight_on { Color::new(255, 230, 120, 255) } else { Color::GRAY };
+                d.draw_text(if player.flashlight_on { "F" } else { "f" }, bx - 18, by, 22, col);
+                d.draw_rectangle_lines(bx, by + 2, 120, 18, col);
+                d.draw_rectangle(bx + 2, by + 4, (116.0 * player.battery) as i32, 14, col);
+            }
+            if enemy.active && enemy.is_cooling_down() {
+                // Barra "calmándose": se llena hasta que el enemigo vuelve a patrullar
+                let p = enemy.cooldown_progress();
+                d.draw_text("Calming", 10, bottom_y - 46, 16, Color::new(160, 200, 255, 220));
+                d.draw_rectangle_lines(80, bottom_y - 44, 100, 12, Color::new(160, 200, 255, 200));
+                d.draw_rectangle(81, bottom_y - 43, (98.0 * p) as i32, 10, Color::new(160, 200, 255, (200.0 * (1.0 - 0.6 * p)) as u8));
+            }
+            if show_compass && game_state.in_play() {
+                // Extracción: el objetivo es la celda de inicio
+                let goal = (cfg.extraction && game_state == GameState::Escaping).then(|| {
+                    let b = block_size as f32;
+                    ((spawn_cell.1 as f32 + 0.5) * b - player.pos.y).atan2((spawn_cell.0 as f32 + 0.5) * b - player.pos.x)
+                });
+                draw_compass(&mut d, player.a, window_width / 2, 6, goal);
+            }
+            if show_orb_indicators && game_state == GameState::Playing {
+                draw_orb_indicators(&mut d, &player, &orbs, block_size as f32, window_width, window_height);
+            }
+            if maze.iter().flatten().any(|&c| c == BANISH) {
+                let (txt, col) = if banish_recharge > 0.0 { (format!("Banish: {:.0}s", banish_recharge.ceil()), Color::GRAY) } else { ("Banish: ready".to_string(), Color::new(200, 120, 255, 255)) };
+                d.draw_text(&txt, 360, bottom_y, 22, col);
+            }
+
+            if let Some(action) = interact_target {
+                let ready = match action { Interaction::Banish => enemy.active && banish_recharge <= 0.0 };
+                let txt = if ready { format!("Press G to {}", action.label()) } else { format!("Can't {} right now", action.label()) };
+                let tw = d.measure_text(&txt, 20);
+                d.draw_text(&txt, (window_width - tw) / 2, window_height / 2 + 30, 20, if ready { Color::WHITE } else { Color::GRAY });
+            }
 
             // Mensajes de estado
             match game_state {
                 GameState::Escaping => {
-                    let msg = "¡Todos los orbs! Busca la salida blanca (g).";
-                    let tw = d.measure_text(msg, 22);
-                    d.draw_text(msg, (window_width - tw)/2, 12, 22, Color::WHITE);
+                    let head = if remaining > 0 { "¡Salida abierta!" } else { "¡Todos los orbs!" };
+                    let msg = if cfg.extraction { format!("{} Vuelve al punto de inicio.", head) } else { format!("{} Busca la salida blanca (g).", head) };
+                    let tw = d.measure_text(&msg, 22);
+                    let my = if show_compass { 44 } else { 12 }; // debajo de la brújula
+                    d.draw_text(&msg, (window_width - tw)/2, my, 22, Color::WHITE);
+                    // Parpadeo al abrirse la salida con orbs pendientes
+                    if unlock_hint_timer > 0.0 && (unlock_hint_timer * 4.0) as i32 % 2 == 0 {
+                        let hint = "SALIDA ABIERTA";
+                        let hw = d.measure_text(hint, 40);
+                        d.draw_text(hint, (window_width - hw)/2, window_height/2 - 90, 40, Color::new(120, 255, 160, 255));
+                    }
                 }
                 GameState::Won => {
                     // Style like the menu: red gradient + vignette + glowing text
@@ -803,7 +2109,7 @@ fn main() {
                             Color::new(220, 20, 40, alpha),
                         );
                     }
-                    let title = "You Escaped!";
+                    let title = if cfg.arcade { "Time's up!" } else { "You Escaped!" };
                     let ts = 60;
                     let tw = d.measure_text(title, ts);
                     let tx = (window_width - tw)/2;
@@ -812,33 +2118,56 @@ fn main() {
                         d.draw_text(title, tx+ox, ty+oy, ts, col);
                     }
                     d.draw_text(title, tx, ty, ts, Color::new(255, 230, 210, 255));
+                    if cfg.arcade {
+                        let sc = format!("Orbs collected: {}", score);
+                        let sw = d.measure_text(&sc, 32);
+                        d.draw_text(&sc, (window_width - sw)/2, ty + 50, 32, Color::new(255, 220, 60, 255));
+                    }
                     let hint = "ENTER: next level | ESC: exit";
                     let hw = d.measure_text(hint, 28);
                     d.draw_text(hint, (window_width - hw)/2, ty + 90, 28, Color::new(240, 220, 220, 255));
+                    if practice_run {
+                        let note = "PRACTICE run - not counted";
+                        let nw = d.measure_text(note, 24);
+                        d.draw_text(note, (window_width - nw)/2, ty + 130, 24, Color::new(255, 220, 60, 255));
+                    }
+                }
+                GameState::Paused => {
+                    let msg = "PAUSA - vuelve a la ventana para continuar";
+                    let tw = d.measure_text(msg, 32);
+                    d.draw_rectangle(0, 0, window_width, window_height, Color::new(0, 0, 0, 160));
+                    d.draw_text(msg, (window_width - tw)/2, window_height/2 - 16, 32, Color::WHITE);
                 }
                 GameState::Caught => {
                     let msg = "GAME OVER - Te atrapó (ENTER: menú, ESC: salir)";
                     let tw = d.measure_text(msg, 36);
                     d.draw_rectangle(0, 0, window_width, window_height, Color::new(0,0,0,200));
                     d.draw_text(msg, (window_width - tw)/2, window_height/2 - 18, 36, Color::RED);
+                    let deaths = format!("Deaths: {}", stats.deaths(cfg.file));
+                    let dw = d.measure_text(&deaths, 26);
+                    d.draw_text(&deaths, (window_width - dw)/2, window_height/2 + 30, 26, Color::new(255, 180, 180, 255));
                 }
                 _ => {}
             }
 
             // Minimap (arriba derecha) según nivel — dibujado después de la linterna para que permanezca visible
             if cfg.show_minimap {
-                draw_minimap(&mut d, &maze, &player, &orbs, &enemy, window_width);
+                draw_minimap(&mut d, &maze, &player, &orbs, &enemy, block_size as f32, window_width, show_enemy_tuning);
+            }
+
+            if show_enemy_tuning {
+                draw_enemy_tuning(&mut d, &enemy, enemy_tune_sel, 10, 80);
+            }
+            if show_asset_report {
+                draw_asset_report(&mut d, &asset_report, 330, 80);
             }
 
             // (overlay de Caught ya manejado en el match anterior)
+            // Fundido de transición por encima de todo
+            if fade_alpha > 0 { d.draw_rectangle(0, 0, window_width, window_height, Color::new(0, 0, 0, fade_alpha)); }
         }
 
-    // Salir/avanzar en pantallas finales
-        if game_state == GameState::Won && (window.is_key_pressed(KeyboardKey::KEY_ENTER) || window.is_key_pressed(KeyboardKey::KEY_KP_ENTER)) {
-            // avanzar nivel y volver a menú
-            selected_level = (selected_level + 1) % 3;
-            game_state = GameState::Menu;
-        }
+    // Salir en pantallas finales (ENTER vuelve al menú con fundido, ver arriba; el nivel ya avanzó al empezar)
         if game_state == GameState::Won && window.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
             break;
         }
@@ -849,3 +2178,717 @@ fn main() {
         // pacing por set_target_fps
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::events::RecordingSink;
+
+    // Sala abierta rodeada de muros
+    fn room(w: usize, h: usize) -> Maze {
+        (0..h).map(|j| (0..w).map(|i| if i == 0 || j == 0 || i + 1 == w || j + 1 == h { WALL } else { FLOOR }).collect()).collect()
+    }
+
+    // Orbs de reset_game y celda de aparición del enemigo (con empates) para una semilla
+    fn run(maze: &Maze, seed: u64) -> (Vec<(f32, f32)>, (usize, usize)) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let (orbs, _, _, _) = reset_game(maze, BLOCK as usize, PLAYER_START_CELL, &mut rng, true);
+        let cells: Vec<(usize, usize)> = maze.iter().enumerate()
+            .flat_map(|(j, row)| row.iter().enumerate().filter(|(_, c)| **c == FLOOR).map(move |(i, _)| (i, j)))
+            .collect();
+        // Jugador en el centro: las cuatro esquinas empatan y decide el rng
+        let (px, py) = (maze[0].len() as f32 * 0.5 * BLOCK, maze.len() as f32 * 0.5 * BLOCK);
+        let (spawn, _) = pick_spawn_cell(&cells, BLOCK, px, py, 3.0, &mut rng).unwrap();
+        (orbs.iter().map(|o| (o.x, o.y)).collect(), spawn)
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_orbs_and_enemy_spawn() {
+        let maze = room(12, 10);
+        let a = run(&maze, 42);
+        assert!(!a.0.is_empty());
+        for _ in 0..3 { assert_eq!(run(&maze, 42), a); }
+    }
+
+    // Enemigo activo y patrullando en el centro de la celda (i, j)
+    fn enemy_at(i: f32, j: f32) -> Enemy {
+        let mut e = Enemy::new((i + 0.5) * BLOCK, (j + 0.5) * BLOCK, 0.0);
+        e.active = true;
+        e
+    }
+
+    #[test]
+    fn broadcast_alerts_only_other_enemies_within_the_radius() {
+        let mut pack = vec![enemy_at(1.0, 1.0), enemy_at(4.0, 1.0), enemy_at(12.0, 1.0)];
+        let (x, y) = (2.5 * BLOCK, 5.5 * BLOCK);
+        assert_eq!(broadcast_alert(&mut pack, 0, x, y, 6.0 * BLOCK), 1);
+        assert!(!pack[0].is_chasing());
+        assert!(pack[1].is_chasing());
+        assert_eq!(pack[1].last_seen(), Some((x, y)));
+        assert!(!pack[2].is_chasing());
+        // Un segundo aviso no cuenta a quien ya busca
+        assert_eq!(broadcast_alert(&mut pack, 0, x, y, 6.0 * BLOCK), 0);
+    }
+
+    #[test]
+    fn reset_places_the_player_at_the_spawn_cell() {
+        let maze = room(12, 10);
+        let mut rng = StdRng::seed_from_u64(7);
+        let (_, _, player, _) = reset_game(&maze, 80, (4, 3), &mut rng, false);
+        assert_eq!((player.pos.x, player.pos.y), (4.5 * 80.0, 3.5 * 80.0));
+        assert!(at_spawn_cell(80.0, player.pos.x, player.pos.y, (4, 3)));
+    }
+
+    // Partida mínima con el orden del bucle principal: enemigo (¿te ve?), orbs, victoria, captura.
+    // Aquí se gana al recoger todos los orbs (sin el tramo de huida hasta la salida).
+    struct Script { maze: Maze, enemy: Enemy, orbs: Vec<Orb>, score: usize, sink: RecordingSink }
+
+    impl Script {
+        fn new(orb_cells: &[usize], enemy_cell: f32) -> Self {
+            let mut enemy = enemy_at(enemy_cell, 1.0);
+            enemy.set_fov(std::f32::consts::TAU);
+            enemy.set_range(4.0 * BLOCK);
+            enemy.set_speed_patrol(0.0);
+            let orbs = orb_cells.iter().map(|&i| Orb { x: (i as f32 + 0.5) * BLOCK, y: 1.5 * BLOCK, active: true, respawn: 0.0 }).collect();
+            Self { maze: room(16, 3), enemy, orbs, score: 0, sink: RecordingSink::default() }
+        }
+
+        // Avanza un fotograma con el jugador en (px, py); true al terminar la partida
+        fn frame(&mut self, px: f32, py: f32) -> bool {
+            self.enemy.update(&self.maze, px, py, BLOCK as usize, 1.0 / 30.0, None);
+            if self.enemy.just_spotted() { self.sink.emit(GameEvent::PlayerSeen); }
+            collect_orbs(&mut self.orbs, px, py, 0.0, &mut self.score, &mut self.sink);
+            if !self.orbs.iter().any(|o| o.active) {
+                self.sink.emit(GameEvent::LevelWon { score: self.score, secs: 0.0 });
+                return true;
+            }
+            let (dx, dy) = (self.enemy.x - px, self.enemy.y - py);
+            if (dx*dx + dy*dy).sqrt() < Difficulty::Normal.cfg().catch_dist {
+                self.sink.emit(GameEvent::PlayerCaught);
+                return true;
+            }
+            false
+        }
+
+        // El jugador camina hacia el este por el pasillo desde la celda 1
+        fn walk_east(&mut self) {
+            let mut px = 1.5 * BLOCK;
+            for _ in 0..600 {
+                if self.frame(px, 1.5 * BLOCK) { return; }
+                px += 100.0 / 30.0;
+            }
+            panic!("the script never ended");
+        }
+    }
+
+    #[test]
+    fn events_follow_a_caught_run_in_order() {
+        let mut run = Script::new(&[3, 14], 13.0);
+        run.walk_east();
+        assert_eq!(run.sink.events, vec![GameEvent::OrbCollected { score: 1 }, GameEvent::PlayerSeen, GameEvent::PlayerCaught]);
+    }
+
+    #[test]
+    fn events_follow_a_won_run_in_order() {
+        // Enemigo detrás de los orbs: te ve, pero recoges el último antes de que llegue
+        let mut run = Script::new(&[2, 6], 9.0);
+        run.walk_east();
+        assert_eq!(run.sink.events, vec![
+            GameEvent::OrbCollected { score: 1 },
+            GameEvent::PlayerSeen,
+            GameEvent::OrbCollected { score: 2 },
+            GameEvent::LevelWon { score: 2, secs: 0.0 },
+        ]);
+    }
+
+    #[test]
+    fn different_seeds_give_different_orb_layouts() {
+        let maze = room(12, 10);
+        assert_ne!(run(&maze, 1).0, run(&maze, 2).0);
+    }
+
+    #[test]
+    fn radar_pings_speed_up_as_the_enemy_closes_in() {
+        let intervals: Vec<f32> = [0.0, 100.0, 400.0, 800.0, 1200.0, 3000.0].iter().map(|&d| radar_ping_interval(d)).collect();
+        assert!(intervals.windows(2).all(|w| w[0] <= w[1]), "{:?}", intervals);
+        assert!(intervals[..5].windows(2).all(|w| w[0] < w[1]));
+        assert_eq!((intervals[0], intervals[5]), (0.3, 2.0));
+    }
+
+    #[test]
+    fn pickup_widens_detection_until_the_reveal_runs_out() {
+        let mut e = enemy_at(1.0, 1.0);
+        let base = e.detection_range();
+        let (dt, mut left) = (0.1, GRAB_REVEAL_SECS);
+        let mut boosted_frames = 0;
+        for _ in 0..20 {
+            left = (left - dt).max(0.0);
+            e.set_detect_boost(grab_reveal_boost(left));
+            if left > 0.0 {
+                assert!(e.detection_range() > base);
+                assert!(e.hears_player(e.x + 2.0 * BLOCK, e.y, BLOCK as usize));
+                boosted_frames += 1;
+            } else {
+                assert_eq!(e.detection_range(), base);
+                assert!(!e.hears_player(e.x + 2.0 * BLOCK, e.y, BLOCK as usize));
+            }
+        }
+        assert!((9..=10).contains(&boosted_frames));
+    }
+
+    #[test]
+    fn framebuffer_size_follows_the_window_at_the_render_scale() {
+        assert_eq!(fb_size_for(1300, 900, 0.66), (858, 594));
+        assert_eq!(fb_size_for(1920, 1080, 0.5), (960, 540));
+        assert_eq!(fb_size_for(640, 480, 1.0), (640, 480));
+        // A minimized or tiny window never yields an empty framebuffer
+        assert_eq!(fb_size_for(1, 1, 0.5), (1, 1));
+        assert_eq!(fb_size_for(0, 0, 0.66), (1, 1));
+    }
+
+    #[test]
+    fn sanity_effects_kick_in_below_half_and_peak_at_zero() {
+        for s in [1.0, 0.75, 0.5] { assert_eq!(sanity_effects(s), (0.0, 0.0, 1.0)); }
+        let (desat, beat, speed) = sanity_effects(0.25);
+        assert!((desat - 0.35).abs() < 1e-6 && (beat - 0.5).abs() < 1e-6 && (speed - 0.925).abs() < 1e-6);
+        assert_eq!(sanity_effects(0.0), (0.7, 1.0, 0.85));
+        // Out-of-range input is clamped
+        assert_eq!(sanity_effects(-3.0), sanity_effects(0.0));
+        assert_eq!(sanity_effects(2.0), sanity_effects(1.0));
+    }
+
+    #[test]
+    fn spawn_picks_the_farthest_cell_and_reports_the_minimum() {
+        let block = BLOCK;
+        let (px, py) = (0.5 * block, 0.5 * block);
+        let cells = [(1, 0), (3, 0), (9, 0), (2, 2)];
+        let mut rng = StdRng::seed_from_u64(1);
+        // Farthest cell is 9 cells away: clears a minimum of 8
+        assert_eq!(pick_spawn_cell(&cells, block, px, py, 8.0, &mut rng), Some(((9, 0), true)));
+        // Nothing is 12 cells away: still the farthest, flagged as too close
+        assert_eq!(pick_spawn_cell(&cells, block, px, py, 12.0, &mut rng), Some(((9, 0), false)));
+        assert_eq!(pick_spawn_cell(&[], block, px, py, 8.0, &mut rng), None);
+        // Equally far cells are both possible picks; nearer ones never are
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let ring = [(8, 0), (0, 8), (5, 1)];
+            let (c, ok) = pick_spawn_cell(&ring, block, px, py, 8.0, &mut rng).unwrap();
+            assert!(ok && (c == (8, 0) || c == (0, 8)));
+        }
+    }
+
+    #[test]
+    fn letterbox_bars_go_on_the_long_side() {
+        // Wide window, 4:3 frame: bars left and right
+        let wide = letterbox_rect(1600.0, 600.0, 800.0, 600.0);
+        assert_eq!((wide.x, wide.y, wide.width, wide.height), (400.0, 0.0, 800.0, 600.0));
+        // Tall window: bars top and bottom
+        let tall = letterbox_rect(800.0, 1200.0, 800.0, 600.0);
+        assert_eq!((tall.x, tall.y, tall.width, tall.height), (0.0, 300.0, 800.0, 600.0));
+        // Matching aspect fills the window
+        let full = letterbox_rect(1600.0, 1200.0, 800.0, 600.0);
+        assert_eq!((full.x, full.y, full.width, full.height), (0.0, 0.0, 1600.0, 1200.0));
+    }
+
+    #[test]
+    fn banish_sends_the_enemy_away_and_waits_for_the_recharge() {
+        let mut e = enemy_at(3.0, 3.0);
+        let (mut recharge, mut away) = (0.0, 0.0);
+        assert!(try_banish(&mut e, &mut recharge, &mut away));
+        assert!(!e.active);
+        assert_eq!((recharge, away), (BANISH_RECHARGE_SECS, BANISH_AWAY_SECS));
+        // Back before the switch recharged: can't be used again
+        e.active = true;
+        recharge -= BANISH_RECHARGE_SECS - 1.0;
+        assert!(!try_banish(&mut e, &mut recharge, &mut away));
+        assert!(e.active);
+        recharge = 0.0;
+        assert!(try_banish(&mut e, &mut recharge, &mut away));
+        // Nothing to banish while the enemy is already away
+        recharge = 0.0;
+        assert!(!try_banish(&mut e, &mut recharge, &mut away));
+        assert_eq!(recharge, 0.0);
+    }
+
+    #[test]
+    fn unseen_exit_does_not_count_under_the_seen_rule() {
+        use crate::core::process_events::move_player;
+        let mut maze = room(7, 3);
+        maze[1][5] = 'g';
+        let block = BLOCK as usize;
+        let look = |p: &Player| exit_in_view(&maze, BLOCK, p.pos.x, p.pos.y, p.a, p.fov, EXIT_SIGHT_CELLS * BLOCK);
+        // De espaldas a la salida: se toca sin haberla visto
+        let mut p = Player::new(4.5 * BLOCK, 1.5 * BLOCK, std::f32::consts::PI);
+        let timer = tick_exit_seen(0.0, look(&p), 0.016);
+        let touched = move_player(&mut p, &maze, block, 40.0, 0.0, false);
+        assert!(touched);
+        assert!(!exit_counts(touched, true, timer));
+        assert!(exit_counts(touched, false, timer));
+        // Mirándola: cuenta
+        let mut p = Player::new(4.5 * BLOCK, 1.5 * BLOCK, 0.0);
+        let timer = tick_exit_seen(0.0, look(&p), 0.016);
+        let touched = move_player(&mut p, &maze, block, 40.0, 0.0, false);
+        assert!(exit_counts(touched, true, timer));
+    }
+
+    #[test]
+    fn seen_exit_stays_valid_for_the_grace_period() {
+        let mut t = tick_exit_seen(0.0, true, 0.1);
+        assert_eq!(t, EXIT_SEEN_GRACE);
+        // Apartar la vista un poco menos que el margen
+        for _ in 0..14 { t = tick_exit_seen(t, false, 0.1); }
+        assert!(exit_counts(true, true, t));
+        for _ in 0..2 { t = tick_exit_seen(t, false, 0.1); }
+        assert!(!exit_counts(true, true, t));
+    }
+
+    #[test]
+    fn flashlight_off_makes_the_player_harder_to_spot() {
+        assert!(flashlight_conspicuity(0.0) < flashlight_conspicuity(0.5));
+        assert!(flashlight_conspicuity(0.5) < flashlight_conspicuity(1.0));
+        assert_eq!(flashlight_conspicuity(1.0), 1.0);
+        assert_eq!(flashlight_conspicuity(3.0), 1.0);
+    }
+
+    #[test]
+    fn heatmap_accumulates_time_in_the_players_cell() {
+        let maze = room(5, 4);
+        let mut heat = heatmap_for(&maze);
+        // Dos pasos en (1, 1), uno en (3, 2) y uno fuera del mapa
+        heatmap_add(&mut heat, BLOCK, 1.5 * BLOCK, 1.5 * BLOCK, 0.25);
+        heatmap_add(&mut heat, BLOCK, 1.9 * BLOCK, 1.1 * BLOCK, 0.5);
+        heatmap_add(&mut heat, BLOCK, 3.2 * BLOCK, 2.7 * BLOCK, 1.0);
+        heatmap_add(&mut heat, BLOCK, -5.0, 1.5 * BLOCK, 9.0);
+        heatmap_add(&mut heat, BLOCK, 40.0 * BLOCK, 1.5 * BLOCK, 9.0);
+        assert_eq!(heat[1][1], 0.75);
+        assert_eq!(heat[2][3], 1.0);
+        let total: f32 = heat.iter().flatten().sum();
+        assert_eq!(total, 1.75);
+        assert_eq!((heat.len(), heat[0].len()), (4, 5));
+    }
+
+    #[test]
+    fn compass_places_headings_relative_to_the_view() {
+        use std::f32::consts::{FRAC_PI_2, FRAC_PI_6, PI, TAU};
+        let near = |a: Option<f32>, b: f32| a.is_some_and(|a| (a - b).abs() < 1e-3);
+        // Mirando al este: E en el centro, 30° a la derecha es un cuarto de la tira
+        assert!(near(compass_offset(0.0, 0.0), 0.0));
+        assert!(near(compass_offset(0.0, FRAC_PI_6), COMPASS_W as f32 / 4.0));
+        assert!(near(compass_offset(0.0, -FRAC_PI_6), -COMPASS_W as f32 / 4.0));
+        // Fuera del arco de 120°
+        assert_eq!(compass_offset(0.0, FRAC_PI_2), None);
+        assert_eq!(compass_offset(0.0, PI), None);
+        // Al girar, la marca se desplaza en sentido contrario y cruza ±π sin saltos
+        assert!(near(compass_offset(0.2, 0.0), -0.2 / COMPASS_SPAN * COMPASS_W as f32));
+        assert!(near(compass_offset(PI - 0.1, -PI + 0.1), 0.2 / COMPASS_SPAN * COMPASS_W as f32));
+        assert!(near(compass_offset(TAU + 0.1, 0.1), 0.0));
+    }
+
+    #[test]
+    fn transition_fades_to_black_and_back() {
+        let mut tr = Transition::default();
+        assert_eq!(tr.alpha(0.1), 0);
+        tr.start(TransitionKind::StartLevel);
+        let steps = 60;
+        let dt = TRANSITION_SECS / steps as f32;
+        let alphas: Vec<u8> = (0..steps).map(|_| tr.alpha(dt)).collect();
+        let peak = steps / 2 - 1;
+        // Sube hasta negro a mitad de camino y vuelve a bajar
+        assert!(alphas[..=peak].windows(2).all(|w| w[0] < w[1]));
+        assert!(alphas[peak..].windows(2).all(|w| w[0] >= w[1]));
+        assert!(alphas[peak] >= 250);
+        assert!(alphas[0] < 20);
+        assert!(*alphas.last().unwrap() < 10);
+        // Un frame más y se acabó
+        assert_eq!(tr.alpha(dt), 0);
+        assert!(!tr.active());
+    }
+
+    #[test]
+    fn transition_switches_state_once_at_the_midpoint() {
+        let mut tr = Transition::default();
+        tr.start(TransitionKind::ToMenu);
+        tr.alpha(TRANSITION_SECS * 0.4);
+        assert_eq!(tr.take_midpoint(), None);
+        // Otra transición pedida a mitad se ignora
+        tr.start(TransitionKind::StartLevel);
+        tr.alpha(TRANSITION_SECS * 0.2);
+        assert_eq!(tr.take_midpoint(), Some(TransitionKind::ToMenu));
+        assert_eq!(tr.take_midpoint(), None);
+    }
+
+    #[test]
+    fn escaping_shortens_the_enemy_sight() {
+        let cfg = level_cfg(1);
+        let mut e = enemy_at(1.0, 1.0);
+        let base = e.detection_range();
+        e.set_sight_scale(cfg.sight_scale(GameState::Playing));
+        assert_eq!(e.detection_range(), base);
+        e.set_sight_scale(cfg.sight_scale(GameState::Escaping));
+        assert!((e.detection_range() - base * cfg.escape_sight).abs() < 1e-3);
+        // Pasillo recto: a media distancia se le ve jugando, pero no huyendo
+        let maze = room(30, 3);
+        let px = 1.5 * BLOCK + base * 0.8;
+        e.set_sight_scale(1.0);
+        assert!(e.sees_player(&maze, px, 1.5 * BLOCK, BLOCK as usize));
+        e.set_sight_scale(cfg.sight_scale(GameState::Escaping));
+        assert!(!e.sees_player(&maze, px, 1.5 * BLOCK, BLOCK as usize));
+    }
+
+    #[test]
+    fn offscreen_orbs_point_from_the_matching_edge() {
+        use std::f32::consts::{FRAC_PI_2, FRAC_PI_3, PI};
+        let at = |bearing: f32| orb_edge_position(0.0, FRAC_PI_3, bearing, 800.0, 600.0);
+        let near = |p: Option<(f32, f32, f32)>, x: f32, y: f32| p.is_some_and(|(px, py, _)| (px - x).abs() < 1e-2 && (py - y).abs() < 1e-2);
+        let m = ORB_INDICATOR_MARGIN;
+        // Dentro del FOV no hay flecha
+        assert_eq!(at(0.2), None);
+        // Derecha, izquierda y detrás (abajo)
+        assert!(near(at(FRAC_PI_2), 800.0 - m, 300.0));
+        assert!(near(at(-FRAC_PI_2), m, 300.0));
+        assert!(near(at(PI), 400.0, 600.0 - m));
+        // Diagonal atrás-derecha: corta antes el borde inferior
+        assert!(near(at(3.0 * PI / 4.0), 400.0 + (300.0 - m), 600.0 - m));
+        // El ángulo relativo se devuelve normalizado
+        assert!(at(-PI + 0.1).is_some_and(|(_, _, rel)| (rel - (-PI + 0.1)).abs() < 1e-5));
+    }
+
+    #[test]
+    fn practice_runs_show_everything_and_do_not_score() {
+        let cfg = practice_cfg(level_cfg(2));
+        assert!(!cfg.enemy_enabled);
+        assert!(cfg.show_minimap);
+        // El minimapa no tiene niebla: la salida se marca desde el inicio
+        let maze = load_maze_with(cfg.file, true);
+        let exits: Vec<char> = maze.iter().flatten().copied().filter(|&c| tiles().is_exit(c)).collect();
+        assert!(!exits.is_empty());
+        assert!(exits.iter().all(|&c| map_cell_color(c).is_some()));
+        let mut meta = Meta { enabled: true, total_orbs: 10 };
+        let mut aggression = Aggression { enabled: true, level: 0.0 };
+        assert_eq!(record_won_run(&mut meta, &mut aggression, 7, 3, true), (false, false));
+        assert_eq!((meta.total_orbs, aggression.level), (10, 0.0));
+        // La misma partida sin práctica sí cuenta
+        assert_eq!(record_won_run(&mut meta, &mut aggression, 7, 3, false), (true, true));
+        assert_eq!(meta.total_orbs, 17);
+        assert!(aggression.level > 0.0);
+    }
+
+    #[test]
+    fn panic_vignette_tightens_with_proximity_and_sight() {
+        // Lejos y sin ser visto: abierta
+        assert_eq!(panic_vignette_target(PANIC_VIGNETTE_RANGE * 2.0, false, false), 0.0);
+        // Más cerca = más cerrada
+        let far = panic_vignette_target(PANIC_VIGNETTE_RANGE * 0.8, false, false);
+        let close = panic_vignette_target(PANIC_VIGNETTE_RANGE * 0.2, false, false);
+        assert!(0.0 < far && far < close);
+        // Persecución y, más aún, ser visto suben la base
+        let d = PANIC_VIGNETTE_RANGE * 0.5;
+        assert!(panic_vignette_target(d, false, true) > panic_vignette_target(d, false, false));
+        assert!(panic_vignette_target(d, true, true) > panic_vignette_target(d, false, true));
+        assert_eq!(panic_vignette_target(0.0, true, true), PANIC_VIGNETTE_MAX);
+    }
+
+    #[test]
+    fn vignette_smoothing_approaches_without_overshoot() {
+        let mut v = 0.0;
+        let mut prev = v;
+        for _ in 0..120 {
+            v = smooth_toward(v, 0.8, PANIC_VIGNETTE_RATE, 1.0 / 60.0);
+            assert!(v > prev && v < 0.8);
+            prev = v;
+        }
+        // Tras 2 s a ritmo 3/s queda a menos de un 1% del objetivo
+        assert!(0.8 - v < 0.008);
+        // Independiente del frame rate: un paso de 1 s = 60 de 1/60 s
+        let mut fine = 0.0;
+        for _ in 0..60 { fine = smooth_toward(fine, 1.0, 3.0, 1.0 / 60.0); }
+        assert!((smooth_toward(0.0, 1.0, 3.0, 1.0) - fine).abs() < 1e-4);
+    }
+
+    #[test]
+    fn orb_markers_place_orbs_at_exactly_those_cells() {
+        let mut maze = room(12, 8);
+        let marks = [(9usize, 1usize), (3, 2), (7, 5)];
+        for &(i, j) in &marks { maze[j][i] = ORB_SPAWN; }
+        let cell = |o: &Orb| ((o.x / BLOCK) as usize, (o.y / BLOCK) as usize);
+        let mut rng = StdRng::seed_from_u64(1);
+        let (orbs, _, _, _) = reset_game(&maze, BLOCK as usize, PLAYER_START_CELL, &mut rng, false);
+        let cells: Vec<(usize, usize)> = orbs.iter().map(cell).collect();
+        assert_eq!(cells, marks);
+        assert!(orbs.iter().all(|o| o.active && (o.x / BLOCK).fract() == 0.5));
+        // Mezclado: las marcas primero y el resto al azar, sin repetir celdas
+        let (orbs, _, _, _) = reset_game(&maze, BLOCK as usize, PLAYER_START_CELL, &mut rng, true);
+        let cells: Vec<(usize, usize)> = orbs.iter().map(cell).collect();
+        assert_eq!(&cells[..3], &marks);
+        assert!(cells.len() > 3);
+        let mut unique = cells.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), cells.len());
+    }
+
+    #[test]
+    fn thumbnails_fit_the_panel_with_square_cells() {
+        for (cols, rows) in [(21, 11), (40, 12), (10, 30), (84, 40), (7, 7), (200, 5)] {
+            let (w, h) = thumb_fit(cols, rows, THUMB_W, THUMB_H);
+            assert!(w <= THUMB_W && h <= THUMB_H, "{}x{} -> {}x{}", cols, rows, w, h);
+            // Llena el hueco en al menos un eje
+            assert!(w >= THUMB_W - 1 || h >= THUMB_H - 1, "{}x{} -> {}x{}", cols, rows, w, h);
+            // Celdas cuadradas: misma escala en x y en y (salvo redondeo)
+            let (sx, sy) = (w as f32 / cols as f32, h as f32 / rows as f32);
+            assert!((sx - sy).abs() <= 1.0 / rows.min(cols) as f32 + 1e-3, "{}x{}", cols, rows);
+        }
+        assert_eq!(thumb_fit(0, 5, THUMB_W, THUMB_H), (0, 0));
+    }
+
+    #[test]
+    fn extraction_wins_back_at_the_spawn_cell() {
+        let maze = room(8, 6);
+        let mut rng = StdRng::seed_from_u64(3);
+        let spawn = (2, 3);
+        let (_, _, mut player, _) = reset_game(&maze, BLOCK as usize, spawn, &mut rng, true);
+        let mut cfg = level_cfg(0);
+        cfg.extraction = true;
+        // Recién aparecido ya está en la celda, pero lejos de ella no escapa ni tocando una salida
+        assert!(cfg.escaped(BLOCK, player.pos.x, player.pos.y, spawn, false));
+        player.teleport(6.5 * BLOCK, 1.5 * BLOCK);
+        assert!(!cfg.escaped(BLOCK, player.pos.x, player.pos.y, spawn, true));
+        // De vuelta en cualquier punto de la celda de inicio
+        player.teleport(2.1 * BLOCK, 3.9 * BLOCK);
+        assert!(cfg.escaped(BLOCK, player.pos.x, player.pos.y, spawn, false));
+        // Sin extracción, la celda de inicio no cuenta y la salida sí
+        cfg.extraction = false;
+        assert!(!cfg.escaped(BLOCK, player.pos.x, player.pos.y, spawn, false));
+        assert!(cfg.escaped(BLOCK, 6.5 * BLOCK, 1.5 * BLOCK, spawn, true));
+    }
+
+    #[test]
+    fn a_catch_counts_one_death_not_one_per_frame() {
+        let mut stats = Stats::default();
+        let mut state = GameState::Playing;
+        // El enemigo sigue encima del jugador durante varios frames
+        let edges = (0..10).filter(|_| catch_player(&mut state, &mut stats, "maze1.txt")).count();
+        assert_eq!((edges, state), (1, GameState::Caught));
+        assert_eq!(stats.deaths("maze1.txt"), 1);
+        // Reintento y captura durante la huida: otra muerte
+        state = GameState::Escaping;
+        assert!(catch_player(&mut state, &mut stats, "maze1.txt"));
+        assert!(!catch_player(&mut state, &mut stats, "maze1.txt"));
+        assert_eq!((stats.deaths("maze1.txt"), stats.deaths("maze2.txt")), (2, 0));
+    }
+
+    #[test]
+    fn spawn_prefers_a_cell_the_player_cannot_see() {
+        let maze = room(14, 6);
+        let cells = vec![(12, 2), (1, 4), (6, 2)];
+        // Jugador en (3, 2) mirando al este: (12, 2) es la más lejana pero está a la vista
+        let mut player = Player::new(3.5 * BLOCK, 2.5 * BLOCK, 0.0);
+        let mut rng = StdRng::seed_from_u64(1);
+        let unseen = unseen_cells(&cells, &maze, BLOCK, &player);
+        assert_eq!(unseen, vec![(1, 4)]);
+        let (c, _) = pick_spawn_cell(&unseen, BLOCK, player.pos.x, player.pos.y, 0.0, &mut rng).unwrap();
+        assert_eq!(c, (1, 4));
+        assert!(!cell_in_view(&maze, BLOCK, player.pos.x, player.pos.y, player.a, player.fov, c));
+        // Si todas están a la vista no se descarta ninguna
+        player.a = std::f32::consts::PI;
+        assert_eq!(unseen_cells(&[(1, 2)], &maze, BLOCK, &player), vec![(1, 2)]);
+    }
+
+    #[test]
+    fn enrage_speeds_up_the_chase_only_while_escaping() {
+        let mut enemy = Enemy::new(0.0, 0.0, 0.0);
+        let base = enemy.effective_chase_speed();
+        let mode = EscapeMode::Enrage(1.2);
+        enemy.set_chase_scale(mode.chase_scale(GameState::Escaping));
+        assert!((enemy.effective_chase_speed() - base * 1.2).abs() < 1e-3);
+        // Fuera de la huida vuelve a la velocidad normal
+        enemy.set_chase_scale(mode.chase_scale(GameState::Playing));
+        assert_eq!(enemy.effective_chase_speed(), base);
+        for m in [EscapeMode::Unchanged, EscapeMode::Despawn] {
+            assert_eq!(m.chase_scale(GameState::Escaping), 1.0);
+        }
+    }
+
+    #[test]
+    fn spawn_announcement_fires_once_per_level() {
+        let mut announced = false;
+        // Inactivo, aparece y sigue activo varios frames, se destierra y vuelve
+        let frames = [false, false, true, true, true, false, true];
+        let fired: Vec<bool> = frames.iter().map(|&active| spawn_announce_edge(active, &mut announced)).collect();
+        assert_eq!(fired, [false, false, true, false, false, false, false]);
+        // Un nivel nuevo vuelve a avisar
+        announced = false;
+        assert!(spawn_announce_edge(true, &mut announced));
+    }
+
+    #[test]
+    fn losing_focus_pauses_and_refocus_resumes() {
+        let (mut state, mut resume) = (GameState::Escaping, GameState::Playing);
+        assert_eq!(focus_pause(&mut state, &mut resume, true, true), None);
+        assert_eq!(focus_pause(&mut state, &mut resume, true, false), Some(true));
+        assert_eq!(state, GameState::Paused);
+        // Mientras dure la pausa la partida no corre
+        assert!(!state.in_play());
+        assert_eq!(focus_pause(&mut state, &mut resume, true, false), None);
+        assert_eq!(focus_pause(&mut state, &mut resume, true, true), Some(false));
+        assert_eq!(state, GameState::Escaping);
+        // Desactivada, o fuera de partida, perder el foco no pausa
+        assert_eq!(focus_pause(&mut state, &mut resume, false, false), None);
+        let mut menu = GameState::Menu;
+        assert_eq!(focus_pause(&mut menu, &mut resume, true, false), None);
+        assert_eq!(menu, GameState::Menu);
+    }
+
+    #[test]
+    fn patrol_breathing_fades_with_distance_and_mutes_on_chase() {
+        assert_eq!(patrol_ambient_volume(0.0, false), PATROL_AMBIENT_MAX);
+        let (near, mid) = (patrol_ambient_volume(200.0, false), patrol_ambient_volume(600.0, false));
+        assert!(PATROL_AMBIENT_MAX > near && near > mid && mid > 0.0);
+        assert_eq!(patrol_ambient_volume(PATROL_AMBIENT_RANGE, false), 0.0);
+        assert_eq!(patrol_ambient_volume(5000.0, false), 0.0);
+        // Persiguiendo, los pasos sustituyen a la respiración aunque esté encima
+        assert_eq!(patrol_ambient_volume(0.0, true), 0.0);
+        assert_eq!(patrol_ambient_volume(200.0, true), 0.0);
+    }
+
+    #[test]
+    fn arcade_orbs_come_back_on_a_safe_cell_after_the_delay() {
+        let maze = room(12, 8);
+        let mut rng = StdRng::seed_from_u64(5);
+        let (px, py) = (3.5 * BLOCK, 3.5 * BLOCK);
+        let mut orbs = vec![
+            Orb { x: px, y: py, active: true, respawn: 0.0 },
+            Orb { x: 8.5 * BLOCK, y: 4.5 * BLOCK, active: true, respawn: 0.0 },
+        ];
+        let (mut score, mut sink) = (0, RecordingSink::default());
+        assert_eq!(collect_orbs(&mut orbs, px, py, ORB_RESPAWN_SECS, &mut score, &mut sink), 1);
+        // Medio segundo antes del plazo sigue recogido
+        for _ in 0..(ORB_RESPAWN_SECS * 2.0) as usize - 1 { tick_orb_respawns(&mut orbs, &maze, BLOCK, px, py, 0.5, &mut rng); }
+        assert!(!orbs[0].active);
+        tick_orb_respawns(&mut orbs, &maze, BLOCK, px, py, 0.5, &mut rng);
+        let o = &orbs[0];
+        assert!(o.active);
+        let (i, j) = ((o.x / BLOCK) as usize, (o.y / BLOCK) as usize);
+        assert!(is_safe_cell(&maze, i, j));
+        assert!(((o.x - px).powi(2) + (o.y - py).powi(2)).sqrt() >= ORB_RESPAWN_MIN_CELLS * BLOCK);
+        assert!((o.x, o.y) != (orbs[1].x, orbs[1].y));
+    }
+
+    #[test]
+    fn standing_still_too_long_sends_the_enemy() {
+        let mut idle = 0.0;
+        // 2.5 s quieto, un paso, y otra vez quieto: el paso reinicia la cuenta
+        assert!(!(0..5).any(|_| anti_camp_due(&mut idle, false, 0.5, true, 3.0)));
+        assert!(!anti_camp_due(&mut idle, true, 0.5, true, 3.0));
+        assert_eq!(idle, 0.0);
+        let fired: Vec<bool> = (0..6).map(|_| anti_camp_due(&mut idle, false, 0.5, true, 3.0)).collect();
+        assert_eq!(fired, [false, false, false, false, false, true]);
+        assert_eq!(idle, 0.0);
+        // El aviso manda al enemigo a buscar donde está el jugador
+        let mut enemy = enemy_at(10.0, 1.0);
+        enemy.nudge_to(2.5 * BLOCK, 1.5 * BLOCK);
+        assert!(enemy.is_chasing());
+        assert_eq!(enemy.last_seen(), Some((2.5 * BLOCK, 1.5 * BLOCK)));
+        // Con la regla desactivada nunca avisa
+        assert!(!(0..100).any(|_| anti_camp_due(&mut idle, false, 0.5, false, 3.0)));
+    }
+
+    #[test]
+    fn integer_scale_uses_the_largest_whole_multiple_centred() {
+        let r = |ww: f32, wh: f32, w: f32, h: f32| { let r = integer_scale_rect(ww, wh, w, h); (r.x, r.y, r.width, r.height) };
+        // 2.5x cabría; se queda en 2x con barras por los cuatro lados
+        assert_eq!(r(1000.0, 600.0, 400.0, 240.0), (100.0, 60.0, 800.0, 480.0));
+        // Manda el lado más justo (alto: 3x), centrado en horizontal
+        assert_eq!(r(1920.0, 1080.0, 320.0, 320.0), (480.0, 60.0, 960.0, 960.0));
+        // Justo a escala 1 llena la ventana
+        assert_eq!(r(640.0, 480.0, 640.0, 480.0), (0.0, 0.0, 640.0, 480.0));
+        // Centro impar: se redondea hacia abajo al píxel
+        assert_eq!(r(641.0, 481.0, 320.0, 240.0), (0.0, 0.0, 640.0, 480.0));
+        // No cabe ni a 1x: escalado libre (letterbox)
+        let small = integer_scale_rect(400.0, 300.0, 800.0, 600.0);
+        let lb = letterbox_rect(400.0, 300.0, 800.0, 600.0);
+        assert_eq!((small.x, small.y, small.width, small.height), (lb.x, lb.y, lb.width, lb.height));
+    }
+
+    #[test]
+    fn the_flashlight_slows_a_light_shy_enemy() {
+        let maze = room(12, 5);
+        let cfg = level_cfg(1);
+        let mut player = Player::new(1.5 * BLOCK, 2.5 * BLOCK, 0.0);
+        player.flashlight_on = true;
+        let mut enemy = enemy_at(5.0, 2.0);
+        let base = enemy.effective_chase_speed();
+        let lit = enemy_in_flashlight(&maze, &player, &enemy, BLOCK as usize);
+        assert!(lit);
+        enemy.set_light_scale(cfg.light_scale(lit));
+        let lit_speed = enemy.effective_chase_speed();
+        // De espaldas a él: a oscuras corre más
+        player.a = std::f32::consts::PI;
+        let lit = enemy_in_flashlight(&maze, &player, &enemy, BLOCK as usize);
+        assert!(!lit);
+        enemy.set_light_scale(cfg.light_scale(lit));
+        assert!(lit_speed < base && base < enemy.effective_chase_speed());
+        // Niveles sin la regla: igual con o sin luz
+        assert_eq!((level_cfg(0).light_scale(true), level_cfg(0).light_scale(false)), (1.0, 1.0));
+    }
+
+    #[test]
+    fn examine_reports_the_wall_cell_the_ray_hits() {
+        let mut maze = room(8, 5);
+        maze[2][7] = 'g';
+        let look = |a: f32| {
+            let p = Player::new(3.5 * BLOCK, 2.5 * BLOCK, a);
+            let dist = cast_ray(&mut Framebuffer::new(1, 1), &maze, &p, a, BLOCK as usize, false, false);
+            examine_cell(&maze, BLOCK, p.pos.x + a.cos() * dist, p.pos.y + a.sin() * dist, a, false)
+        };
+        assert_eq!(look(0.0), Some((7, 2, 'g')));
+        // Hacia el oeste y el norte el impacto cae en el borde de la celda libre: cuenta la pared
+        assert_eq!(look(std::f32::consts::PI), Some((0, 2, WALL)));
+        assert_eq!(look(-std::f32::consts::FRAC_PI_2), Some((3, 0, WALL)));
+        assert_eq!(examine_cell(&maze, BLOCK, -10.0, 10.0, 0.0, false), None);
+    }
+
+    #[test]
+    fn holding_breath_quiets_the_player_at_a_sanity_cost() {
+        let mut player = Player::new(0.0, 0.0, 0.0);
+        let (noise, shake) = (player.noise_mul(), player.shake_mul());
+        assert_eq!(breath_drain(&mut player, 1.0), 0.0);
+        player.holding_breath = true;
+        assert!(player.noise_mul() < noise && player.shake_mul() < shake);
+        // Cada segundo contenido cuesta cordura hasta el mínimo, y entonces se suelta sola
+        let mut sanity: f32 = 0.3;
+        for _ in 0..10 {
+            sanity -= breath_drain(&mut player, sanity) * 0.5;
+            if !player.holding_breath { break; }
+        }
+        assert!(sanity <= BREATH_MIN_SANITY + 1e-6 && sanity < 0.3);
+        assert!(!player.holding_breath);
+        assert_eq!((player.noise_mul(), player.shake_mul()), (noise, shake));
+    }
+
+    #[test]
+    fn exit_beam_only_shows_while_escaping() {
+        let mut maze = room(8, 5);
+        maze[2][7] = 'g';
+        // Fuera de la huida no hay haz
+        for state in [GameState::Menu, GameState::Playing, GameState::Paused, GameState::Won, GameState::Caught] {
+            assert!(exit_beacons(&maze, BLOCK, state, None).is_empty(), "{:?}", state);
+        }
+        assert_eq!(exit_beacons(&maze, BLOCK, GameState::Escaping, None), vec![(7.5 * BLOCK, 2.5 * BLOCK)]);
+        // En extracción marca la celda de inicio, no la salida
+        assert_eq!(exit_beacons(&maze, BLOCK, GameState::Escaping, Some((2, 3))), vec![(2.5 * BLOCK, 3.5 * BLOCK)]);
+    }
+
+    #[test]
+    fn render_scale_steps_stay_within_the_table() {
+        let top = RENDER_SCALES.len() - 1;
+        assert_eq!(step_render_scale(DEFAULT_RENDER_SCALE_IDX, false, false), DEFAULT_RENDER_SCALE_IDX);
+        assert_eq!(step_render_scale(DEFAULT_RENDER_SCALE_IDX, true, false), DEFAULT_RENDER_SCALE_IDX + 1);
+        assert_eq!(step_render_scale(DEFAULT_RENDER_SCALE_IDX, false, true), DEFAULT_RENDER_SCALE_IDX - 1);
+        // En los extremos se queda donde está
+        assert_eq!(step_render_scale(top, true, false), top);
+        assert_eq!(step_render_scale(0, false, true), 0);
+        assert_eq!(RENDER_SCALES[top], 1.0);
+        assert!(RENDER_SCALES.windows(2).all(|w| w[0] < w[1]));
+    }
+}
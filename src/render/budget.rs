@@ -0,0 +1,109 @@
+//! Frame-time budget that sheds optional effects under load.
+//!
+//! A rolling (exponential) average of the frame time picks how many effects are off.
+//! Effects are dropped in `SHED_ORDER` as the average crosses each threshold and come
+//! back, in reverse order, once it falls a margin below it.
+
+/// Optional per-frame effects, in the order they are given up.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Effect {
+    Anisotropic,
+    ColorGrade,
+    Blur,
+    Supersampling,
+}
+
+pub const SHED_ORDER: [Effect; 4] = [Effect::Anisotropic, Effect::ColorGrade, Effect::Blur, Effect::Supersampling];
+/// Average frame time (s) above which the matching `SHED_ORDER` entry is turned off.
+/// All sit above 1/60 s, and `threshold * RECOVER_MARGIN` does too, so a game capped at 60 FPS
+/// gets every effect back once it keeps up again.
+pub const SHED_THRESHOLDS: [f32; 4] = [1.0 / 50.0, 1.0 / 45.0, 1.0 / 38.0, 1.0 / 30.0];
+/// An effect returns once the average drops below `threshold * RECOVER_MARGIN`.
+pub const RECOVER_MARGIN: f32 = 0.9;
+/// Weight of the newest frame in the rolling average.
+const SMOOTHING: f32 = 0.08;
+
+#[derive(Clone, Debug)]
+pub struct EffectBudget {
+    avg: f32,
+    shed: usize,
+}
+
+impl Default for EffectBudget {
+    fn default() -> Self { Self::new() }
+}
+
+impl EffectBudget {
+    pub fn new() -> Self {
+        Self { avg: 1.0 / 60.0, shed: 0 }
+    }
+
+    /// Feed this frame's `dt`; spikes above 0.25 s (loading, window drag) are ignored.
+    pub fn update(&mut self, dt: f32) {
+        if !(0.0..=0.25).contains(&dt) { return; }
+        self.avg += (dt - self.avg) * SMOOTHING;
+        while self.shed < SHED_ORDER.len() && self.avg > SHED_THRESHOLDS[self.shed] { self.shed += 1; }
+        while self.shed > 0 && self.avg < SHED_THRESHOLDS[self.shed - 1] * RECOVER_MARGIN { self.shed -= 1; }
+    }
+
+    pub fn enabled(&self, effect: Effect) -> bool {
+        SHED_ORDER.iter().position(|&e| e == effect).is_none_or(|k| k >= self.shed)
+    }
+
+    /// Number of effects currently turned off.
+    pub fn shed(&self) -> usize { self.shed }
+
+    pub fn avg_frame_time(&self) -> f32 { self.avg }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settle(b: &mut EffectBudget, dt: f32) {
+        for _ in 0..300 { b.update(dt); }
+    }
+
+    fn enabled(b: &EffectBudget) -> Vec<bool> {
+        SHED_ORDER.iter().map(|&e| b.enabled(e)).collect()
+    }
+
+    #[test]
+    fn slower_frames_shed_effects_in_priority_order() {
+        let mut b = EffectBudget::new();
+        assert_eq!(enabled(&b), vec![true; 4]);
+        let mut seen = vec![enabled(&b)];
+        for fps in [55.0, 48.0, 42.0, 35.0, 20.0] {
+            settle(&mut b, 1.0 / fps);
+            seen.push(enabled(&b));
+        }
+        assert_eq!(seen, vec![
+            vec![true, true, true, true],
+            vec![true, true, true, true],
+            vec![false, true, true, true],
+            vec![false, false, true, true],
+            vec![false, false, false, true],
+            vec![false, false, false, false],
+        ]);
+    }
+
+    #[test]
+    fn effects_come_back_once_frames_recover() {
+        let mut b = EffectBudget::new();
+        settle(&mut b, 1.0 / 20.0);
+        assert_eq!(b.shed(), 4);
+        // 48 FPS is under the colour grade threshold but not yet its recovery margin
+        settle(&mut b, 1.0 / 48.0);
+        assert_eq!(enabled(&b), vec![false, false, true, true]);
+        settle(&mut b, 1.0 / 60.0);
+        assert_eq!(b.shed(), 0);
+    }
+
+    #[test]
+    fn hitches_are_ignored() {
+        let mut b = EffectBudget::new();
+        for _ in 0..50 { b.update(2.0); }
+        assert_eq!(b.shed(), 0);
+        assert_eq!(b.avg_frame_time(), 1.0 / 60.0);
+    }
+}
@@ -1,10 +1,84 @@
 //! Ray casting helper.
 use crate::render::framebuffer::Framebuffer;
-use crate::core::maze::Maze;
+use crate::core::maze::{Maze, cell_at};
+use crate::core::tiles::tiles;
 use crate::core::player::Player;
 use crate::render::line::line;
 use raylib::prelude::*;
 
+/// Rays give up after this many cells without a hit.
+const MAX_RAY_CELLS: f32 = 31.25;
+
+/// Grid line a ray crossed to reach the wall it hit.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HitSide {
+    /// A vertical grid line (constant x): the face points east or west.
+    Vertical,
+    /// A horizontal grid line (constant y): the face points north or south.
+    Horizontal,
+}
+
+/// Where and how a ray met a wall.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RayHit {
+    /// Fish-eye corrected distance (perpendicular to the view direction), at least 1.
+    pub dist: f32,
+    /// Distance along the ray.
+    pub ray_dist: f32,
+    /// World position of the hit (unwrapped on toroidal levels).
+    pub x: f32,
+    pub y: f32,
+    pub side: HitSide,
+    /// Cell hit, folded back into the maze on toroidal levels.
+    pub cell: (isize, isize),
+}
+
+/// Walk the grid cell by cell (DDA) from the player along `angle` until a cell the player
+/// can't walk through. `None` if the ray leaves the map or runs out of reach first. With
+/// `wrap` the ray leaves one edge and keeps going in from the opposite one.
+pub fn cast_ray_hit(fb: &mut Framebuffer, maze: &Maze, player: &Player, angle: f32, block_size: usize, wrap: bool) -> Option<RayHit> {
+    let block = block_size as f32;
+    let (px, py) = (player.pos.x / block, player.pos.y / block);
+    let (dx, dy) = (angle.cos(), angle.sin());
+    let (mut i, mut j) = (px.floor() as isize, py.floor() as isize);
+    // Ray length (in cells) between two crossings of the same grid axis
+    let delta_x = if dx.abs() < 1e-9 { f32::INFINITY } else { (1.0 / dx).abs() };
+    let delta_y = if dy.abs() < 1e-9 { f32::INFINITY } else { (1.0 / dy).abs() };
+    let (step_i, mut next_x) = if dx < 0.0 { (-1, (px - i as f32) * delta_x) } else { (1, (i as f32 + 1.0 - px) * delta_x) };
+    let (step_j, mut next_y) = if dy < 0.0 { (-1, (py - j as f32) * delta_y) } else { (1, (j as f32 + 1.0 - py) * delta_y) };
+    fb.stats.rays += 1;
+    let fold = |i: isize, j: isize| {
+        let (w, h) = (maze.first().map_or(1, |r| r.len()).max(1) as isize, maze.len().max(1) as isize);
+        if wrap { (i.rem_euclid(w), j.rem_euclid(h)) } else { (i, j) }
+    };
+    let hit = |t: f32, side: HitSide, i: isize, j: isize| {
+        let ray_dist = t * block;
+        RayHit {
+            dist: (ray_dist * (angle - player.a).cos().abs()).max(1.0),
+            ray_dist,
+            x: player.pos.x + dx * ray_dist,
+            y: player.pos.y + dy * ray_dist,
+            side,
+            cell: fold(i, j),
+        }
+    };
+    // Standing inside a wall (shouldn't happen): report it right at the camera
+    if cell_at(maze, i, j, wrap).is_some_and(|c| !tiles().player_walkable(c)) {
+        return Some(hit(0.0, HitSide::Vertical, i, j));
+    }
+    loop {
+        fb.stats.ray_steps += 1;
+        let (t, side) = if next_x < next_y {
+            let t = next_x; next_x += delta_x; i += step_i; (t, HitSide::Vertical)
+        } else {
+            let t = next_y; next_y += delta_y; j += step_j; (t, HitSide::Horizontal)
+        };
+        if t > MAX_RAY_CELLS { return None; }
+        let c = cell_at(maze, i, j, wrap)?;
+        if !tiles().player_walkable(c) { return Some(hit(t, side, i, j)); }
+    }
+}
+
 pub fn cast_ray(
     fb: &mut Framebuffer,
     maze: &Maze,
@@ -12,31 +86,101 @@ pub fn cast_ray(
     angle: f32,
     block_size: usize,
     debug_draw: bool,
+    wrap: bool,
 ) -> f32 {
-    let step = 4.0f32;
-    let mut d = 0.0f32;
-    let dir = (angle.cos(), angle.sin());
-    let max_dist = 2000.0;
-    let mut hit = false;
-    let (mut hx, mut hy) = (player.pos.x, player.pos.y);
-    while d < max_dist {
-        hx = player.pos.x + dir.0 * d;
-        hy = player.pos.y + dir.1 * d;
-        let i = (hx / block_size as f32).floor() as isize;
-        let j = (hy / block_size as f32).floor() as isize;
-        if i < 0 || j < 0 { break; }
-        let (i,j)=(i as usize, j as usize);
-        if j >= maze.len() || i >= maze[0].len() { break; }
-        let c = maze[j][i];
-        if c != ' ' { hit = true; break; }
-        d += step;
-    }
+    let hit = cast_ray_hit(fb, maze, player, angle, block_size, wrap);
     if debug_draw {
+        let reach = hit.map_or(block_size as f32 * MAX_RAY_CELLS, |h| h.ray_dist);
+        let (hx, hy) = (player.pos.x + angle.cos() * reach, player.pos.y + angle.sin() * reach);
         fb.set_current_color(Color::WHITE);
         line(fb, player.pos.x as i32, player.pos.y as i32, hx as i32, hy as i32);
     }
-    if !hit { return 0.0; }
-    let diff = angle - player.a;
-    let d_corr = d * diff.cos().abs();
-    d_corr.max(1.0)
+    hit.map_or(0.0, |h| h.dist)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const B: usize = 64;
+
+    fn grid(rows: &[&str]) -> Maze {
+        rows.iter().map(|r| r.chars().collect()).collect()
+    }
+
+    // Room with one solid block at cell (4, 3)
+    fn room() -> Maze {
+        grid(&[
+            "+-----+",
+            "|     |",
+            "|     |",
+            "|   # |",
+            "|     |",
+            "+-----+",
+        ])
+    }
+
+    fn cast(maze: &Maze, player: &Player, angle: f32, wrap: bool) -> Option<RayHit> {
+        cast_ray_hit(&mut Framebuffer::new(4, 4), maze, player, angle, B, wrap)
+    }
+
+    fn close(a: f32, b: f32) -> bool { (a - b).abs() < 1e-3 }
+
+    #[test]
+    fn axis_aligned_rays_hit_the_facing_walls() {
+        let maze = room();
+        let p = Player::new(96.0, 96.0, 0.0);
+        let east = cast(&maze, &p, 0.0, false).unwrap();
+        assert!(close(east.ray_dist, 288.0) && close(east.dist, 288.0));
+        assert_eq!((east.side, east.cell), (HitSide::Vertical, (6, 1)));
+        assert!(close(east.x, 384.0) && close(east.y, 96.0));
+        let p = Player::new(96.0, 96.0, std::f32::consts::FRAC_PI_2);
+        let south = cast(&maze, &p, std::f32::consts::FRAC_PI_2, false).unwrap();
+        assert!(close(south.ray_dist, 224.0) && close(south.dist, 224.0));
+        assert_eq!((south.side, south.cell), (HitSide::Horizontal, (1, 5)));
+    }
+
+    #[test]
+    fn diagonal_rays_report_the_face_they_cross() {
+        let maze = room();
+        let p = Player::new(96.0, 96.0, 0.0);
+        // Aimed at the block's west face (x = 256) and at its north face (y = 192)
+        let west_face = cast(&maze, &p, 128f32.atan2(160.0), false).unwrap();
+        assert_eq!((west_face.side, west_face.cell), (HitSide::Vertical, (4, 3)));
+        assert!(close(west_face.ray_dist, (160f32 * 160.0 + 128.0 * 128.0).sqrt()));
+        assert!(close(west_face.x, 256.0) && close(west_face.y, 224.0));
+        let north_face = cast(&maze, &p, 96f32.atan2(192.0), false).unwrap();
+        assert_eq!((north_face.side, north_face.cell), (HitSide::Horizontal, (4, 3)));
+        assert!(close(north_face.ray_dist, (192f32 * 192.0 + 96.0 * 96.0).sqrt()));
+        // Off-centre rays are fish-eye corrected against the view direction
+        assert!(close(north_face.dist, north_face.ray_dist * (96f32.atan2(192.0)).cos()));
+    }
+
+    #[test]
+    fn wrapped_rays_come_back_in_from_the_opposite_edge() {
+        let maze = grid(&["#####", "#    ", "#####"]);
+        let p = Player::new(96.0, 96.0, 0.0);
+        let hit = cast(&maze, &p, 0.0, true).unwrap();
+        assert!(close(hit.ray_dist, 224.0));
+        assert!(close(hit.x, 320.0));
+        assert_eq!((hit.side, hit.cell), (HitSide::Vertical, (0, 1)));
+        assert_eq!(cast(&maze, &p, 0.0, false), None);
+    }
+
+    #[test]
+    fn starting_inside_a_wall_hits_at_the_camera() {
+        let maze = room();
+        let p = Player::new(4.5 * 64.0, 3.5 * 64.0, 0.0);
+        let hit = cast(&maze, &p, 1.0, false).unwrap();
+        assert_eq!((hit.ray_dist, hit.dist, hit.cell), (0.0, 1.0, (4, 3)));
+    }
+
+    #[test]
+    fn rays_give_up_past_max_reach() {
+        let row = format!("|{}|", " ".repeat(38));
+        let maze = grid(&[&"-".repeat(40), &row, &"-".repeat(40)]);
+        let p = Player::new(96.0, 96.0, 0.0);
+        assert_eq!(cast(&maze, &p, 0.0, false), None);
+        assert!(cast(&maze, &p, std::f32::consts::PI, false).is_some());
+    }
 }
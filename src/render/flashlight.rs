@@ -0,0 +1,85 @@
+//! Flashlight darkness overlay drawn from one precomputed radial-gradient texture.
+//!
+//! The texture is white with alpha 0 inside `GRAD_INNER`, a smooth ramp up to opaque at
+//! `GRAD_OUTER` (fractions of the half-size) and opaque beyond; the draw tint picks the
+//! darkness color and strength.
+use raylib::prelude::*;
+
+pub const GRAD_SIZE: u32 = 256;
+pub const GRAD_INNER: f32 = 0.50;
+pub const GRAD_OUTER: f32 = 0.62;
+
+/// RGBA texels of a `size`x`size` radial gradient (row-major).
+pub fn gradient_pixels(size: u32, inner: f32, outer: f32) -> Vec<Color> {
+    let half = size as f32 * 0.5;
+    let span = (outer - inner).max(1e-4);
+    let mut px = Vec::with_capacity((size * size) as usize);
+    for y in 0..size {
+        for x in 0..size {
+            let (dx, dy) = (x as f32 + 0.5 - half, y as f32 + 0.5 - half);
+            let r = (dx*dx + dy*dy).sqrt() / half;
+            let t = ((r - inner) / span).clamp(0.0, 1.0);
+            let a = t * t * (3.0 - 2.0 * t); // smoothstep
+            px.push(Color::new(255, 255, 255, (a * 255.0).round() as u8));
+        }
+    }
+    px
+}
+
+/// Upload the gradient once; `None` if the texture couldn't be created.
+pub fn make_gradient_texture(rl: &mut RaylibHandle, thread: &RaylibThread) -> Option<Texture2D> {
+    let img = Image::gen_image_color(GRAD_SIZE as i32, GRAD_SIZE as i32, Color::BLANK);
+    let mut tex = rl.load_texture_from_image(thread, &img).ok()?;
+    let px = gradient_pixels(GRAD_SIZE, GRAD_INNER, GRAD_OUTER);
+    let bytes: &[u8] = unsafe { std::slice::from_raw_parts(px.as_ptr() as *const u8, px.len() * std::mem::size_of::<Color>()) };
+    tex.update_texture(bytes).ok()?;
+    tex.set_texture_filter(thread, TextureFilter::TEXTURE_FILTER_BILINEAR);
+    Some(tex)
+}
+
+/// Darken everything outside a circle of radius `r0` around `(cx, cy)`: the gradient quad
+/// plus four solid bars for the rest of the window, all with `tint` (alpha = darkness).
+pub fn draw_flashlight(d: &mut impl RaylibDraw, tex: &Texture2D, cx: f32, cy: f32, r0: f32, window_width: i32, window_height: i32, tint: Color) {
+    let half = (r0 / GRAD_INNER).max(1.0);
+    let (x0, y0) = ((cx - half).floor(), (cy - half).floor());
+    let side = (2.0 * half).ceil();
+    let src = Rectangle { x: 0.0, y: 0.0, width: tex.width() as f32, height: tex.height() as f32 };
+    let dst = Rectangle { x: x0, y: y0, width: side, height: side };
+    d.draw_texture_pro(tex, src, dst, Vector2 { x: 0.0, y: 0.0 }, 0.0, tint);
+    let (qx0, qy0, qx1, qy1) = (x0 as i32, y0 as i32, (x0 + side) as i32, (y0 + side) as i32);
+    if qy0 > 0 { d.draw_rectangle(0, 0, window_width, qy0, tint); }
+    if qy1 < window_height { d.draw_rectangle(0, qy1, window_width, window_height - qy1, tint); }
+    let (ry0, ry1) = (qy0.max(0), qy1.min(window_height));
+    if ry1 > ry0 {
+        if qx0 > 0 { d.draw_rectangle(0, ry0, qx0, ry1 - ry0, tint); }
+        if qx1 < window_width { d.draw_rectangle(qx1, ry0, window_width - qx1, ry1 - ry0, tint); }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gradient_is_clear_in_the_middle_and_opaque_at_the_edge() {
+        let px = gradient_pixels(GRAD_SIZE, GRAD_INNER, GRAD_OUTER);
+        assert_eq!(px.len(), (GRAD_SIZE * GRAD_SIZE) as usize);
+        let at = |x: u32, y: u32| px[(y * GRAD_SIZE + x) as usize];
+        let mid = GRAD_SIZE / 2;
+        assert_eq!(at(mid, mid).a, 0);
+        assert_eq!(at(mid + GRAD_SIZE / 5, mid).a, 0);
+        assert_eq!(at(0, mid).a, 255);
+        assert_eq!(at(0, 0).a, 255);
+        assert!(px.iter().all(|c| (c.r, c.g, c.b) == (255, 255, 255)));
+    }
+
+    #[test]
+    fn gradient_alpha_rises_through_the_ramp() {
+        let px = gradient_pixels(GRAD_SIZE, GRAD_INNER, GRAD_OUTER);
+        let mid = GRAD_SIZE / 2;
+        // Along a radius, from the centre out to the edge
+        let alphas: Vec<u8> = (0..mid).rev().map(|x| px[(mid * GRAD_SIZE + x) as usize].a).collect();
+        assert!(alphas.windows(2).all(|w| w[0] <= w[1]));
+        assert!(alphas.iter().any(|&a| a > 0 && a < 255));
+    }
+}
@@ -3,10 +3,12 @@
 //! Responsibilities:
 //! - Maintain a CPU-side RGBA buffer with simple pixel ops
 //! - Upload buffer to a persistent Raylib `Texture2D`
-//! - Provide lightweight blur and vignette helpers (used selectively)
+//! - Provide lightweight blur, vignette, color-grade and scanline helpers (used selectively)
+//! - Multi-pass effects double-buffer through a reusable scratch buffer (no per-frame allocation)
 //!
 use raylib::prelude::*;
 use raylib::core::texture::RaylibTexture2D;
+use crate::render::perf::RenderStats;
 
 pub struct Framebuffer {
     pub color_buffer: Vec<Color>,
@@ -14,13 +16,25 @@ pub struct Framebuffer {
     pub height: u32,
     pub background_color: Color,
     pub current_color: Color,
+    scratch: Vec<Color>,
+    /// Work counters for the current frame (reset by the caller each frame).
+    pub stats: RenderStats,
 }
 
 impl Framebuffer {
     pub fn new(width: u32, height: u32) -> Self {
         let size = (width * height) as usize;
         let bg = Color::BLACK;
-        Self { color_buffer: vec![bg; size], width, height, background_color: bg, current_color: Color::WHITE }
+        Self { color_buffer: vec![bg; size], width, height, background_color: bg, current_color: Color::WHITE, scratch: Vec::with_capacity(size), stats: RenderStats::default() }
+    }
+    /// Reallocate to `width` x `height`, filled with the background color; colors are kept.
+    /// Callers must also resize their zbuffer and reallocate the GPU texture.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        let size = (width * height) as usize;
+        self.color_buffer = vec![self.background_color; size];
+        self.scratch = Vec::with_capacity(size);
+        self.width = width;
+        self.height = height;
     }
     #[inline]
     pub fn clear(&mut self) { self.color_buffer.fill(self.background_color); }
@@ -33,13 +47,21 @@ impl Framebuffer {
     #[inline] pub fn set_current_color(&mut self, c: Color) { self.current_color = c; }
     #[inline] pub fn set_background_color(&mut self, c: Color) { self.background_color = c; }
     pub fn upload_to_texture(&self, tex: &mut Texture2D) { let byte_len = self.color_buffer.len() * std::mem::size_of::<Color>(); let bytes: &[u8] = unsafe { std::slice::from_raw_parts(self.color_buffer.as_ptr() as *const u8, byte_len) }; let _ = tex.update_texture(bytes); }
+    // Copy the frame into the scratch buffer; after the first call this reuses its capacity.
+    fn fill_scratch(&mut self) {
+        self.scratch.clear();
+        self.scratch.extend_from_slice(&self.color_buffer);
+    }
+    /// Capacity of the scratch buffer (stays put once the first blur has sized it).
+    pub fn scratch_capacity(&self) -> usize { self.scratch.capacity() }
     pub fn apply_anxiety_blur(&mut self, strength: f32, passes: u32) {
         if strength <= 0.0 { return; }
         let s = strength.clamp(0.0, 1.0);
         let w = self.width as i32;
         let h = self.height as i32;
-        let mut tmp: Vec<Color> = self.color_buffer.clone();
+        self.fill_scratch();
         for _ in 0..passes.min(3) {
+            self.stats.blur_passes += 1;
             for y in 1..h-1 {
                 let ym = (y-1) as u32; let y0 = y as u32; let yp = (y+1) as u32;
                 for x in 1..w-1 {
@@ -52,10 +74,10 @@ impl Framebuffer {
                     let avg_b = (c.b as u32 + c1.b as u32 + c2.b as u32 + c3.b as u32 + c4.b as u32) / 5;
                     let lerp = |a: u8, b: u32| -> u8 { ( (a as f32) * (1.0 - s) + (b as f32) * s ) as u8 };
                     let out = Color::new(lerp(c.r, avg_r), lerp(c.g, avg_g), lerp(c.b, avg_b), c.a);
-                    tmp[(y0 * self.width + x0) as usize] = out;
+                    self.scratch[(y0 * self.width + x0) as usize] = out;
                 }
             }
-            std::mem::swap(&mut self.color_buffer, &mut tmp);
+            std::mem::swap(&mut self.color_buffer, &mut self.scratch);
         }
     }
     pub fn apply_vignette(&mut self, intensity: f32) {
@@ -68,14 +90,174 @@ impl Framebuffer {
             }
         }
     }
+    /// CRT look: odd rows darkened by `intensity` (0 = no-op, 1 = black), plus an optional faint
+    /// vertical RGB mask (each column favors one channel). Run after the other effects.
+    pub fn apply_scanlines(&mut self, intensity: f32, rgb_mask: bool) {
+        let k = intensity.clamp(0.0, 1.0); if k <= 0.0 { return; }
+        let mask_k = 1.0 - 0.25 * k;
+        for (y, row) in self.color_buffer.chunks_mut(self.width.max(1) as usize).enumerate() {
+            let row_k = if y % 2 == 1 { 1.0 - k } else { 1.0 };
+            for (x, c) in row.iter_mut().enumerate() {
+                let (mut kr, mut kg, mut kb) = (row_k, row_k, row_k);
+                if rgb_mask {
+                    match x % 3 { 0 => { kg *= mask_k; kb *= mask_k; } 1 => { kr *= mask_k; kb *= mask_k; } _ => { kr *= mask_k; kg *= mask_k; } }
+                }
+                *c = Color::new((c.r as f32 * kr) as u8, (c.g as f32 * kg) as u8, (c.b as f32 * kb) as u8, c.a);
+            }
+        }
+    }
+    pub fn apply_color_grade(&mut self, tint: Color, saturation: f32, contrast: f32) {
+        let sat = saturation.max(0.0);
+        let con = contrast.max(0.0);
+        let (tr, tg, tb) = (tint.r as f32 / 255.0, tint.g as f32 / 255.0, tint.b as f32 / 255.0);
+        for c in self.color_buffer.iter_mut() {
+            let (r, g, b) = (c.r as f32, c.g as f32, c.b as f32);
+            let lum = 0.299 * r + 0.587 * g + 0.114 * b;
+            let grade = |v: f32, t: f32| -> u8 { let v = lum + (v - lum) * sat; let v = (v - 128.0) * con + 128.0; (v * t).clamp(0.0, 255.0) as u8 };
+            *c = Color::new(grade(r, tr), grade(g, tg), grade(b, tb), c.a);
+        }
+    }
     pub fn apply_circular_blur(&mut self, strength: f32, passes: u32, radius_ratio: f32) {
         if strength <= 0.0 { return; }
         let s = strength.clamp(0.0, 1.0);
-        let w = self.width as i32; let h = self.height as i32; let mut tmp: Vec<Color> = self.color_buffer.clone();
+        let w = self.width as i32; let h = self.height as i32; self.fill_scratch();
         let cx = (self.width as f32) * 0.5; let cy = (self.height as f32) * 0.5; let r_base = (self.width.min(self.height) as f32) * 0.5 * radius_ratio.clamp(0.05, 1.0); let r2 = r_base * r_base;
         for _ in 0..passes.min(2) {
-            for y in 1..h-1 { let y0 = y as u32; let ym = (y-1) as u32; let yp = (y+1) as u32; for x in 1..w-1 { let x0 = x as u32; let dx = x as f32 - cx; let dy = y as f32 - cy; if dx*dx + dy*dy > r2 { continue; } let xm = (x-1) as u32; let xp = (x+1) as u32; let c  = self.get_pixel(x0,y0); let c1 = self.get_pixel(xm,y0); let c2 = self.get_pixel(xp,y0); let c3 = self.get_pixel(x0,ym); let c4 = self.get_pixel(x0,yp); let avg_r = (c.r as u32 + c1.r as u32 + c2.r as u32 + c3.r as u32 + c4.r as u32) / 5; let avg_g = (c.g as u32 + c1.g as u32 + c2.g as u32 + c3.g as u32 + c4.g as u32) / 5; let avg_b = (c.b as u32 + c1.b as u32 + c2.b as u32 + c3.b as u32 + c4.b as u32) / 5; let lerp = |a: u8, b: u32| -> u8 { ((a as f32) * (1.0 - s) + (b as f32) * s) as u8 }; let out = Color::new(lerp(c.r, avg_r), lerp(c.g, avg_g), lerp(c.b, avg_b), c.a); tmp[(y0 * self.width + x0) as usize] = out; } }
-            std::mem::swap(&mut self.color_buffer, &mut tmp);
+            self.stats.blur_passes += 1;
+            for y in 1..h-1 { let y0 = y as u32; let ym = (y-1) as u32; let yp = (y+1) as u32; for x in 1..w-1 { let x0 = x as u32; let dx = x as f32 - cx; let dy = y as f32 - cy; if dx*dx + dy*dy > r2 { continue; } let xm = (x-1) as u32; let xp = (x+1) as u32; let c  = self.get_pixel(x0,y0); let c1 = self.get_pixel(xm,y0); let c2 = self.get_pixel(xp,y0); let c3 = self.get_pixel(x0,ym); let c4 = self.get_pixel(x0,yp); let avg_r = (c.r as u32 + c1.r as u32 + c2.r as u32 + c3.r as u32 + c4.r as u32) / 5; let avg_g = (c.g as u32 + c1.g as u32 + c2.g as u32 + c3.g as u32 + c4.g as u32) / 5; let avg_b = (c.b as u32 + c1.b as u32 + c2.b as u32 + c3.b as u32 + c4.b as u32) / 5; let lerp = |a: u8, b: u32| -> u8 { ((a as f32) * (1.0 - s) + (b as f32) * s) as u8 }; let out = Color::new(lerp(c.r, avg_r), lerp(c.g, avg_g), lerp(c.b, avg_b), c.a); self.scratch[(y0 * self.width + x0) as usize] = out; } }
+            std::mem::swap(&mut self.color_buffer, &mut self.scratch);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 4x2 frame with a spread of colors
+    fn sample_frame() -> Framebuffer {
+        let mut fb = Framebuffer::new(4, 2);
+        let colors = [
+            Color::new(255, 0, 0, 255), Color::new(0, 255, 0, 255), Color::new(0, 0, 255, 255), Color::new(200, 120, 40, 255),
+            Color::new(13, 77, 201, 128), Color::WHITE, Color::BLACK, Color::new(90, 90, 90, 255),
+        ];
+        fb.color_buffer.copy_from_slice(&colors);
+        fb
+    }
+
+    #[test]
+    fn zero_saturation_turns_the_frame_gray() {
+        let mut fb = sample_frame();
+        fb.apply_color_grade(Color::WHITE, 0.0, 1.0);
+        for (c, orig) in fb.color_buffer.iter().zip(sample_frame().color_buffer) {
+            assert!(c.r == c.g && c.g == c.b, "{:?}", c);
+            assert_eq!(c.a, orig.a);
+        }
+        // Brighter inputs stay brighter
+        assert!(fb.color_buffer[1].r > fb.color_buffer[2].r);
+    }
+
+    #[test]
+    fn full_saturation_with_neutral_settings_keeps_colors() {
+        let mut fb = sample_frame();
+        fb.apply_color_grade(Color::WHITE, 1.0, 1.0);
+        assert_eq!(fb.color_buffer, sample_frame().color_buffer);
+    }
+
+
+    // Patterned frame big enough for the blur's interior to matter
+    fn noisy_frame() -> Framebuffer {
+        let mut fb = Framebuffer::new(16, 12);
+        for (k, c) in fb.color_buffer.iter_mut().enumerate() {
+            let k = k as u32;
+            *c = Color::new((k * 37 % 256) as u8, (k * 91 % 256) as u8, (k * 13 % 256) as u8, 255);
+        }
+        fb
+    }
+
+    // The blur as it was before the scratch buffer: a fresh copy of the frame every pass
+    fn allocating_blur(fb: &Framebuffer, s: f32, passes: u32) -> Vec<Color> {
+        let (w, h) = (fb.width as usize, fb.height as usize);
+        let mut cur = fb.color_buffer.clone();
+        for _ in 0..passes.min(3) {
+            let mut tmp = cur.clone();
+            for y in 1..h - 1 {
+                for x in 1..w - 1 {
+                    let px = |x: usize, y: usize| cur[y * w + x];
+                    let (c, n) = (px(x, y), [px(x - 1, y), px(x + 1, y), px(x, y - 1), px(x, y + 1)]);
+                    let avg = |f: fn(&Color) -> u8| (f(&c) as u32 + n.iter().map(|v| f(v) as u32).sum::<u32>()) / 5;
+                    let lerp = |a: u8, b: u32| ((a as f32) * (1.0 - s) + (b as f32) * s) as u8;
+                    tmp[y * w + x] = Color::new(lerp(c.r, avg(|c| c.r)), lerp(c.g, avg(|c| c.g)), lerp(c.b, avg(|c| c.b)), c.a);
+                }
+            }
+            cur = tmp;
+        }
+        cur
+    }
+
+    #[test]
+    fn blur_reuses_its_scratch_buffer() {
+        let mut fb = noisy_frame();
+        fb.apply_anxiety_blur(0.6, 2);
+        let cap = fb.scratch_capacity();
+        assert!(cap >= fb.color_buffer.len());
+        for _ in 0..5 {
+            fb.apply_anxiety_blur(0.6, 3);
+            fb.apply_circular_blur(0.5, 2, 0.8);
+            assert_eq!(fb.scratch_capacity(), cap);
+        }
+    }
+
+    #[test]
+    fn blur_matches_the_allocating_version() {
+        for passes in [1, 2, 3] {
+            let mut fb = noisy_frame();
+            // Run twice so the second call starts from a used scratch buffer
+            fb.apply_anxiety_blur(0.7, passes);
+            let expected = allocating_blur(&fb, 0.7, passes);
+            fb.apply_anxiety_blur(0.7, passes);
+            assert_eq!(fb.color_buffer, expected, "{} passes", passes);
+        }
+    }
+
+    #[test]
+    fn resize_reallocates_with_the_background() {
+        let mut fb = Framebuffer::new(8, 4);
+        let bg = Color::new(10, 20, 30, 255);
+        fb.set_background_color(bg);
+        fb.set_current_color(Color::RED);
+        fb.set_pixel(1, 1);
+        fb.resize(5, 7);
+        assert_eq!((fb.width, fb.height, fb.color_buffer.len()), (5, 7, 35));
+        assert!(fb.color_buffer.iter().all(|&c| c == bg));
+        assert_eq!((fb.background_color, fb.current_color), (bg, Color::RED));
+        // Drawing uses the new stride and ignores the old bounds
+        fb.set_pixel(4, 6);
+        fb.set_pixel(7, 1);
+        assert_eq!(fb.get_pixel(4, 6), Color::RED);
+        assert_eq!(fb.color_buffer.iter().filter(|&&c| c == Color::RED).count(), 1);
+    }
+
+    #[test]
+    fn scanlines_darken_odd_rows_only() {
+        let flat = || {
+            let mut fb = Framebuffer::new(6, 4);
+            fb.set_background_color(Color::new(200, 160, 120, 255));
+            fb.clear();
+            fb
+        };
+        let mut fb = flat();
+        fb.apply_scanlines(0.0, true);
+        assert_eq!(fb.color_buffer, flat().color_buffer);
+        fb.apply_scanlines(0.5, false);
+        for x in 0..6 {
+            assert_eq!(fb.get_pixel(x, 0), Color::new(200, 160, 120, 255));
+            assert_eq!(fb.get_pixel(x, 1), Color::new(100, 80, 60, 255));
         }
+        // The RGB mask keeps one channel per column at full strength
+        let mut fb = flat();
+        fb.apply_scanlines(0.5, true);
+        let (r, g) = (fb.get_pixel(0, 0), fb.get_pixel(1, 0));
+        assert!(r.r == 200 && r.g < 160 && g.g == 160 && g.r < 200);
     }
 }
@@ -7,6 +7,9 @@
 //! - `line`: Bresenham integer line drawing
 //! - `render3d`: Column renderer for walls and scene
 //! - `sprites`: Sprite drawing (billboards and sorting)
+//! - `flashlight`: Radial-gradient flashlight overlay
+//! - `budget`: Frame-time budget that sheds optional effects
+//! - `perf`: Per-frame render counters (rays, steps, sprite pixels, blur passes)
 
 pub mod framebuffer;
 pub mod textures;
@@ -14,3 +17,6 @@ pub mod casters;
 pub mod line;
 pub mod render3d;
 pub mod sprites;
+pub mod flashlight;
+pub mod budget;
+pub mod perf;
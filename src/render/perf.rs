@@ -0,0 +1,53 @@
+//! Per-frame render counters for performance tuning.
+//!
+//! The framebuffer owns one `RenderStats`; the ray caster, sprite drawing and blur passes
+//! bump it as they work, and the game resets it at the start of each frame.
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    /// Rays cast (walls plus the 2D debug view).
+    pub rays: u32,
+    /// Grid cells stepped through by all rays.
+    pub ray_steps: u32,
+    /// Sprite pixels written.
+    pub sprite_pixels: u32,
+    /// Full-screen blur passes run.
+    pub blur_passes: u32,
+}
+
+impl RenderStats {
+    pub fn reset(&mut self) { *self = Self::default(); }
+
+    /// One-line summary for the overlay.
+    pub fn summary(&self) -> String {
+        let avg = if self.rays > 0 { self.ray_steps as f32 / self.rays as f32 } else { 0.0 };
+        format!("rays {}  steps {} ({:.1}/ray)  sprite px {}  blur {}", self.rays, self.ray_steps, avg, self.sprite_pixels, self.blur_passes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::player::Player;
+    use crate::render::casters::cast_ray_hit;
+    use crate::render::framebuffer::Framebuffer;
+
+    #[test]
+    fn counters_accumulate_within_a_frame_and_reset_between() {
+        let maze: Vec<Vec<char>> = ["+----+", "|    |", "+----+"].iter().map(|r| r.chars().collect()).collect();
+        let p = Player::new(96.0, 96.0, 0.0);
+        let mut fb = Framebuffer::new(16, 16);
+        // Frame 1: two rays east (4 cells to the wall each) and a two-pass blur
+        for _ in 0..2 { cast_ray_hit(&mut fb, &maze, &p, 0.0, 64, false); }
+        fb.apply_anxiety_blur(0.5, 2);
+        assert_eq!(fb.stats, RenderStats { rays: 2, ray_steps: 8, sprite_pixels: 0, blur_passes: 2 });
+        assert!(fb.stats.summary().contains("(4.0/ray)"));
+        // Frame 2 starts from zero; passes are capped per call
+        fb.stats.reset();
+        assert_eq!(fb.stats, RenderStats::default());
+        fb.apply_anxiety_blur(0.5, 9);
+        fb.apply_anxiety_blur(0.0, 2);
+        assert_eq!(fb.stats, RenderStats { blur_passes: 3, ..RenderStats::default() });
+        assert!(fb.stats.summary().contains("(0.0/ray)"));
+    }
+}
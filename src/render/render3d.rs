@@ -1,16 +1,110 @@
 //! 3D renderer (columns + textured walls, sky/ground).
 use raylib::prelude::*;
 use crate::render::framebuffer::Framebuffer;
-use crate::core::maze::Maze;
+use crate::core::maze::{Maze, cell_at, wrap_pos};
+use crate::core::tiles::{tiles, EXIT};
 use crate::core::player::Player;
 use crate::render::textures::TextureManager;
-use crate::render::casters::cast_ray;
+use crate::render::casters::{cast_ray_hit, HitSide, RayHit};
 
 const CEIL_TOP:   Color = Color::new(10, 12, 18, 255);
 const CEIL_MID:   Color = Color::new(20, 24, 32, 255);
 const FLOOR_NEAR: Color = Color::new(56, 58, 62, 255);
 const FLOOR_FAR:  Color = Color::new(26, 28, 30, 255);
 
+/// How wall columns are filled.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WallStyle {
+    /// Texture-mapped walls (default).
+    Textured,
+    /// One solid color per wall texture key.
+    FlatColor,
+    /// Only the top and bottom edge of each column.
+    Wireframe,
+}
+
+impl WallStyle {
+    pub fn next(self) -> Self {
+        match self { WallStyle::Textured => WallStyle::FlatColor, WallStyle::FlatColor => WallStyle::Wireframe, WallStyle::Wireframe => WallStyle::Textured }
+    }
+}
+
+const WIRE_COLOR: Color = Color::new(80, 255, 220, 255);
+
+/// Upper bound for `render_3d`'s rays-per-column supersampling factor.
+pub const MAX_RAY_FACTOR: u32 = 4;
+
+// Projection constant tuned for `PROJ_BLOCK`-sized cells; scaled by the actual cell size
+// so a wall keeps the same on-screen height at the same distance measured in cells.
+const PROJ_K: f32 = 120.0;
+const PROJ_BLOCK: f32 = 64.0;
+
+#[inline]
+pub fn projection_k(block_size: usize) -> f32 { PROJ_K * (block_size as f32 / PROJ_BLOCK) }
+
+/// On-screen height (px) of a wall at perpendicular distance `d` (world units); `hh` is half
+/// the framebuffer height.
+#[inline]
+pub fn column_height(hh: f32, d: f32, block_size: usize) -> f32 { (hh / d) * projection_k(block_size) }
+
+/// Perpendicular distance (world units) of the floor or ceiling seen `dy` px from the horizon:
+/// the inverse of `column_height`, so a wall's base lands on the floor row at its distance.
+#[inline]
+pub fn row_distance(hh: f32, dy: f32, block_size: usize) -> f32 { hh * projection_k(block_size) * 0.5 / dy }
+
+/// Default height (px) below which a wall column is filled with one fogged color instead of
+/// being texture-sampled; detail is invisible that small. 0 disables the shortcut.
+pub const FLAT_COLUMN_PX: f32 = 6.0;
+// Distance (in cells) at which a flat distant column is fully fogged to `FLOOR_FAR`.
+const FLAT_FOG_CELLS: f32 = 32.0;
+
+/// True if a column `col_px` pixels tall should skip texture sampling.
+#[inline]
+pub fn column_is_flat(col_px: f32, threshold: f32) -> bool { col_px < threshold }
+
+// Fake contact shadow: columns within `AO_WIDTH` (in texture U) of a cell edge get darker,
+// up to `AO_STRENGTH` right at the edge.
+const AO_WIDTH: f32 = 0.12;
+const AO_STRENGTH: f32 = 0.35;
+
+/// Brightness multiplier for a wall column at texture coordinate `u` (1.0 mid-wall, darker
+/// towards u = 0 / 1 where walls meet).
+#[inline]
+pub fn corner_ao(u: f32) -> f32 {
+    let edge = u.min(1.0 - u).clamp(0.0, 0.5);
+    if edge >= AO_WIDTH { return 1.0; }
+    let k = 1.0 - edge / AO_WIDTH;
+    1.0 - AO_STRENGTH * k * k
+}
+
+// Cheap anisotropic filtering: at grazing angles a screen column spans many texels along U,
+// so average a few taps across that span instead of sampling one texel.
+pub const MAX_ANISO_TAPS: u32 = 4;
+const ANISO_GRAZING_COS: f32 = 0.35;
+
+/// Texel taps along U for a wall hit whose incidence cosine (ray vs. wall normal) is `cos_inc`:
+/// 1 head-on, more as the angle gets shallower, up to `MAX_ANISO_TAPS`.
+#[inline]
+pub fn aniso_taps(cos_inc: f32) -> u32 {
+    let c = cos_inc.abs();
+    if c >= ANISO_GRAZING_COS { return 1; }
+    ((ANISO_GRAZING_COS / c.max(1e-3)).ceil() as u32).clamp(2, MAX_ANISO_TAPS)
+}
+
+#[inline]
+fn average_colors(cs: &[Color]) -> Color {
+    let n = cs.len().max(1) as u32;
+    let (mut r, mut g, mut b, mut a) = (0u32, 0u32, 0u32, 0u32);
+    for c in cs { r += c.r as u32; g += c.g as u32; b += c.b as u32; a += c.a as u32; }
+    Color::new((r / n) as u8, (g / n) as u8, (b / n) as u8, (a / n) as u8)
+}
+
+#[inline]
+fn scale_color(c: Color, k: f32) -> Color {
+    let f = |x: u8| -> u8 { (x as f32 * k) as u8 };
+    Color::new(f(c.r), f(c.g), f(c.b), c.a)
+}
+
 #[inline]
 fn lerp_color(a: Color, b: Color, t: f32) -> Color {
     let t = t.clamp(0.0, 1.0);
@@ -18,20 +112,41 @@ fn lerp_color(a: Color, b: Color, t: f32) -> Color {
     Color::new(f(a.r, b.r), f(a.g, b.g), f(a.b, b.b), 255)
 }
 
-fn paint_ceiling_and_floor_textured(fb: &mut Framebuffer, texman: &TextureManager) {
-    let w = fb.width as u32;
-    let h = fb.height as u32;
+// Floor/ceiling caster. Each row at `dy` pixels from the horizon sees the ground (or the
+// ceiling, mirrored) at perpendicular distance `row_distance(dy)`, the inverse of the wall
+// column height; each screen column adds its ray direction divided by the fish-eye factor, so
+// the texture recedes exactly like the walls. Without a 'G'/'K' texture the gradient stays.
+fn paint_ceiling_and_floor_textured(fb: &mut Framebuffer, texman: &TextureManager, player: &Player, block_size: usize) {
+    let w = fb.width;
+    let h = fb.height;
     let hh = h / 2;
-    if let Some((tw, th)) = texman.image_size('K') {
-        for y in 0..hh {
-            let ty = (y as u32 * th) / hh;
-            for x in 0..w {
-                let tx = ((x as u32) * tw) / w;
-                let c = texman.get_pixel_color('K', tx, ty.min(th-1));
-                fb.set_pixel_color(x, y, c);
+    let block = block_size as f32;
+    let horizon = h as f32 * 0.5;
+    // Per-column world step per unit of perpendicular distance (cells)
+    let dirs: Vec<(f32, f32)> = (0..w).map(|x| {
+        let diff = (x as f32 / w as f32 - 0.5) * player.fov;
+        let a = player.a + diff;
+        let inv = 1.0 / diff.cos().max(1e-3) / block;
+        (a.cos() * inv, a.sin() * inv)
+    }).collect();
+    let (px, py) = (player.pos.x / block, player.pos.y / block);
+    let cast = |fb: &mut Framebuffer, key: char, rows: std::ops::Range<u32>, dy_of: &dyn Fn(u32) -> f32, far: Color| {
+        let Some((tw, th)) = texman.image_size(key) else { return false; };
+        for y in rows {
+            let d = row_distance(horizon, dy_of(y).max(0.5), block_size);
+            let fog = (d / block / FLAT_FOG_CELLS).clamp(0.0, 1.0);
+            for (x, &(dx, dy)) in dirs.iter().enumerate() {
+                let (wx, wy) = (px + dx * d, py + dy * d);
+                let tx = (wx.rem_euclid(1.0) * tw as f32) as u32;
+                let ty = (wy.rem_euclid(1.0) * th as f32) as u32;
+                let c = texman.get_pixel_color(key, tx.min(tw - 1), ty.min(th - 1));
+                fb.set_pixel_color(x as u32, y, if fog > 0.0 { lerp_color(c, far, fog) } else { c });
             }
         }
-    } else {
+        true
+    };
+    let ceiling_dy = |y: u32| horizon - (y as f32 + 0.5);
+    if !cast(fb, 'K', 0..hh, &ceiling_dy, CEIL_TOP) {
         for y in 0..hh {
             let t = y as f32 / hh as f32;
             let col = lerp_color(CEIL_TOP, CEIL_MID, t);
@@ -39,16 +154,8 @@ fn paint_ceiling_and_floor_textured(fb: &mut Framebuffer, texman: &TextureManage
             for x in 0..w { fb.set_pixel(x, y); }
         }
     }
-    if let Some((tw, th)) = texman.image_size('G') {
-        for y in hh..h {
-            let ty = (((y - hh) as u32) * th) / (h - hh);
-            for x in 0..w {
-                let tx = ((x as u32) * tw) / w;
-                let c = texman.get_pixel_color('G', tx.min(tw-1), ty.min(th-1));
-                fb.set_pixel_color(x, y, c);
-            }
-        }
-    } else {
+    let floor_dy = |y: u32| y as f32 + 0.5 - horizon;
+    if !cast(fb, 'G', hh..h, &floor_dy, FLOOR_FAR) {
         for y in hh..h {
             let t = (y - hh) as f32 / (h - hh) as f32;
             let col = lerp_color(FLOOR_FAR, FLOOR_NEAR, t);
@@ -58,6 +165,33 @@ fn paint_ceiling_and_floor_textured(fb: &mut Framebuffer, texman: &TextureManage
     }
 }
 
+// Horizon mist: a band of `MIST_BAND` × screen height on each side of the horizon row.
+const MIST_COLOR: Color = Color::new(120, 130, 145, 255);
+const MIST_BAND: f32 = 0.06;
+
+/// Blend an animated mist band over the rows around the horizon (only the sky/floor behind
+/// walls; walls are drawn afterwards). `amount` is the peak blend at the horizon, 0 = off.
+pub fn paint_horizon_mist(fb: &mut Framebuffer, time_sec: f32, amount: f32) {
+    let amount = amount.clamp(0.0, 1.0);
+    if amount <= 0.0 { return; }
+    let (w, h) = (fb.width, fb.height);
+    let hh = h as f32 * 0.5;
+    let band = (h as f32 * MIST_BAND).max(1.0);
+    let y_start = (hh - band).ceil().max(0.0) as u32;
+    let y_end = ((hh + band).floor() as u32).min(h.saturating_sub(1));
+    for y in y_start..=y_end {
+        let k = 1.0 - ((y as f32 - hh).abs() / band).min(1.0);
+        if k <= 0.0 { continue; }
+        for x in 0..w {
+            // Slow drifting wisps: two sines over x, phase-shifted per row
+            let drift = 0.75 + 0.25 * ((x as f32 * 0.045 + time_sec * 0.6 + y as f32 * 0.35).sin() * 0.6 + (x as f32 * 0.013 - time_sec * 0.35).sin() * 0.4);
+            let t = amount * k * k * drift;
+            let c = fb.get_pixel(x, y);
+            fb.set_pixel_color(x, y, lerp_color(c, MIST_COLOR, t));
+        }
+    }
+}
+
 pub fn render_3d(
     fb: &mut Framebuffer,
     maze: &Maze,
@@ -68,50 +202,57 @@ pub fn render_3d(
     time_sec: f32,
     panic_mode: bool,
     brightness: f32,
-) {
+    style: WallStyle,
+    ray_factor: u32,
+    mist: f32,
+    aniso: bool,
+    wrap: bool,
+    flat_below: f32,
+) -> usize {
     let w = fb.width as usize;
     let h = fb.height as f32;
     let hh = h * 0.5;
     let _ = (time_sec, panic_mode, brightness);
-    paint_ceiling_and_floor_textured(fb, texman);
+    paint_ceiling_and_floor_textured(fb, texman, player, block_size);
+    paint_horizon_mist(fb, time_sec, mist);
+    // `factor` rays per column at sub-column offsets; the nearest hit represents the column
+    // (keeps thin wall edges). Factor 1 is exactly one ray at the column's left edge.
+    let factor = ray_factor.clamp(1, MAX_RAY_FACTOR);
+    let mut rays = 0usize;
     for (i, z) in zbuffer.iter_mut().enumerate().take(w) {
-        let t = i as f32 / fb.width as f32;
-        let ray_a = player.a - (player.fov * 0.5) + (player.fov * t);
-        let d = cast_ray(fb, maze, player, ray_a, block_size, false);
-        *z = if d > 0.0 { d } else { f32::INFINITY };
-        if d <= 0.0 { continue; }
-
-        let diff = ray_a - player.a;
-        let d_world = d / diff.cos().abs().max(1e-4);
-        let hit_x = player.pos.x + ray_a.cos() * d_world;
-        let hit_y = player.pos.y + ray_a.sin() * d_world;
-        let ci = (hit_x / block_size as f32).floor() as isize;
-        let cj = (hit_y / block_size as f32).floor() as isize;
+        let mut best: Option<(RayHit, f32)> = None;
+        for k in 0..factor {
+            let t = (i as f32 + k as f32 / factor as f32) / fb.width as f32;
+            let a = player.a - (player.fov * 0.5) + (player.fov * t);
+            let hk = cast_ray_hit(fb, maze, player, a, block_size, wrap);
+            rays += 1;
+            if let Some(hk) = hk.filter(|hk| best.is_none_or(|(b, _)| hk.dist < b.dist)) { best = Some((hk, a)); }
+        }
+        let Some((hit, ray_a)) = best else { *z = f32::INFINITY; continue; };
+        *z = hit.dist;
+        let d = hit.dist;
+        let d_world = hit.ray_dist;
+        // Fold hits past an edge back in so the texture coordinate matches the wrapped wall
+        let (hit_x, hit_y) = if wrap { wrap_pos(maze, block_size as f32, hit.x, hit.y) } else { (hit.x, hit.y) };
+        let (ci, cj) = hit.cell;
         let mut is_exit_col = false;
         let mut wall_char = '#';
-        if cj >= 0 && ci >= 0 {
-            let (ci, cj) = (ci as usize, cj as usize);
-            if cj < maze.len() && ci < maze[cj].len() {
-                let ch = maze[cj][ci];
-                is_exit_col = ch == 'g';
-                wall_char = ch;
-            }
+        if let Some(ch) = cell_at(maze, ci, cj, wrap) {
+            is_exit_col = tiles().looks_like_exit(ch);
+            wall_char = ch;
         }
 
-        const PROJ_K: f32 = 120.0;
-        let mut col_h = (hh / d) * PROJ_K;
-        let gap: f32 = 12.0;
-        if col_h > gap * 2.0 { col_h -= gap * 2.0; }
+        let col_h = column_height(hh, d, block_size);
         let y0 = (hh - col_h * 0.5).max(0.0) as u32;
         let y1 = (hh + col_h * 0.5).min(h - 1.0) as u32;
         let x = i as u32;
 
         let tex_key: char = if is_exit_col {
-            'g'
+            tiles().tex_key(wall_char).unwrap_or(EXIT)
         } else {
-            match wall_char {
-                '1' | '2' | '3' | '4' => wall_char,
-                _ => {
+            match tiles().tex_key(wall_char) {
+                Some(k) => k,
+                None => {
                     let (ci, cj) = (ci.max(0) as usize, cj.max(0) as usize);
                     let h = (ci.wrapping_mul(31)) ^ (cj.wrapping_mul(17));
                     match h % 3 { 0 => '2', 1 => '3', _ => '4' }
@@ -119,20 +260,571 @@ pub fn render_3d(
             }
         };
 
+        match style {
+            WallStyle::Textured => {}
+            WallStyle::FlatColor => {
+                fb.set_current_color(TextureManager::color_from_char(tex_key));
+                for y in y0..=y1 { fb.set_pixel(x, y); }
+                continue;
+            }
+            WallStyle::Wireframe => {
+                fb.set_current_color(WIRE_COLOR);
+                fb.set_pixel(x, y0);
+                fb.set_pixel(x, y1);
+                continue;
+            }
+        }
+
         let (tw, th) = texman.image_size(tex_key).unwrap_or((64, 64));
         let fx = (hit_x / block_size as f32).fract().abs();
         let fy = (hit_y / block_size as f32).fract().abs();
-        let dist_fx = fx.min(1.0 - fx);
-        let dist_fy = fy.min(1.0 - fy);
-        let u = if dist_fx < dist_fy { fy } else { fx };
+        // A vertical grid line runs along y, so the texture follows y there (x on horizontal ones)
+        let u = if hit.side == HitSide::Vertical { fy } else { fx };
         let tx = (u * tw as f32).clamp(0.0, tw as f32 - 1.0) as u32;
+        let ao = corner_ao(u);
+        // Incidence: a vertical-line face points along x, a horizontal-line face along y
+        let cos_inc = if hit.side == HitSide::Vertical { ray_a.cos() } else { ray_a.sin() };
+        let taps = if aniso { aniso_taps(cos_inc) } else { 1 };
+        let mut tap_tx = [tx; MAX_ANISO_TAPS as usize];
+        if taps > 1 {
+            // U span of one screen column on this wall: lateral width at the hit, stretched by the angle
+            let span = (d_world * player.fov / fb.width as f32) / (block_size as f32 * cos_inc.abs().max(0.05));
+            for (k, t) in tap_tx.iter_mut().enumerate().take(taps as usize) {
+                let uk = (u + span * ((k as f32 + 0.5) / taps as f32 - 0.5)).rem_euclid(1.0);
+                *t = (uk * tw as f32).clamp(0.0, tw as f32 - 1.0) as u32;
+            }
+        }
+
+        // Distant column: one mid-texel color, fogged with distance, for the whole column
+        if column_is_flat((y1 - y0 + 1) as f32, flat_below) {
+            let fog = (d / (block_size as f32 * FLAT_FOG_CELLS)).clamp(0.0, 1.0);
+            let col = lerp_color(texman.get_pixel_color(tex_key, tx, th / 2), FLOOR_FAR, fog);
+            fb.set_current_color(if ao < 1.0 { scale_color(col, ao) } else { col });
+            for y in y0..=y1 { fb.set_pixel(x, y); }
+            continue;
+        }
 
         for y in y0..=y1 {
             let v = ((y - y0) as f32) / ((y1 - y0 + 1) as f32);
             let ty = (v * th as f32).clamp(0.0, th as f32 - 1.0) as u32;
-            let col = texman.get_pixel_color(tex_key, tx, ty);
-            fb.set_current_color(col);
+            let col = if taps > 1 {
+                let mut cs = [Color::BLACK; MAX_ANISO_TAPS as usize];
+                for (c, &t) in cs.iter_mut().zip(tap_tx.iter()).take(taps as usize) { *c = texman.get_pixel_color(tex_key, t, ty); }
+                average_colors(&cs[..taps as usize])
+            } else {
+                texman.get_pixel_color(tex_key, tx, ty)
+            };
+            fb.set_current_color(if ao < 1.0 { scale_color(col, ao) } else { col });
             fb.set_pixel(x, y);
         }
     }
+    rays
+}
+
+/// Project a point on the ground plane to framebuffer coordinates using the same
+/// column/height model as the walls. `None` if it's behind the camera or outside the FOV.
+pub fn project_ground_point(fb_w: f32, fb_h: f32, player: &Player, block_size: usize, wx: f32, wy: f32) -> Option<(f32, f32)> {
+    let dx = wx - player.pos.x;
+    let dy = wy - player.pos.y;
+    let mut diff = dy.atan2(dx) - player.a;
+    while diff >  std::f32::consts::PI { diff -= 2.0*std::f32::consts::PI; }
+    while diff < -std::f32::consts::PI { diff += 2.0*std::f32::consts::PI; }
+    if diff.abs() > player.fov * 0.5 { return None; }
+    let d = (dx*dx + dy*dy).sqrt() * diff.cos();
+    if d < 1.0 { return None; }
+    let hh = fb_h * 0.5;
+    let sx = ((diff / player.fov) + 0.5) * fb_w;
+    let sy = hh + (hh / d) * projection_k(block_size) * 0.5;
+    Some((sx, sy))
+}
+
+/// Depth of world point `(wx, wy)` along the view axis: the fish-eye corrected distance that
+/// `render_3d` stores in the zbuffer.
+#[inline]
+fn view_depth(player: &Player, wx: f32, wy: f32) -> f32 {
+    (wx - player.pos.x) * player.a.cos() + (wy - player.pos.y) * player.a.sin()
+}
+
+/// Plot a ring of ground points of world radius `radius` around `(cx, cy)`, occluded by walls.
+pub fn draw_ground_ring(
+    fb: &mut Framebuffer,
+    player: &Player,
+    block_size: usize,
+    zbuffer: &[f32],
+    cx: f32,
+    cy: f32,
+    radius: f32,
+    color: Color,
+) {
+    let (w, h) = (fb.width as f32, fb.height as f32);
+    let samples = 48;
+    fb.set_current_color(color);
+    for k in 0..samples {
+        let ang = (k as f32) * (std::f32::consts::TAU / samples as f32);
+        let (wx, wy) = (cx + radius * ang.cos(), cy + radius * ang.sin());
+        if let Some((sx, sy)) = project_ground_point(w, h, player, block_size, wx, wy) {
+            if sx < 0.0 || sy < 0.0 || sx >= w || sy >= h { continue; }
+            let col = sx as usize;
+            if col < zbuffer.len() && view_depth(player, wx, wy) >= zbuffer[col] { continue; }
+            fb.set_pixel(sx as u32, sy as u32);
+            fb.set_pixel(sx as u32 + 1, sy as u32);
+        }
+    }
+}
+
+/// Half-width (cells) of the ground shadow ellipse drawn by `draw_ground_shadow`.
+pub const SHADOW_RADIUS_CELLS: f32 = 0.22;
+/// How far (cells) the ground shadow is stretched away from the player.
+pub const SHADOW_LENGTH_CELLS: f32 = 0.5;
+
+/// Darken the floor under a sprite standing at `(cx, cy)` with a crude directional shadow: an
+/// ellipse `SHADOW_RADIUS_CELLS` wide stretched `SHADOW_LENGTH_CELLS` away from the player. Each
+/// floor pixel below the horizon is projected back onto the ground; pixels where a wall column
+/// is nearer than that ground point (zbuffer) are left alone. `strength` 0..1 is how dark.
+pub fn draw_ground_shadow(
+    fb: &mut Framebuffer,
+    player: &Player,
+    block_size: usize,
+    zbuffer: &[f32],
+    cx: f32,
+    cy: f32,
+    strength: f32,
+) {
+    let k = strength.clamp(0.0, 1.0);
+    if k <= 0.0 { return; }
+    let (radius, length) = (SHADOW_RADIUS_CELLS * block_size as f32, SHADOW_LENGTH_CELLS * block_size as f32);
+    let (w, h) = (fb.width as f32, fb.height as f32);
+    let hh = h * 0.5;
+    let dist = ((cx - player.pos.x).powi(2) + (cy - player.pos.y).powi(2)).sqrt().max(1e-3);
+    let away = ((cx - player.pos.x) / dist, (cy - player.pos.y) / dist);
+    // Ellipse: centre pushed away from the player, long axis along `away`
+    let (ex, ey) = (cx + away.0 * length * 0.5, cy + away.1 * length * 0.5);
+    let (ra, rb) = (radius + length * 0.5, radius);
+    // Screen bounds from points around the ellipse; any point off-view widens to the full width
+    let (mut x0, mut x1, mut y0, mut y1) = (w, 0.0f32, h, hh);
+    let mut clipped = false;
+    for s in 0..16 {
+        let t = s as f32 * (std::f32::consts::TAU / 16.0);
+        let (u, v) = (ra * t.cos(), rb * t.sin());
+        let (wx, wy) = (ex + away.0 * u - away.1 * v, ey + away.1 * u + away.0 * v);
+        match project_ground_point(w, h, player, block_size, wx, wy) {
+            Some((sx, sy)) => { x0 = x0.min(sx); x1 = x1.max(sx); y0 = y0.min(sy); y1 = y1.max(sy); }
+            None => clipped = true,
+        }
+    }
+    if clipped { x0 = 0.0; x1 = w - 1.0; y1 = h - 1.0; }
+    if x0 > x1 || y0 > y1 { return; }
+    let proj_k = projection_k(block_size);
+    for sy in (y0.max(hh + 1.0) as u32)..=(y1.min(h - 1.0) as u32) {
+        // Perpendicular ground distance seen by this row (inverse of `project_ground_point`)
+        let d = hh * proj_k * 0.5 / (sy as f32 - hh);
+        for sx in (x0.max(0.0) as u32)..=(x1.min(w - 1.0) as u32) {
+            if (sx as usize) < zbuffer.len() && d >= zbuffer[sx as usize] { continue; }
+            let diff = (sx as f32 / w - 0.5) * player.fov;
+            let (a, r) = (player.a + diff, d / diff.cos().max(1e-3));
+            let (rx, ry) = (player.pos.x + a.cos() * r - ex, player.pos.y + a.sin() * r - ey);
+            let (u, v) = (rx * away.0 + ry * away.1, -rx * away.1 + ry * away.0);
+            let e = (u / ra).powi(2) + (v / rb).powi(2);
+            if e > 1.0 { continue; }
+            // Softer towards the rim
+            let c = fb.get_pixel(sx, sy);
+            fb.set_pixel_color(sx, sy, scale_color(c, 1.0 - k * (1.0 - e)));
+        }
+    }
+}
+
+/// Half width (cells) of the exit light beam.
+pub const BEAM_RADIUS_CELLS: f32 = 0.3;
+/// How far the beam's core is blended toward its color (0..1).
+const BEAM_STRENGTH: f32 = 0.75;
+/// Slack (cells) so the beam stays visible over the face of the cell it rises from.
+const BEAM_DEPTH_SLACK_CELLS: f32 = 0.75;
+
+/// Vertical light pillar rising from ground point `(cx, cy)` to the top of the view, brightest
+/// at its core and towards the floor. Walls nearer than the beam's cell hide it (`zbuffer`).
+pub fn draw_light_beam(
+    fb: &mut Framebuffer,
+    player: &Player,
+    block_size: usize,
+    zbuffer: &[f32],
+    cx: f32,
+    cy: f32,
+    color: Color,
+) {
+    let (w, h) = (fb.width as f32, fb.height as f32);
+    let Some((sx, floor_y)) = project_ground_point(w, h, player, block_size, cx, cy) else { return; };
+    let block = block_size as f32;
+    let (dx, dy) = (cx - player.pos.x, cy - player.pos.y);
+    let dist = (dx*dx + dy*dy).sqrt().max(1.0);
+    let d = dist * (dy.atan2(dx) - player.a).cos();
+    let half = ((BEAM_RADIUS_CELLS * block / dist).atan() / player.fov * w).max(1.0);
+    let y1 = floor_y.min(h - 1.0).max(0.0) as u32;
+    for x in ((sx - half).max(0.0) as u32)..=((sx + half).min(w - 1.0) as u32) {
+        if (x as usize) < zbuffer.len() && d - BEAM_DEPTH_SLACK_CELLS * block >= zbuffer[x as usize] { continue; }
+        let u = ((x as f32 - sx) / half).abs();
+        if u >= 1.0 { continue; }
+        let core = 1.0 - u * u;
+        for y in 0..=y1 {
+            // Fades upwards from the floor it rises from
+            let k = BEAM_STRENGTH * core * (0.35 + 0.65 * y as f32 / y1.max(1) as f32);
+            let c = fb.get_pixel(x, y);
+            fb.set_pixel_color(x, y, lerp_color(c, color, k));
+        }
+    }
+}
+
+/// Gray level for a wall at perpendicular distance `d`: 255 right at the camera, 0 at `max_dist` or beyond.
+#[inline]
+pub fn depth_shade(d: f32, max_dist: f32) -> u8 {
+    if !d.is_finite() || max_dist <= 0.0 { return 0; }
+    ((1.0 - d / max_dist).clamp(0.0, 1.0) * 255.0) as u8
+}
+
+/// Debug post-pass: repaint the frame as a grayscale depth map from `zbuffer` (near = white,
+/// far = black). Walls use the same column height as `render_3d`, so projection bugs show up.
+pub fn draw_depth_view(fb: &mut Framebuffer, zbuffer: &[f32], block_size: usize, max_dist: f32) {
+    let w = fb.width as usize;
+    let h = fb.height as f32;
+    let hh = h * 0.5;
+    fb.set_current_color(Color::BLACK);
+    for y in 0..fb.height {
+        for x in 0..fb.width { fb.set_pixel(x, y); }
+    }
+    for (i, &d) in zbuffer.iter().enumerate().take(w) {
+        if !d.is_finite() || d <= 0.0 { continue; }
+        let g = depth_shade(d, max_dist);
+        let col_h = column_height(hh, d, block_size);
+        let y0 = (hh - col_h * 0.5).max(0.0) as u32;
+        let y1 = (hh + col_h * 0.5).min(h - 1.0) as u32;
+        fb.set_current_color(Color::new(g, g, g, 255));
+        for y in y0..=y1 { fb.set_pixel(i as u32, y); }
+    }
+}
+
+/// Mark trap tiles on the floor: an outline and an X inside each trap cell within
+/// `max_cells` of the player, occluded by walls through `zbuffer`.
+pub fn draw_trap_markers(fb: &mut Framebuffer, maze: &Maze, player: &Player, block_size: usize, zbuffer: &[f32], max_cells: f32, color: Color) {
+    let (w, h) = (fb.width as f32, fb.height as f32);
+    let block = block_size as f32;
+    let samples = 16;
+    fb.set_current_color(color);
+    for (j, row) in maze.iter().enumerate() {
+        for (i, &c) in row.iter().enumerate() {
+            if !tiles().is_hazard(c) { continue; }
+            let (x0, y0) = (i as f32 * block + block * 0.1, j as f32 * block + block * 0.1);
+            let (x1, y1) = (x0 + block * 0.8, y0 + block * 0.8);
+            let (mx, my) = ((x0 + x1) * 0.5 - player.pos.x, (y0 + y1) * 0.5 - player.pos.y);
+            if mx*mx + my*my > (max_cells * block).powi(2) { continue; }
+            let edges = [((x0, y0), (x1, y0)), ((x1, y0), (x1, y1)), ((x1, y1), (x0, y1)), ((x0, y1), (x0, y0)), ((x0, y0), (x1, y1)), ((x1, y0), (x0, y1))];
+            for ((ax, ay), (bx, by)) in edges {
+                for k in 0..=samples {
+                    let t = k as f32 / samples as f32;
+                    let (wx, wy) = (ax + (bx - ax) * t, ay + (by - ay) * t);
+                    if let Some((sx, sy)) = project_ground_point(w, h, player, block_size, wx, wy) {
+                        if sx < 0.0 || sy < 0.0 || sx >= w || sy >= h { continue; }
+                        let col = sx as usize;
+                        if col < zbuffer.len() && view_depth(player, wx, wy) >= zbuffer[col] { continue; }
+                        fb.set_pixel(sx as u32, sy as u32);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wall_height_depends_on_distance_in_cells_not_block_size() {
+        let hh = 300.0;
+        let reference = column_height(hh, 3.0 * 64.0, 64);
+        for block in [16usize, 32, 48, 80, 128] {
+            let h = column_height(hh, 3.0 * block as f32, block);
+            assert!((h - reference).abs() < 1e-3, "block {}: {} vs {}", block, h, reference);
+        }
+    }
+
+    #[test]
+    fn ground_points_project_to_the_same_row_for_any_block_size() {
+        let row = |block: usize| {
+            let b = block as f32;
+            let p = Player::new(1.5 * b, 1.5 * b, 0.0);
+            project_ground_point(640.0, 480.0, &p, block, 4.5 * b, 1.5 * b).unwrap()
+        };
+        let (sx, sy) = row(64);
+        for block in [32usize, 80, 100] {
+            let (x, y) = row(block);
+            assert!((x - sx).abs() < 1e-3 && (y - sy).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn row_distance_inverts_the_column_height() {
+        let hh = 240.0;
+        for block in [32usize, 64, 80] {
+            for cells in [0.5f32, 1.0, 2.5, 7.0, 20.0] {
+                let d = cells * block as f32;
+                let base = column_height(hh, d, block) * 0.5;
+                let back = row_distance(hh, base, block);
+                assert!((back - d).abs() < d * 1e-4, "block {}: {} vs {}", block, back, d);
+            }
+        }
+    }
+
+    #[test]
+    fn wall_base_meets_the_floor_and_ground_sprites() {
+        // Wall face 3 cells ahead: its bottom row, the floor row at that distance and a ground
+        // point on the face all land on the same screen row
+        let (w, h, block) = (640.0, 480.0, 64usize);
+        let b = block as f32;
+        let p = Player::new(1.5 * b, 1.5 * b, 0.0);
+        let d = 3.0 * b;
+        let bottom = h * 0.5 + column_height(h * 0.5, d, block) * 0.5;
+        let (_, ground) = project_ground_point(w, h, &p, block, p.pos.x + d, p.pos.y).unwrap();
+        assert!((bottom - ground).abs() < 1e-3);
+        assert!((row_distance(h * 0.5, bottom - h * 0.5, block) - d).abs() < 1e-2);
+    }
+
+    #[test]
+    fn ground_ring_points_land_on_screen_below_the_horizon() {
+        let (w, h) = (320.0, 240.0);
+        let p = Player::new(96.0, 96.0, 0.0);
+        let at = |bearing: f32| project_ground_point(w, h, &p, 64, 96.0 + 200.0 * bearing.cos(), 96.0 + 200.0 * bearing.sin());
+        let (cx, cy) = at(0.0).unwrap();
+        assert!((cx - w * 0.5).abs() < 1e-3 && cy > h * 0.5 && cy < h);
+        let (lx, ly) = at(-0.5).unwrap();
+        let (rx, ry) = at(0.5).unwrap();
+        assert!(lx >= 0.0 && lx < cx && rx > cx && rx < w);
+        // Off-axis points at the same range are nearer in perpendicular distance: lower on screen
+        assert!((ly - ry).abs() < 1e-3 && ly > cy);
+        assert_eq!(at(std::f32::consts::PI), None);
+        assert_eq!(at(1.0), None);
+    }
+
+    #[test]
+    fn ground_ring_draws_only_below_the_horizon() {
+        let mut fb = Framebuffer::new(160, 120);
+        let p = Player::new(96.0, 96.0, 0.0);
+        let zbuffer = vec![f32::INFINITY; 160];
+        draw_ground_ring(&mut fb, &p, 64, &zbuffer, 96.0 + 192.0, 96.0, 32.0, Color::RED);
+        let lit: Vec<usize> = fb.color_buffer.iter().enumerate().filter(|(_, c)| **c == Color::RED).map(|(i, _)| i / 160).collect();
+        assert!(!lit.is_empty());
+        assert!(lit.iter().all(|&y| y > 60));
+        // A wall in front hides the whole ring
+        let mut fb = Framebuffer::new(160, 120);
+        draw_ground_ring(&mut fb, &p, 64, &[50.0; 160], 96.0 + 192.0, 96.0, 32.0, Color::RED);
+        assert!(!fb.color_buffer.contains(&Color::RED));
+    }
+
+    #[test]
+    fn nearer_walls_are_brighter_in_the_depth_view() {
+        let mut fb = Framebuffer::new(4, 60);
+        let zbuffer = [100.0, 400.0, f32::INFINITY, 900.0];
+        draw_depth_view(&mut fb, &zbuffer, 64, 1000.0);
+        let mid = |x: u32| fb.get_pixel(x, 30).r;
+        assert!(mid(0) > mid(1) && mid(1) > mid(3));
+        assert_eq!(mid(2), 0, "no hit stays black");
+        assert_eq!((depth_shade(0.0, 1000.0), depth_shade(2000.0, 1000.0)), (255, 0));
+    }
+
+
+    fn walled_room() -> Maze {
+        ["+-----+", "|     |", "|     |", "|     |", "+-----+"].iter().map(|r| r.chars().collect()).collect()
+    }
+
+    // Facing a wall 3 cells east, rendered into a small frame; also returns the rays cast
+    fn draw(style: WallStyle, ray_factor: u32) -> (Framebuffer, Vec<f32>, usize) {
+        let p = Player::new(2.5 * 64.0, 2.5 * 64.0, 0.0);
+        let mut fb = Framebuffer::new(64, 48);
+        let mut z = vec![0.0; 64];
+        let tm = TextureManager::procedural();
+        let rays = render_3d(&mut fb, &walled_room(), 64, &p, &tm, &mut z, 0.0, false, 1.0, style, ray_factor, 0.0, false, false, 0.0);
+        (fb, z, rays)
+    }
+
+    fn draw_style(style: WallStyle) -> (Framebuffer, Vec<f32>) {
+        let (fb, z, _) = draw(style, 1);
+        (fb, z)
+    }
+
+    fn wall_rows(fb: &Framebuffer, d: f32) -> (u32, u32) {
+        let hh = fb.height as f32 * 0.5;
+        let col_h = column_height(hh, d, 64);
+        ((hh - col_h * 0.5).max(0.0) as u32, (hh + col_h * 0.5).min(fb.height as f32 - 1.0) as u32)
+    }
+
+    #[test]
+    fn flat_color_fills_the_wall_column_with_one_color() {
+        let (fb, z) = draw_style(WallStyle::FlatColor);
+        let (y0, y1) = wall_rows(&fb, z[32]);
+        assert!(y1 > y0 + 2);
+        let c = fb.get_pixel(32, y0);
+        assert!((y0..=y1).all(|y| fb.get_pixel(32, y) == c));
+        // Floor and ceiling around it are still painted
+        assert_ne!(fb.get_pixel(32, fb.height - 1), c);
+    }
+
+    #[test]
+    fn wireframe_only_writes_the_edge_rows() {
+        let (fb, z) = draw_style(WallStyle::Wireframe);
+        let (y0, y1) = wall_rows(&fb, z[32]);
+        let wire: Vec<u32> = (0..fb.height).filter(|&y| fb.get_pixel(32, y) == WIRE_COLOR).collect();
+        assert_eq!(wire, vec![y0, y1]);
+    }
+
+    #[test]
+    fn ray_factor_one_casts_one_ray_per_column_at_its_left_edge() {
+        let (_, z, rays) = draw(WallStyle::Textured, 1);
+        assert_eq!(rays, 64);
+        let p = Player::new(2.5 * 64.0, 2.5 * 64.0, 0.0);
+        for (i, &d) in z.iter().enumerate() {
+            let a = p.a - p.fov * 0.5 + p.fov * (i as f32 / 64.0);
+            let hit = cast_ray_hit(&mut Framebuffer::new(1, 1), &walled_room(), &p, a, 64, false).unwrap();
+            assert_eq!(d, hit.dist, "column {}", i);
+        }
+    }
+
+    #[test]
+    fn ray_factor_multiplies_the_rays_and_is_capped() {
+        assert_eq!(draw(WallStyle::Textured, 2).2, 128);
+        assert_eq!(draw(WallStyle::Textured, 0).2, 64);
+        assert_eq!(draw(WallStyle::Textured, 99).2, 64 * MAX_RAY_FACTOR as usize);
+        // Every column keeps a finite depth either way
+        assert!(draw(WallStyle::Textured, 2).1.iter().all(|d| d.is_finite()));
+    }
+
+    #[test]
+    fn wall_corners_are_darker_than_the_middle() {
+        assert_eq!(corner_ao(0.5), 1.0);
+        assert!(corner_ao(0.0) < corner_ao(0.5));
+        assert!(corner_ao(1.0) < corner_ao(0.5));
+        assert!((corner_ao(0.0) - (1.0 - AO_STRENGTH)).abs() < 1e-6);
+        // Symmetric, and fading in towards the edge
+        for u in [0.01f32, 0.03, 0.07] {
+            assert!((corner_ao(u) - corner_ao(1.0 - u)).abs() < 1e-5);
+        }
+        assert!(corner_ao(0.01) < corner_ao(0.03));
+        assert_eq!(corner_ao(AO_WIDTH), 1.0);
+    }
+
+    #[test]
+    fn mist_only_touches_rows_near_the_horizon() {
+        let black = || { let mut fb = Framebuffer::new(32, 100); fb.set_background_color(Color::BLACK); fb.clear(); fb };
+        let mut fb = black();
+        paint_horizon_mist(&mut fb, 1.3, 0.8);
+        let changed: Vec<u32> = (0..fb.height).filter(|&y| (0..fb.width).any(|x| fb.get_pixel(x, y) != Color::BLACK)).collect();
+        assert!(changed.contains(&50));
+        // Band of 6% of the height on each side
+        assert!(changed.iter().all(|&y| (44..=56).contains(&y)), "{:?}", changed);
+        assert_eq!(fb.get_pixel(5, 0), Color::BLACK);
+        assert_eq!(fb.get_pixel(5, 99), Color::BLACK);
+        // Off leaves the frame alone
+        let mut off = black();
+        paint_horizon_mist(&mut off, 1.3, 0.0);
+        assert!((0..100).all(|y| off.get_pixel(3, y) == Color::BLACK));
+    }
+
+    #[test]
+    fn grazing_columns_take_more_texel_taps() {
+        // Head-on (either facing) takes one tap
+        assert_eq!(aniso_taps(1.0), 1);
+        assert_eq!(aniso_taps(-0.9), 1);
+        assert_eq!(aniso_taps(ANISO_GRAZING_COS), 1);
+        // Shallower angles take more, up to the cap
+        let t = |deg: f32| aniso_taps(deg.to_radians().cos());
+        assert!(t(80.0) >= 2);
+        assert!(t(80.0) <= t(86.0));
+        assert_eq!(t(89.9), MAX_ANISO_TAPS);
+        assert_eq!(aniso_taps(0.0), MAX_ANISO_TAPS);
+    }
+
+    #[test]
+    fn taps_are_averaged_per_channel() {
+        let c = average_colors(&[Color::new(0, 100, 200, 255), Color::new(100, 200, 0, 255)]);
+        assert_eq!((c.r, c.g, c.b, c.a), (50, 150, 100, 255));
+        assert_eq!(average_colors(&[Color::RED]), Color::RED);
+    }
+
+    #[test]
+    fn short_columns_are_one_color_and_tall_ones_textured() {
+        let p = Player::new(2.5 * 64.0, 2.5 * 64.0, 0.0);
+        let tm = TextureManager::procedural();
+        let column = |flat_below: f32| {
+            let (mut fb, mut z) = (Framebuffer::new(64, 48), vec![0.0; 64]);
+            render_3d(&mut fb, &walled_room(), 64, &p, &tm, &mut z, 0.0, false, 1.0, WallStyle::Textured, 1, 0.0, false, false, flat_below);
+            let (y0, y1) = wall_rows(&fb, z[32]);
+            let mut colors: Vec<Color> = (y0..=y1).map(|y| fb.get_pixel(32, y)).collect();
+            colors.dedup();
+            (y1 - y0 + 1, colors.len())
+        };
+        let (col_px, textured) = column(0.0);
+        assert!(textured > 1);
+        // Same column under a threshold above its height: a single color
+        assert_eq!(column(col_px as f32 + 1.0).1, 1);
+        assert_eq!(column(col_px as f32).1, textured);
+        assert!(column_is_flat(5.0, FLAT_COLUMN_PX) && !column_is_flat(FLAT_COLUMN_PX, FLAT_COLUMN_PX));
+    }
+
+    #[test]
+    fn ground_shadow_stops_at_nearer_walls() {
+        let p = Player::new(2.5 * 64.0, 2.5 * 64.0, 0.0);
+        // Shadow three cells ahead on a white frame; returns the darkened pixels per column
+        let shade = |z: &[f32]| {
+            let mut fb = Framebuffer::new(64, 48);
+            fb.set_background_color(Color::WHITE);
+            fb.clear();
+            draw_ground_shadow(&mut fb, &p, 64, z, 5.5 * 64.0, 2.5 * 64.0, 0.8);
+            // Only floor rows (below the horizon) are touched
+            assert!((0..64).all(|x| (0..=24).all(|y| fb.get_pixel(x, y) == Color::WHITE)));
+            (0..64u32).map(|x| (0..48).filter(|&y| fb.get_pixel(x, y) != Color::WHITE).count()).collect::<Vec<_>>()
+        };
+        let open = shade(&[f32::INFINITY; 64]);
+        assert!(open[..32].iter().any(|&n| n > 0) && open[32..].iter().any(|&n| n > 0));
+        // A wall one cell away over the right half hides the shadow there only
+        let z: Vec<f32> = (0..64).map(|x| if x >= 32 { 64.0 } else { f32::INFINITY }).collect();
+        let walled = shade(&z);
+        assert_eq!(walled[..32], open[..32]);
+        assert!(walled[32..].iter().all(|&n| n == 0));
+    }
+
+    #[test]
+    fn ground_ring_in_front_of_a_wall_at_the_view_edge_is_drawn() {
+        let p = Player::new(0.0, 0.0, 0.0);
+        // Ring near the edge of the view; a wall right behind its farthest point in every column
+        let a = p.fov * 0.4;
+        let (cx, cy, r) = (200.0 * a.cos(), 200.0 * a.sin(), 8.0);
+        let ring = |z: f32| {
+            let mut fb = Framebuffer::new(64, 48);
+            fb.set_background_color(Color::BLACK);
+            fb.clear();
+            draw_ground_ring(&mut fb, &p, 64, &[z; 64], cx, cy, r, Color::WHITE);
+            fb.color_buffer.iter().filter(|&&c| c == Color::WHITE).count()
+        };
+        let behind = view_depth(&p, cx, cy) + r + 1.0;
+        // Euclidean distances there exceed the wall depth; only the view-axis depth is in front
+        assert!((cx * cx + cy * cy).sqrt() - r > behind);
+        assert!(ring(f32::INFINITY) > 0);
+        assert_eq!(ring(behind), ring(f32::INFINITY));
+        assert_eq!(ring(view_depth(&p, cx, cy) - r - 1.0), 0);
+    }
+
+    #[test]
+    fn trap_marker_in_front_of_a_wall_off_axis_is_drawn() {
+        let mut maze: Maze = vec![vec![' '; 8]; 6];
+        maze[3][4] = crate::core::maze::TRAP;
+        let p = Player::new(0.5 * 64.0, 1.5 * 64.0, 0.0);
+        let markers = |z: f32| {
+            let mut fb = Framebuffer::new(64, 48);
+            fb.set_background_color(Color::BLACK);
+            fb.clear();
+            draw_trap_markers(&mut fb, &maze, &p, 64, &[z; 64], 8.0, Color::WHITE);
+            fb.color_buffer.iter().filter(|&&c| c == Color::WHITE).count()
+        };
+        // A wall just past the trap's far edge: off-axis points are farther than it in a straight line
+        let behind = view_depth(&p, 4.9 * 64.0, 3.9 * 64.0) + 1.0;
+        assert!(markers(f32::INFINITY) > 0);
+        assert_eq!(markers(behind), markers(f32::INFINITY));
+    }
 }
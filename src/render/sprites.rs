@@ -2,12 +2,31 @@
 //!
 //! Exposes:
 //! - `draw_sprite_world`: draw a single billboard sprite with z-buffer
-//! - `draw_sprites_sorted`: sort by distance and draw many sprites
+//! - `draw_sprites_sorted`: sort by distance and draw many sprites (optionally only the N nearest)
 //!
+use raylib::prelude::Color;
 use crate::render::framebuffer::Framebuffer;
 use crate::core::player::Player;
 use crate::render::textures::TextureManager;
 
+/// Multiply a texel by a tint (white = unchanged); alpha is kept from the texel.
+#[inline]
+pub fn tint_color(c: Color, tint: Color) -> Color {
+    let m = |a: u8, b: u8| -> u8 { ((a as u16 * b as u16 + 127) / 255) as u8 };
+    Color::new(m(c.r, tint.r), m(c.g, tint.g), m(c.b, tint.b), c.a)
+}
+
+/// Extra screen pixels kept around the view before a sprite counts as off-screen.
+pub const CULL_MARGIN_PX: f32 = 2.0;
+
+/// True while any part of a sprite `sprite_w` px wide, centred `angle_diff` rad off the view
+/// axis, can land on a `screen_w` px wide view spanning `fov` rad (screen x is linear in angle).
+#[inline]
+pub fn sprite_in_view(angle_diff: f32, sprite_w: f32, screen_w: f32, fov: f32) -> bool {
+    let half_angle = (sprite_w * 0.5 + CULL_MARGIN_PX) / screen_w * fov;
+    angle_diff.abs() <= fov * 0.5 + half_angle
+}
+
 pub fn draw_sprite_world(
     framebuffer: &mut Framebuffer,
     player: &Player,
@@ -18,6 +37,8 @@ pub fn draw_sprite_world(
     key: char,
     size_factor: f32,
     v_offset: f32,
+    tint: Color,
+    squash: f32,
 ) {
     let sw = framebuffer.width as f32;
     let sh = framebuffer.height as f32;
@@ -27,7 +48,6 @@ pub fn draw_sprite_world(
     let mut angle_diff = sprite_a - player.a;
     while angle_diff >  std::f32::consts::PI { angle_diff -= 2.0*std::f32::consts::PI; }
     while angle_diff < -std::f32::consts::PI { angle_diff += 2.0*std::f32::consts::PI; }
-    if angle_diff.abs() > player.fov * 0.55 { return; }
     let dist = (dx*dx + dy*dy).sqrt();
     if dist < 8.0 || dist > 2500.0 { return; }
     let screen_x = ((angle_diff / player.fov) + 0.5) * sw;
@@ -36,39 +56,137 @@ pub fn draw_sprite_world(
     let max_px = if is_enemy_face { sh * 0.90 } else { sh * 0.42 };
     if sprite_size > max_px { sprite_size = max_px; }
     if sprite_size <= 1.0 { return; }
+    // Cull only once the whole (squashed) width is past the screen edge: no pop-in for wide sprites
+    if !sprite_in_view(angle_diff, sprite_size / squash, sw, player.fov) { return; }
     let mut center_y = sh * (0.5 + v_offset);
     if is_enemy_face && dist < 140.0 { center_y += (3.0 * ((dist * 0.05).sin())).round(); }
-    let start_x = (screen_x - sprite_size * 0.5).max(0.0) as i32;
-    let end_x   = (screen_x + sprite_size * 0.5).min(sw - 1.0) as i32;
-    let start_y = (center_y - sprite_size * 0.5).max(0.0) as i32;
-    let end_y   = (start_y as f32 + sprite_size).min(sh - 1.0) as i32;
+    // Walk-cycle squash/stretch: height scales by `squash`, width by its inverse, feet stay put
+    let (sprite_w, sprite_h) = (sprite_size / squash, sprite_size * squash);
+    let bottom = center_y + sprite_size * 0.5;
+    // Unclamped sprite rect; texels are mapped from it (sub-pixel) so clipping doesn't squash the image
+    let left = screen_x - sprite_w * 0.5;
+    let top  = bottom - sprite_h;
+    let start_x = left.max(0.0) as i32;
+    let end_x   = (screen_x + sprite_w * 0.5).min(sw - 1.0) as i32;
+    let start_y = top.max(0.0) as i32;
+    let end_y   = bottom.min(sh - 1.0) as i32;
     let (tex_w, tex_h) = texman.image_size(key).unwrap_or((64, 64));
+    let (tw, th) = (tex_w as f32, tex_h as f32);
     for sx in start_x..=end_x {
         if (sx as usize) < zbuffer.len() && dist >= zbuffer[sx as usize] { continue; }
-        let tx = (((sx - start_x) as f32) / (end_x - start_x + 1) as f32 * tex_w as f32) as u32;
+        let u = (((sx as f32 + 0.5) - left) / sprite_w * tw).clamp(0.0, tw - 0.001);
         for sy in start_y..=end_y {
-            let ty = (((sy - start_y) as f32) / (end_y - start_y + 1) as f32 * tex_h as f32) as u32;
-            let color = texman.get_pixel_color(key, tx, ty);
+            let v = (((sy as f32 + 0.5) - top) / sprite_h * th).clamp(0.0, th - 0.001);
+            let color = texman.sample_color(key, u, v);
             if color.a < 8 { continue; }
-            framebuffer.set_current_color(color);
+            framebuffer.set_current_color(tint_color(color, tint));
             framebuffer.set_pixel(sx as u32, sy as u32);
+            framebuffer.stats.sprite_pixels += 1;
         }
     }
 }
 
+/// Sorts `sprites` far-to-near and draws them; with `max_drawn = Some(n)` only the `n` nearest
+/// are rasterized (the far tail is skipped). Returns how many were drawn.
 pub fn draw_sprites_sorted(
     framebuffer: &mut Framebuffer,
     player: &Player,
     texman: &TextureManager,
     zbuffer: &[f32],
-    sprites: &mut [(&str, f32, f32, char, f32, f32)],
-) {
+    sprites: &mut [(&str, f32, f32, char, f32, f32, Color, f32)],
+    max_drawn: Option<usize>,
+) -> usize {
+    // Equal distances fall back to world position so overlapping sprites keep one draw order
+    // from frame to frame (no flicker); `total_cmp` also gives NaN a fixed place.
     sprites.sort_by(|a, b| {
         let da = (a.1 - player.pos.x).powi(2) + (a.2 - player.pos.y).powi(2);
         let db = (b.1 - player.pos.x).powi(2) + (b.2 - player.pos.y).powi(2);
-        db.partial_cmp(&da).unwrap_or(std::cmp::Ordering::Equal)
+        db.total_cmp(&da).then(a.1.total_cmp(&b.1)).then(a.2.total_cmp(&b.2))
     });
-    for (_id, x, y, key, size, v_off) in sprites.iter().copied() {
-        draw_sprite_world(framebuffer, player, texman, zbuffer, x, y, key, size, v_off);
+    let skip = max_drawn.map_or(0, |n| sprites.len().saturating_sub(n));
+    for (_id, x, y, key, size, v_off, tint, squash) in sprites[skip..].iter().copied() {
+        draw_sprite_world(framebuffer, player, texman, zbuffer, x, y, key, size, v_off, tint, squash);
+    }
+    sprites.len() - skip
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tint_multiplies_the_texel() {
+        let red = Color::new(255, 0, 0, 255);
+        assert_eq!(tint_color(Color::WHITE, red), red);
+        let texel = Color::new(200, 120, 40, 90);
+        assert_eq!(tint_color(texel, Color::WHITE), texel);
+        // Alpha always comes from the texel
+        assert_eq!(tint_color(texel, Color::new(255, 255, 255, 0)).a, 90);
+        assert_eq!(tint_color(Color::new(200, 100, 50, 255), Color::new(128, 128, 128, 255)), Color::new(100, 50, 25, 255));
+    }
+
+    #[test]
+    fn a_cap_draws_only_the_nearest_sprites() {
+        let tm = TextureManager::procedural();
+        let p = Player::new(0.0, 0.0, 0.0);
+        // Five sprites fanned across the view, distance growing with the index
+        let place = |k: usize| {
+            let (d, a) = (100.0 * (k + 1) as f32, -0.4 + 0.2 * k as f32);
+            ("orb", d * a.cos(), d * a.sin(), 'g', 64.0, 0.0, Color::WHITE, 1.0)
+        };
+        let column = |k: usize| (((-0.4 + 0.2 * k as f32) / p.fov + 0.5) * 200.0) as u32;
+        let drawn_with = |cap: Option<usize>| {
+            let mut fb = Framebuffer::new(200, 100);
+            let bg = fb.get_pixel(0, 0);
+            let mut sprites: Vec<_> = (0..5).rev().map(place).collect();
+            let n = draw_sprites_sorted(&mut fb, &p, &tm, &[f32::INFINITY; 200], &mut sprites, cap);
+            (n, (0..5).map(|k| fb.get_pixel(column(k), 50) != bg).collect::<Vec<bool>>())
+        };
+        assert_eq!(drawn_with(None), (5, vec![true; 5]));
+        assert_eq!(drawn_with(Some(2)), (2, vec![true, true, false, false, false]));
+        assert_eq!(drawn_with(Some(0)), (0, vec![false; 5]));
+        assert_eq!(drawn_with(Some(9)).0, 5);
+    }
+
+    #[test]
+    fn equidistant_sprites_keep_one_order() {
+        let tm = TextureManager::procedural();
+        let p = Player::new(0.0, 0.0, 0.0);
+        // Two at the same distance, mirrored across the view axis, and one farther away
+        let all = [
+            ("a", 100.0, 30.0, 'o', 32.0, 0.0, Color::WHITE, 1.0),
+            ("b", 100.0, -30.0, 'o', 32.0, 0.0, Color::WHITE, 1.0),
+            ("far", 400.0, 0.0, 'o', 32.0, 0.0, Color::WHITE, 1.0),
+        ];
+        let order = |input: [usize; 3]| {
+            let mut sprites: Vec<_> = input.iter().map(|&i| all[i]).collect();
+            draw_sprites_sorted(&mut Framebuffer::new(64, 32), &p, &tm, &[f32::INFINITY; 64], &mut sprites, None);
+            sprites.iter().map(|s| s.0).collect::<Vec<_>>()
+        };
+        let first = order([0, 1, 2]);
+        assert_eq!(first[0], "far");
+        for input in [[1, 0, 2], [2, 1, 0], [0, 2, 1]] {
+            assert_eq!(order(input), first);
+        }
+    }
+
+
+    #[test]
+    fn wide_sprites_past_the_edge_are_still_drawn() {
+        let tm = TextureManager::procedural();
+        let p = Player::new(0.0, 0.0, 0.0);
+        // Near orb (42 px wide on a 200 px view) centred 10 px past the right edge
+        let a = p.fov * 0.5 + 10.0 / 200.0 * p.fov;
+        let drawn_at = |d: f32| {
+            let mut fb = Framebuffer::new(200, 100);
+            draw_sprite_world(&mut fb, &p, &tm, &[f32::INFINITY; 200], d * a.cos(), d * a.sin(), 'g', 64.0, 0.0, Color::WHITE, 1.0);
+            fb.stats.sprite_pixels
+        };
+        assert!(drawn_at(100.0) > 0);
+        // The same angle is fully off-screen for a small, far sprite
+        assert!(!sprite_in_view(a, 4.0, 200.0, p.fov));
+        assert_eq!(drawn_at(1600.0), 0);
+        assert!(sprite_in_view(a, 42.0, 200.0, p.fov));
+        assert!(!sprite_in_view(p.fov, 42.0, 200.0, p.fov));
     }
 }
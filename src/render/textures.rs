@@ -1,35 +1,52 @@
 //! Texture and pixmap management.
 use raylib::prelude::*;
 use std::collections::HashMap;
+use crate::core::assets::{AssetReport, AssetSource};
 
 #[derive(Clone)]
 struct Pixmap { w: u32, h: u32, px: Vec<Color> }
-impl Pixmap { fn new(w: u32, h: u32, px: Vec<Color>) -> Self { Self { w, h, px } } #[inline] fn sample(&self, x: u32, y: u32) -> Color { let xi = (x % self.w) as usize; let yi = (y % self.h) as usize; self.px[(yi * self.w as usize) + xi] } }
+impl Pixmap { fn new(w: u32, h: u32, px: Vec<Color>) -> Self { Self { w, h, px } } #[inline] fn sample(&self, x: u32, y: u32) -> Color { let xi = (x % self.w) as usize; let yi = (y % self.h) as usize; self.px[(yi * self.w as usize) + xi] }
+    // Bilinear sample at texel-space coords (u, v), clamped at the edges (no wrap bleeding on sprites).
+    fn sample_bilinear(&self, u: f32, v: f32) -> Color { let fx = (u - 0.5).clamp(0.0, (self.w - 1) as f32); let fy = (v - 0.5).clamp(0.0, (self.h - 1) as f32); let (x0, y0) = (fx.floor() as u32, fy.floor() as u32); let (x1, y1) = ((x0 + 1).min(self.w - 1), (y0 + 1).min(self.h - 1)); let (tx, ty) = (fx - x0 as f32, fy - y0 as f32); let (c00, c10, c01, c11) = (self.sample(x0, y0), self.sample(x1, y0), self.sample(x0, y1), self.sample(x1, y1)); let ch = |a: u8, b: u8, c: u8, d: u8| -> u8 { let top = a as f32 + (b as f32 - a as f32) * tx; let bot = c as f32 + (d as f32 - c as f32) * tx; (top + (bot - top) * ty).round().clamp(0.0, 255.0) as u8 }; Color::new(ch(c00.r, c10.r, c01.r, c11.r), ch(c00.g, c10.g, c01.g, c11.g), ch(c00.b, c10.b, c01.b, c11.b), ch(c00.a, c10.a, c01.a, c11.a)) } }
 
-pub struct TextureManager { maps: HashMap<char, Pixmap>, textures: HashMap<char, Texture2D>, alert_mode: bool }
+/// Keys that always get a texture, generated when no file was found.
+const FALLBACK_KEYS: &[char] = &['K', 'G', '+', '-', '|', '#', '1', '2', '3', '4', 'g', 'B', 'o', 'N', 'E', 'S', 'W'];
+
+pub struct TextureManager { maps: HashMap<char, Pixmap>, textures: HashMap<char, Texture2D>, alert_mode: bool, bilinear: bool, report: AssetReport }
 
 impl TextureManager {
     pub fn new(rl: &mut RaylibHandle, thread: &RaylibThread) -> Self {
-        let mut tm = Self { maps: HashMap::new(), textures: HashMap::new(), alert_mode: false };
+        let mut tm = Self::empty();
+        let mut loaded_from: HashMap<char, &str> = HashMap::new();
         let candidates: &[(&str, char)] = &[
             ("assets/wall1.png", '1'), ("wall1.png", '1'), ("./wall1.png", '1'), ("assets/walls/wall1.png", '1'),
             ("assets/wall2.png", '2'), ("wall2.png", '2'), ("./wall2.png", '2'), ("assets/walls/wall2.png", '2'),
             ("assets/wall3.png", '3'), ("wall3.png", '3'), ("./wall3.png", '3'), ("assets/walls/wall3.png", '3'),
             ("assets/wall4.png", '4'), ("wall4.png", '4'), ("./wall4.png", '4'), ("assets/walls/wall4.png", '4'),
-            ("assets/goal.png",  'g'), ("assets/orb.png",   'o'),
+            ("assets/goal.png",  'g'), ("assets/banish.png", 'B'), ("assets/orb.png",   'o'),
             ("assets/sky.png",      'K'), ("assets/skybox.png",   'K'), ("assets/ceiling.png",  'K'), ("assets/center.png",   'K'),
             ("assets/ground.png",   'G'), ("assets/floor.png",    'G'),
             ("assets/enemy_n.png", 'N'), ("assets/enemy_e.png", 'E'), ("assets/enemy_s.png", 'S'), ("assets/enemy_w.png", 'W'),
             ("assets/enemy.png", 'N'), ("assets/enemyy.png", 'N'), ("assets/enemy2.png", 'N'), ("assets/puffle.png", 'o'), ("assets/key.png", 'o'),
             ("assets/center.png", '+'), ("assets/ground.png", '#'), ("assets/iglo.png", '4'),
         ];
-        for (path, key) in candidates { if let Ok(img) = Image::load_image(path) { if let Ok(tex) = rl.load_texture_from_image(thread, &img) { tm.textures.insert(*key, tex); } let w = img.width().max(1) as u32; let h = img.height().max(1) as u32; let data = img.get_image_data().to_vec(); tm.maps.insert(*key, Pixmap::new(w, h, data)); } }
-        let fallbacks: &[char] = &['K', 'G', '+', '-', '|', '#', '1', '2', '3', '4', 'g', 'o', 'N', 'E', 'S', 'W'];
-        for &k in fallbacks { if !tm.maps.contains_key(&k) { let pm = match k { 'K' => { let w = 256; let h = 128; let mut px = vec![Color::BLACK; (w*h) as usize]; let top = Color::new(12,16,26,255); let mid = Color::new(20,28,44,255); for y in 0..h { let t = y as f32 / (h-1) as f32; let col = Self::mix(top, mid, (t*255.0) as u8); for x in 0..w { px[(y*w + x) as usize] = col; } } Pixmap::new(w as u32, h as u32, px) } 'G' => Self::make_checker_pixmap(128, 128, Color::new(48,48,52,255)), '|' | '-' | '+' => { if let Some(pm) = tm.maps.get(&'1').cloned() { pm } else { Self::make_pool_wall(64, 64, false) } }, 'g' => Self::make_checker_pixmap(64, 64, Color::new(30, 160, 30, 255)), 'o' => Self::make_glowing_orb(64, 64, Color::new(255, 240, 80, 255)), 'N' => Self::make_enemy_flat(64, 64, Color::new(255, 120, 120, 255)), 'E' => Self::make_enemy_flat(64, 64, Color::new(120, 255, 120, 255)), 'S' => Self::make_enemy_flat(64, 64, Color::new(120, 120, 255, 255)), 'W' => Self::make_enemy_flat(64, 64, Color::new(255, 180, 80, 255)), _   => { if let Some(pm) = tm.maps.get(&'1').cloned() { pm } else { Self::make_checker_pixmap(64, 64, Self::color_from_char(k)) } }, }; tm.maps.insert(k, pm); } }
+        for (path, key) in candidates { if let Ok(img) = Image::load_image(path) { if let Ok(tex) = rl.load_texture_from_image(thread, &img) { tm.textures.insert(*key, tex); } let w = img.width().max(1) as u32; let h = img.height().max(1) as u32; let data = img.get_image_data().to_vec(); tm.maps.insert(*key, Pixmap::new(w, h, data)); loaded_from.insert(*key, path); } }
+        tm.fill_fallbacks();
+        for &k in FALLBACK_KEYS { let src = match loaded_from.get(&k) { Some(p) => AssetSource::Disk(p.to_string()), None => AssetSource::Procedural }; tm.report.push(format!("texture '{}'", k), src); }
         tm
     }
+    fn empty() -> Self { Self { maps: HashMap::new(), textures: HashMap::new(), alert_mode: false, bilinear: false, report: AssetReport::new() } }
+    /// Generate a pixmap for every key nothing was loaded for.
+    fn fill_fallbacks(&mut self) {
+        for &k in FALLBACK_KEYS { if !self.maps.contains_key(&k) { let pm = match k { 'K' => { let w = 256; let h = 128; let mut px = vec![Color::BLACK; (w*h) as usize]; let top = Color::new(12,16,26,255); let mid = Color::new(20,28,44,255); for y in 0..h { let t = y as f32 / (h-1) as f32; let col = Self::mix(top, mid, (t*255.0) as u8); for x in 0..w { px[(y*w + x) as usize] = col; } } Pixmap::new(w as u32, h as u32, px) } 'G' => Self::make_checker_pixmap(128, 128, Color::new(48,48,52,255)), '|' | '-' | '+' => { if let Some(pm) = self.maps.get(&'1').cloned() { pm } else { Self::make_pool_wall(64, 64, false) } }, 'g' => Self::make_checker_pixmap(64, 64, Color::new(30, 160, 30, 255)), 'B' => Self::make_checker_pixmap(64, 64, Color::new(150, 40, 200, 255)), 'o' => Self::make_glowing_orb(64, 64, Color::new(255, 240, 80, 255)), 'N' => Self::make_enemy_flat(64, 64, Color::new(255, 120, 120, 255)), 'E' => Self::make_enemy_flat(64, 64, Color::new(120, 255, 120, 255)), 'S' => Self::make_enemy_flat(64, 64, Color::new(120, 120, 255, 255)), 'W' => Self::make_enemy_flat(64, 64, Color::new(255, 180, 80, 255)), _   => { if let Some(pm) = self.maps.get(&'1').cloned() { pm } else { Self::make_checker_pixmap(64, 64, Self::color_from_char(k)) } }, }; self.maps.insert(k, pm); } }
+    }
+    /// Only the generated textures, no window needed.
+    #[cfg(test)]
+    pub fn procedural() -> Self { let mut tm = Self::empty(); tm.fill_fallbacks(); tm }
+    /// Which texture keys came from disk and which use a generated fallback.
+    pub fn report(&self) -> &AssetReport { &self.report }
     pub fn set_alert_mode(&mut self, alert: bool) { if self.alert_mode == alert { return; } self.alert_mode = alert; let pm = Self::make_pool_wall(64, 64, alert); self.maps.insert('|', pm); }
-    fn color_from_char(c: char) -> Color { let k = c as u32; let r = ((k * 97) % 200 + 40) as u8; let g = ((k * 57) % 200 + 40) as u8; let b = ((k * 31) % 200 + 40) as u8; Color::new(r, g, b, 255) }
+    pub fn color_from_char(c: char) -> Color { let k = c as u32; let r = ((k * 97) % 200 + 40) as u8; let g = ((k * 57) % 200 + 40) as u8; let b = ((k * 31) % 200 + 40) as u8; Color::new(r, g, b, 255) }
     fn make_checker_pixmap(w: u32, h: u32, base: Color) -> Pixmap { let mut px = vec![base; (w * h) as usize]; let cell = 8u32; for y in 0..h { for x in 0..w { if ((x / cell) + (y / cell)) % 2 == 0 { let i = (y * w + x) as usize; let c = px[i]; px[i] = Self::mix(c, Color::WHITE, 24); } } } Pixmap::new(w, h, px) }
     fn make_pool_wall(w: u32, h: u32, alert: bool) -> Pixmap { let mut px = vec![Color::BLACK; (w * h) as usize]; let stripe_h = (h / 8).max(4); let bright = if alert { Color::new(255, 40, 40, 255) } else { Color::new(80, 200, 255, 255) }; let mid    = if alert { Color::new(190, 30, 30, 255) } else { Color::new(40, 140, 220, 255) }; let dim    = if alert { Color::new(120, 20, 20, 255) } else { Color::new(20, 90, 160, 255) }; let paint_stripe = |px: &mut [Color], y0: u32, h: u32, w: u32| { for y in y0..(y0 + h).min(h + y0) { let t = ((y - y0) as f32) / (h as f32 - 1.0).max(1.0); let col = if t < 0.25 { Self::mix(bright, mid, (t * 4.0 * 255.0) as u8) } else if t < 0.75 { Self::mix(mid, dim, ((t - 0.25) * (255.0 / 0.5)) as u8) } else { Self::mix(dim, Color::BLACK, ((t - 0.75) * (255.0 / 0.25)) as u8) }; for x in 0..w { let i = (y * w + x) as usize; px[i] = Self::additive(px[i], col); } } }; paint_stripe(&mut px, 0, stripe_h, w); paint_stripe(&mut px, h - stripe_h, stripe_h, w); for y in (h/2 - 4)..=(h/2 + 4) { for x in 0..w { let i = (y * w + x) as usize; px[i] = Self::mix(px[i], Color::new(20,20,20,255), 32); } } Pixmap::new(w, h, px) }
     fn make_glowing_orb(w: u32, h: u32, color: Color) -> Pixmap { let mut px = vec![Color::new(0,0,0,0); (w * h) as usize]; let cx = (w as f32) * 0.5; let cy = (h as f32) * 0.5; let r  = (w.min(h) as f32) * 0.3; for y in 0..h { for x in 0..w { let dx = x as f32 - cx; let dy = y as f32 - cy; let d  = (dx*dx + dy*dy).sqrt(); let i  = (y * w + x) as usize; if d <= r { let t = (1.0 - (d / r)).clamp(0.0, 1.0); let core = Self::mix(color, Color::WHITE, (t * 220.0) as u8); px[i] = Self::additive(px[i], core); px[i].a = 255; } else { let t = (1.0 - ((d - r) / (r*0.9))).clamp(0.0, 1.0); if t > 0.0 { let halo = Self::mix(color, Color::new(0,0,0,0), (200.0 * (1.0 - t)) as u8); px[i] = Self::additive(px[i], halo); px[i].a = (t * 180.0) as u8; } } } } Pixmap::new(w, h, px) }
@@ -37,7 +54,41 @@ impl TextureManager {
     #[inline] fn mix(a: Color, b: Color, t: u8) -> Color { let ta = t as u16; let na = 255u16 - ta; let mixc = |x: u8, y: u8| -> u8 { (((x as u16)*na + (y as u16)*ta) / 255) as u8 }; Color::new(mixc(a.r,b.r), mixc(a.g,b.g), mixc(a.b,b.b), mixc(a.a,b.a)) }
     #[inline] fn additive(a: Color, b: Color) -> Color { let add = |x: u8, y: u8| -> u8 { let s = x as u16 + y as u16; if s > 255 { 255 } else { s as u8 } }; Color::new(add(a.r,b.r), add(a.g,b.g), add(a.b,b.b), add(a.a,b.a)) }
     pub fn get_pixel_color(&self, key: char, tx: u32, ty: u32) -> Color { if let Some(pm) = self.maps.get(&key) { return pm.sample(tx, ty); } Color::WHITE }
+    // Float texel-space sample; nearest by default, bilinear when enabled (smoother scaled sprites).
+    pub fn sample_color(&self, key: char, u: f32, v: f32) -> Color { if let Some(pm) = self.maps.get(&key) { if self.bilinear { return pm.sample_bilinear(u, v); } return pm.sample(u.max(0.0) as u32, v.max(0.0) as u32); } Color::WHITE }
+    pub fn set_bilinear(&mut self, on: bool) { self.bilinear = on; }
+    pub fn is_bilinear(&self) -> bool { self.bilinear }
     pub fn image_size(&self, key: char) -> Option<(u32,u32)> { self.maps.get(&key).map(|p| (p.w, p.h)) }
     #[allow(dead_code)] pub fn texture_for(&self, key: char) -> Option<&Texture2D> { self.textures.get(&key) }
     pub fn is_alert(&self) -> bool { self.alert_mode }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two texels side by side: black | white
+    fn edge() -> Pixmap { Pixmap::new(2, 1, vec![Color::BLACK, Color::WHITE]) }
+
+    #[test]
+    fn nearest_jumps_at_the_texel_boundary() {
+        let pm = edge();
+        assert_eq!(pm.sample(0.99f32 as u32, 0), Color::BLACK);
+        assert_eq!(pm.sample(1.0f32 as u32, 0), Color::WHITE);
+    }
+
+    #[test]
+    fn bilinear_blends_across_the_texel_boundary() {
+        let pm = edge();
+        // Texel centres are exact; the boundary between them is the average
+        assert_eq!(pm.sample_bilinear(0.5, 0.5), Color::BLACK);
+        assert_eq!(pm.sample_bilinear(1.5, 0.5), Color::WHITE);
+        let mid = pm.sample_bilinear(1.0, 0.5);
+        assert_eq!((mid.r, mid.g, mid.b, mid.a), (128, 128, 128, 255));
+        let quarter = pm.sample_bilinear(0.75, 0.5);
+        assert_eq!(quarter.r, 64);
+        // Clamped at the outer edges instead of wrapping into the other side
+        assert_eq!(pm.sample_bilinear(0.0, 0.5), Color::BLACK);
+        assert_eq!(pm.sample_bilinear(2.0, 0.5), Color::WHITE);
+    }
+}